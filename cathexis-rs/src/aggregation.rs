@@ -0,0 +1,192 @@
+use crate::core::Evidence;
+use crate::numeric::Scalar;
+use serde::{Deserialize, Serialize};
+
+/// How witness contributions λ δ_ik rs_kj are combined into the indirect evidence of
+/// (i, j) during propagation, and how evidence from several sources is fused.
+///
+/// Robust modes treat the witness set as a panel of n = |N(i)| reports, where a witness
+/// with no evidence about j reports (0, 0), and return n × a robust per-witness estimate,
+/// so they agree with `Sum` when the reports agree. Bounded-influence guarantees, for an
+/// adversary controlling f of the n witnesses:
+///
+/// - `InfluenceCap { cap }`: each witness moves r + s by at most `cap`, so f colluders move
+///   the indirect evidence by at most f × cap (and never more than |N(i)| × cap overall).
+/// - `TrimmedMean { trim }`: with f ≤ ⌊trim × n⌋, each coordinate of the result lies within
+///   n × [min, max] of the honest witnesses' reports.
+/// - `MedianOfMeans { groups }`: the reports are dealt into g = min(groups, n) groups, so
+///   with f < g / 2 (a majority of groups is honest) each coordinate lies within
+///   n × [min, max] of the honest groups' means. Panels smaller than `groups` get one
+///   group per witness, i.e. the median of the reports.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Aggregation {
+    /// Plain sum (Primer Section 7); unbounded influence.
+    #[default]
+    Sum,
+    /// Each contribution is scaled down so that its r + s is at most `cap`.
+    InfluenceCap { cap: f64 },
+    /// Coordinate-wise mean after dropping the ⌊trim × n⌋ lowest and highest reports.
+    TrimmedMean { trim: f64 },
+    /// Coordinate-wise median of the means of min(`groups`, n) groups. The nonzero reports
+    /// are dealt round-robin in witness order, followed by the (0, 0) reports of the silent
+    /// witnesses, whose positions are not known.
+    MedianOfMeans { groups: usize },
+}
+
+impl Aggregation {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Aggregation::Sum => Ok(()),
+            Aggregation::InfluenceCap { cap } if !(cap > 0.0 && cap.is_finite()) => {
+                Err(format!("Influence cap must be > 0, got {}", cap))
+            }
+            Aggregation::TrimmedMean { trim } if !(0.0..0.5).contains(&trim) => {
+                Err(format!("Trim fraction must be in [0, 0.5), got {}", trim))
+            }
+            Aggregation::MedianOfMeans { groups: 0 } => {
+                Err("Median-of-means needs at least one group".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Combines the nonzero reports `contributions` (in witness order) of a panel of `n`
+    /// witnesses; the remaining n - len reports are (0, 0). An empty panel gives (0, 0)
+    /// in every mode.
+    pub fn aggregate<S: Scalar>(&self, contributions: &[(S, S)], n: usize) -> (S, S) {
+        let n = n.max(contributions.len());
+        if n == 0 {
+            return (S::zero(), S::zero());
+        }
+        let count = |k: usize| S::from_f64(k as f64);
+        match *self {
+            Aggregation::Sum => contributions.iter().fold((S::zero(), S::zero()), |acc, (r, s)| {
+                (acc.0 + r.clone(), acc.1 + s.clone())
+            }),
+            Aggregation::InfluenceCap { cap } => {
+                let cap = S::from_f64(cap);
+                contributions.iter().fold((S::zero(), S::zero()), |acc, (r, s)| {
+                    let mass = r.clone() + s.clone();
+                    let scale = if mass > cap { cap.clone() / mass } else { S::one() };
+                    (acc.0 + r.clone() * scale.clone(), acc.1 + s.clone() * scale)
+                })
+            }
+            Aggregation::TrimmedMean { trim } => {
+                let cut = (trim * n as f64).floor() as usize;
+                let coord = |f: fn(&(S, S)) -> S| {
+                    let mut xs = padded(contributions.iter().map(f), n);
+                    xs.sort_by(S::total_cmp);
+                    let kept = &xs[cut..n - cut];
+                    let sum = kept.iter().fold(S::zero(), |acc, x| acc + x.clone());
+                    count(n) * sum / count(kept.len())
+                };
+                (coord(|c| c.0.clone()), coord(|c| c.1.clone()))
+            }
+            Aggregation::MedianOfMeans { groups } => {
+                let g = groups.min(n);
+                let coord = |f: fn(&(S, S)) -> S| {
+                    let xs = padded(contributions.iter().map(f), n);
+                    let mut sums = vec![S::zero(); g];
+                    let mut counts = vec![0usize; g];
+                    for (idx, x) in xs.into_iter().enumerate() {
+                        sums[idx % g] += x;
+                        counts[idx % g] += 1;
+                    }
+                    let mut means: Vec<S> = sums.into_iter().zip(&counts).map(|(s, &c)| s / count(c)).collect();
+                    count(n) * median(&mut means)
+                };
+                (coord(|c| c.0.clone()), coord(|c| c.1.clone()))
+            }
+        }
+    }
+
+    /// Fuses evidence about the same target from several sources (cumulative fusion is the
+    /// sum in evidence space). All items must share the prior weight K.
+    pub fn fuse<S: Scalar>(&self, items: &[Evidence<S>]) -> Result<Option<Evidence<S>>, String> {
+        let Some(first) = items.first() else {
+            return Ok(None);
+        };
+        if let Some(other) = items.iter().find(|e| e.k != first.k) {
+            return Err(format!("Cannot fuse evidence with K={} and K={}", first.k, other.k));
+        }
+        let reports: Vec<(S, S)> = items.iter().map(|e| (e.r.clone(), e.s.clone())).collect();
+        let (r, s) = self.aggregate(&reports, reports.len());
+        Ok(Some(Evidence::new(r, s, first.k.clone())))
+    }
+}
+
+fn padded<S: Scalar>(values: impl Iterator<Item = S>, n: usize) -> Vec<S> {
+    let mut xs: Vec<S> = values.collect();
+    xs.resize(n, S::zero());
+    xs
+}
+
+fn median<S: Scalar>(xs: &mut [S]) -> S {
+    xs.sort_by(S::total_cmp);
+    let m = xs.len() / 2;
+    if xs.len() % 2 == 1 {
+        xs[m].clone()
+    } else {
+        (xs[m - 1].clone() + xs[m].clone()) / S::from_f64(2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 7 honest witnesses reporting about (2, 1) and 2 colluders reporting (1000, 0).
+    fn reports() -> Vec<(f64, f64)> {
+        let mut reports = vec![(2.0, 1.0), (2.1, 0.9), (1.9, 1.1), (2.0, 1.0), (2.2, 1.0), (1.8, 1.0), (2.0, 1.0)];
+        reports.insert(1, (1000.0, 0.0));
+        reports.insert(5, (1000.0, 0.0));
+        reports
+    }
+
+    #[test]
+    fn test_bounded_influence() {
+        let reports = reports();
+        let n = reports.len();
+        let (sum_r, _) = Aggregation::Sum.aggregate(&reports, n);
+        assert!(sum_r > 2000.0);
+
+        let (r, _) = Aggregation::InfluenceCap { cap: 5.0 }.aggregate(&reports, n);
+        assert!(r <= 14.0 + 2.0 * 5.0);
+
+        let honest_max = 2.2 * n as f64;
+        let honest_min = 1.8 * n as f64;
+        for mode in [Aggregation::TrimmedMean { trim: 0.25 }, Aggregation::MedianOfMeans { groups: 5 }] {
+            let (r, s) = mode.aggregate(&reports, n);
+            assert!((honest_min..=honest_max).contains(&r), "{:?}: r = {}", mode, r);
+            assert!(s <= 1.1 * n as f64);
+        }
+
+        // Silent witnesses count as (0, 0) reports: a lone loud witness is trimmed away
+        let (r, s) = Aggregation::TrimmedMean { trim: 0.2 }.aggregate(&[(1000.0, 0.0)], 5);
+        assert_eq!((r, s), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_agreeing_reports_match_sum() {
+        let reports = vec![(3.0, 1.0); 6];
+        let sum = Aggregation::Sum.aggregate(&reports, 6);
+        for mode in [
+            Aggregation::TrimmedMean { trim: 0.2 },
+            Aggregation::MedianOfMeans { groups: 3 },
+            Aggregation::InfluenceCap { cap: 10.0 },
+        ] {
+            let (r, s) = mode.aggregate(&reports, 6);
+            approx::assert_relative_eq!(r, sum.0, epsilon = 1e-12);
+            approx::assert_relative_eq!(s, sum.1, epsilon = 1e-12);
+        }
+
+        let fused = Aggregation::MedianOfMeans { groups: 3 }
+            .fuse(&[Evidence::new(3.0, 1.0, 2.0); 6])
+            .unwrap()
+            .unwrap();
+        approx::assert_relative_eq!(fused.r, 18.0, epsilon = 1e-12);
+        assert!(Aggregation::Sum.fuse(&[Evidence::new(1.0, 0.0, 2.0), Evidence::new(1.0, 0.0, 1.0)]).is_err());
+        assert!(Aggregation::TrimmedMean { trim: 0.5 }.validate().is_err());
+    }
+}
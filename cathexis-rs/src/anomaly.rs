@@ -0,0 +1,364 @@
+use crate::engine::EqbslEngine;
+use crate::events::Event;
+use crate::operators;
+use crate::state::{EqbslState, NodeId, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Streaming anomaly detection parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyConfig {
+    /// EWMA smoothing factor of the per-step baselines.
+    pub alpha: f64,
+    /// Steps observed before any alert is raised.
+    pub warmup: usize,
+    /// Per-step inbound mass on a channel below this never alerts.
+    pub min_mass: f64,
+    /// Z-score over the EWMA baseline that alerts.
+    pub z_threshold: f64,
+    /// Ratio of the step mass to the baseline mean that alerts.
+    pub rate_ratio: f64,
+    /// Share of the step mass from a single counterparty that alerts when the step is
+    /// also above baseline (mean + one standard deviation).
+    pub max_concentration: f64,
+    /// Floor of the baseline standard deviation, so quiet agents do not alert on noise.
+    pub min_std: f64,
+    /// Withhold the events behind an alert from the engine until reviewed.
+    pub quarantine: bool,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.2,
+            warmup: 3,
+            min_mass: 5.0,
+            z_threshold: 4.0,
+            rate_ratio: 5.0,
+            max_concentration: 0.8,
+            min_std: 1.0,
+            quarantine: false,
+        }
+    }
+}
+
+impl AnomalyConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.alpha > 0.0 && self.alpha <= 1.0) {
+            return Err(format!("alpha must be in (0,1], got {}", self.alpha));
+        }
+        if !(0.0..=1.0).contains(&self.max_concentration) {
+            return Err(format!("max_concentration must be in [0,1], got {}", self.max_concentration));
+        }
+        for (name, x) in [
+            ("min_mass", self.min_mass),
+            ("z_threshold", self.z_threshold),
+            ("rate_ratio", self.rate_ratio),
+            ("min_std", self.min_std),
+        ] {
+            if !(x > 0.0 && x.is_finite()) {
+                return Err(format!("{} must be > 0, got {}", name, x));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// EWMA mean and variance of the inbound mass per step on one channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub mean: f64,
+    pub var: f64,
+}
+
+impl Baseline {
+    fn update(&mut self, x: f64, alpha: f64) {
+        let diff = x - self.mean;
+        self.mean += alpha * diff;
+        self.var = (1.0 - alpha) * (self.var + alpha * diff * diff);
+    }
+}
+
+/// Which test raised an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Signal {
+    ZScore,
+    RateChange,
+    Concentration,
+}
+
+/// Anomalous inbound evidence on one channel of one agent in one step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub t: i64,
+    pub agent: NodeId,
+    pub channel: usize,
+    pub signals: Vec<Signal>,
+    /// Inbound mass on the channel in this step.
+    pub mass: f64,
+    /// (r, s) of that mass: r for farming, s for slander.
+    pub rs: (f64, f64),
+    pub baseline: Baseline,
+    pub z: f64,
+    /// Share of the mass from the largest counterparty (a source agent, or a hyperedge).
+    pub concentration: f64,
+    pub top_counterparty: NodeId,
+    /// Events carrying the mass.
+    pub events: Vec<String>,
+}
+
+/// An event withheld from the engine pending review.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantinedEvent {
+    pub event: Event,
+    /// Step time at which it was withheld.
+    pub held_at: i64,
+    pub signals: Vec<Signal>,
+}
+
+/// Result of one `AnomalyDetector::process` call.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyReport {
+    pub alerts: Vec<Alert>,
+    /// Number of events passed to the engine.
+    pub applied: usize,
+    /// Ids of the events quarantined in this step.
+    pub quarantined: Vec<String>,
+}
+
+/// Per-agent, per-channel burst detection on the event stream, in front of the engine.
+///
+/// Each step the inbound mass of every agent on every channel (a pair event counts for its
+/// destination, a hyperedge event for each participant in equal shares) is compared with
+/// an EWMA baseline of previous steps: an alert is raised when the mass is at least
+/// `min_mass` and its z-score or its ratio to the baseline mean is too high, or when most
+/// of an above-baseline step comes from one counterparty. With `quarantine` the events
+/// behind an alert are held back until `release`d or `reject`ed, and do not enter the
+/// baseline, so a flood cannot move opinions or teach the detector it is normal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    steps: usize,
+    baselines: BTreeMap<NodeId, Vec<Baseline>>,
+    quarantine: BTreeMap<String, QuarantinedEvent>,
+}
+
+/// Signals per flagged event index of a step.
+type Flagged = BTreeMap<usize, BTreeSet<Signal>>;
+
+/// Admitted inbound mass of a step per (agent, channel).
+type Observed = BTreeMap<(NodeId, usize), f64>;
+
+struct Inbound<'a> {
+    mass: f64,
+    by_counterparty: BTreeMap<&'a str, f64>,
+    /// Mass per event index.
+    events: BTreeMap<usize, f64>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Result<Self, String> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            steps: 0,
+            baselines: BTreeMap::new(),
+            quarantine: BTreeMap::new(),
+        })
+    }
+
+    pub fn config(&self) -> &AnomalyConfig {
+        &self.config
+    }
+
+    /// Baseline of `agent` on `channel`, once the agent has received evidence.
+    pub fn baseline(&self, agent: &str, channel: usize) -> Option<&Baseline> {
+        self.baselines.get(agent)?.get(channel)
+    }
+
+    /// Events awaiting review, by event id.
+    pub fn quarantined(&self) -> &BTreeMap<String, QuarantinedEvent> {
+        &self.quarantine
+    }
+
+    /// Scores the step (t, events), then steps `engine` with every event not quarantined.
+    /// Event ids must be unique within the step and not already in quarantine. The
+    /// baselines and the quarantine only change once the engine has accepted the step.
+    pub fn process(&mut self, engine: &mut EqbslEngine, t: i64, events: Vec<Event>) -> Result<AnomalyReport, String> {
+        operators::validate_events(engine.state(), engine.params(), t, &events)?;
+        let mut ids = BTreeSet::new();
+        for event in &events {
+            if !ids.insert(event.id()) {
+                return Err(format!("Duplicate event id '{}' in step t={}", event.id(), t));
+            }
+            if self.quarantine.contains_key(event.id()) {
+                return Err(format!("Event '{}' is already quarantined", event.id()));
+            }
+        }
+        let (alerts, flagged, observed) = self.observe(engine.state(), engine.params(), t, &events);
+        let mut report = AnomalyReport {
+            alerts,
+            ..Default::default()
+        };
+        let mut admitted = Vec::new();
+        let mut held = Vec::new();
+        for (n, event) in events.into_iter().enumerate() {
+            match flagged.get(&n) {
+                Some(signals) if self.config.quarantine => {
+                    report.quarantined.push(event.id().to_string());
+                    held.push(QuarantinedEvent {
+                        event,
+                        held_at: t,
+                        signals: signals.iter().copied().collect(),
+                    });
+                }
+                _ => admitted.push(event),
+            }
+        }
+        report.applied = admitted.len();
+        engine.step(t, admitted)?;
+
+        self.learn(observed, engine.params().channels());
+        for event in held {
+            self.quarantine.insert(event.event.id().to_string(), event);
+        }
+        Ok(report)
+    }
+
+    /// Applies a quarantined event after review, in a step at the engine's current time
+    /// (decayed by its lateness, as with `LatePolicy::ApplyAtNow`).
+    pub fn release(&mut self, engine: &mut EqbslEngine, event_id: &str) -> Result<(), String> {
+        let held = self
+            .quarantine
+            .remove(event_id)
+            .ok_or_else(|| format!("Event '{}' is not quarantined", event_id))?;
+        if let Err(err) = engine.step(engine.state().t, vec![held.event.clone()]) {
+            self.quarantine.insert(event_id.to_string(), held);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Discards a quarantined event after review and returns it.
+    pub fn reject(&mut self, event_id: &str) -> Result<Event, String> {
+        self.quarantine
+            .remove(event_id)
+            .map(|held| held.event)
+            .ok_or_else(|| format!("Event '{}' is not quarantined", event_id))
+    }
+
+    /// Scores one step against the baselines. Returns the alerts, the signals of each
+    /// flagged event index, and the admitted inbound mass per (agent, channel) for `learn`.
+    fn observe(&self, state: &EqbslState, params: &Params, t: i64, events: &[Event]) -> (Vec<Alert>, Flagged, Observed) {
+        let m = params.channels();
+        let mut inbound: BTreeMap<(&NodeId, usize), Inbound> = BTreeMap::new();
+        for (n, event) in events.iter().enumerate() {
+            let masses = event.de().channel_masses();
+            let (targets, counterparty, share): (Vec<&NodeId>, &str, f64) = match event {
+                Event::Pair(e) => (vec![&e.dst], &e.src, 1.0),
+                Event::Hyper(e) => {
+                    let nodes = if e.nodes.is_empty() {
+                        state.hyperedges.get(&e.hid).map(|h| &h.nodes[..]).unwrap_or(&[])
+                    } else {
+                        &e.nodes[..]
+                    };
+                    (nodes.iter().collect(), &e.hid, 1.0 / nodes.len().max(1) as f64)
+                }
+            };
+            for agent in targets {
+                for (c, &x) in masses.iter().enumerate().take(m) {
+                    if x <= 0.0 {
+                        continue;
+                    }
+                    let entry = inbound.entry((agent, c)).or_insert_with(|| Inbound {
+                        mass: 0.0,
+                        by_counterparty: BTreeMap::new(),
+                        events: BTreeMap::new(),
+                    });
+                    entry.mass += x * share;
+                    *entry.by_counterparty.entry(counterparty).or_insert(0.0) += x * share;
+                    *entry.events.entry(n).or_insert(0.0) += x * share;
+                }
+            }
+        }
+
+        let armed = self.steps >= self.config.warmup;
+        let mut alerts = Vec::new();
+        let mut flagged = Flagged::new();
+        for (&(agent, c), step) in &inbound {
+            let baseline = self.baseline(agent, c).copied().unwrap_or_default();
+            let std = baseline.var.sqrt().max(self.config.min_std);
+            let z = (step.mass - baseline.mean) / std;
+            let (top_counterparty, top) = step
+                .by_counterparty
+                .iter()
+                .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(k, v)| (k.to_string(), *v))
+                .unwrap_or_default();
+            let concentration = top / step.mass;
+            let mut signals = Vec::new();
+            if armed && step.mass >= self.config.min_mass {
+                if z >= self.config.z_threshold {
+                    signals.push(Signal::ZScore);
+                }
+                if step.mass >= self.config.rate_ratio * baseline.mean {
+                    signals.push(Signal::RateChange);
+                }
+                if concentration >= self.config.max_concentration && step.mass > baseline.mean + std {
+                    signals.push(Signal::Concentration);
+                }
+            }
+            if signals.is_empty() {
+                continue;
+            }
+            for &n in step.events.keys() {
+                flagged.entry(n).or_default().extend(&signals);
+            }
+            alerts.push(Alert {
+                t,
+                agent: agent.clone(),
+                channel: c,
+                signals,
+                mass: step.mass,
+                rs: (params.w_pos[c] * step.mass, params.w_neg[c] * step.mass),
+                baseline,
+                z,
+                concentration,
+                top_counterparty,
+                events: step.events.keys().map(|&n| events[n].id().to_string()).collect(),
+            });
+        }
+
+        // Quarantined mass stays out of the baselines
+        let admitted = |step: &Inbound| -> f64 {
+            step.events
+                .iter()
+                .filter(|(n, _)| !(self.config.quarantine && flagged.contains_key(n)))
+                .map(|(_, x)| x)
+                .sum()
+        };
+        let observed = inbound
+            .iter()
+            .map(|(&(agent, c), step)| ((agent.clone(), c), admitted(step)))
+            .filter(|(_, x)| *x > 0.0)
+            .collect();
+        (alerts, flagged, observed)
+    }
+
+    /// Folds a step's admitted mass into the baselines; every tracked baseline sees the step.
+    fn learn(&mut self, observed: Observed, m: usize) {
+        for (agent, _) in observed.keys() {
+            if !self.baselines.contains_key(agent) {
+                self.baselines.insert(agent.clone(), vec![Baseline::default(); m]);
+            }
+        }
+        let alpha = self.config.alpha;
+        for (agent, channels) in self.baselines.iter_mut() {
+            for (c, baseline) in channels.iter_mut().enumerate() {
+                baseline.update(observed.get(&(agent.clone(), c)).copied().unwrap_or(0.0), alpha);
+            }
+        }
+        self.steps += 1;
+    }
+}
@@ -0,0 +1,251 @@
+use crate::canonical::{Canonical, Digest};
+use crate::pipeline::AgentHandleResponse;
+use crate::state::NodeId;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The signed content of a trust handle: the agent's category and label, the categoriser's
+/// probabilities, the root of Com(E_t) it was computed from, and its validity window
+/// (unix seconds, `expires_at` exclusive).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandleClaim {
+    pub agent: NodeId,
+    pub category_id: usize,
+    pub label: String,
+    pub probabilities: Vec<f64>,
+    pub state_root: Digest,
+    pub issued_at: i64,
+    pub expires_at: i64,
+}
+
+impl HandleClaim {
+    pub fn new(
+        agent: &str,
+        response: &AgentHandleResponse,
+        state_root: Digest,
+        issued_at: i64,
+        expires_at: i64,
+    ) -> Result<Self, String> {
+        if expires_at <= issued_at {
+            return Err(format!("Handle expires at {} before it is issued at {}", expires_at, issued_at));
+        }
+        Ok(Self {
+            agent: agent.to_string(),
+            category_id: response.category_id,
+            label: response.label.clone(),
+            probabilities: response.probabilities.clone(),
+            state_root,
+            issued_at,
+            expires_at,
+        })
+    }
+
+    pub fn is_valid_at(&self, now: i64) -> bool {
+        self.issued_at <= now && now < self.expires_at
+    }
+}
+
+impl Canonical for HandleClaim {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (&self.agent, self.category_id, &self.label).encode(out);
+        (&self.probabilities, self.state_root).encode(out);
+        (self.issued_at, self.expires_at).encode(out);
+    }
+}
+
+/// A handle claim with an Ed25519 signature by the key `key_id`, for third parties to verify
+/// offline against a `KeyRing`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedHandle {
+    pub claim: HandleClaim,
+    pub key_id: String,
+    pub signature: Signature,
+}
+
+impl SignedHandle {
+    /// Checks that `key_id` is in `keys` and was active when the claim was issued, that the
+    /// signature is valid, and that the claim is valid at `now`.
+    pub fn verify(&self, keys: &KeyRing, now: i64) -> Result<(), String> {
+        keys.verify_signature(
+            &self.key_id,
+            self.claim.issued_at,
+            &message(&self.key_id, &self.claim),
+            &self.signature,
+        )
+        .map_err(|e| format!("{} on the handle of '{}'", e, self.claim.agent))?;
+        if !self.claim.is_valid_at(now) {
+            return Err(format!(
+                "Handle of '{}' is valid from {} to {}, not at {}",
+                self.claim.agent, self.claim.issued_at, self.claim.expires_at, now
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The bytes signed for a claim: domain, key ID and claim, canonically encoded.
+fn message(key_id: &str, claim: &HandleClaim) -> Vec<u8> {
+    ("eqbsl/handle/v1", key_id, claim).to_canonical_bytes()
+}
+
+/// The issuer's signing key under its key ID. Holds the secret key, so it is neither
+/// serialised nor printed.
+#[derive(Clone)]
+pub struct HandleSigner {
+    key_id: String,
+    key: SigningKey,
+}
+
+impl HandleSigner {
+    pub fn new(key_id: &str, key: SigningKey) -> Self {
+        Self {
+            key_id: key_id.to_string(),
+            key,
+        }
+    }
+
+    /// A signer with a fresh random key.
+    pub fn generate(key_id: &str) -> Self {
+        Self::new(key_id, SigningKey::generate(&mut rand::thread_rng()))
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.key.verifying_key()
+    }
+
+    pub fn sign(&self, claim: HandleClaim) -> SignedHandle {
+        let signature = self.sign_bytes(&message(&self.key_id, &claim));
+        SignedHandle {
+            claim,
+            key_id: self.key_id.clone(),
+            signature,
+        }
+    }
+
+    /// Signs other issuer statements, e.g. `threshold::IssuedCommitment`; `message` must
+    /// start with its own domain.
+    pub(crate) fn sign_bytes(&self, message: &[u8]) -> Signature {
+        self.key.sign(message)
+    }
+}
+
+impl std::fmt::Debug for HandleSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandleSigner")
+            .field("key_id", &self.key_id)
+            .field("verifying_key", &self.verifying_key())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A public key and the period in which it may issue handles (unix seconds, `not_after`
+/// exclusive).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedKey {
+    pub key: VerifyingKey,
+    pub not_before: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<i64>,
+}
+
+impl PublishedKey {
+    pub fn is_active_at(&self, t: i64) -> bool {
+        self.not_before <= t && self.not_after.is_none_or(|end| t < end)
+    }
+}
+
+/// The issuer's public keys by key ID, published for offline verification. Rotating keeps
+/// the old key, retired, so handles it signed stay valid until they expire; revoking
+/// removes it, invalidating them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRing {
+    keys: BTreeMap<String, PublishedKey>,
+}
+
+impl KeyRing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key_id: &str) -> Option<&PublishedKey> {
+        self.keys.get(key_id)
+    }
+
+    pub fn key_ids(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    /// Adds `key` as `key_id`, active from `not_before`. Key IDs must be unique.
+    pub fn add(&mut self, key_id: &str, key: VerifyingKey, not_before: i64) -> Result<(), String> {
+        if self.keys.contains_key(key_id) {
+            return Err(format!("Key ID '{}' is already in use", key_id));
+        }
+        self.keys.insert(
+            key_id.to_string(),
+            PublishedKey {
+                key,
+                not_before,
+                not_after: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stops `key_id` from issuing handles from `at` on.
+    pub fn retire(&mut self, key_id: &str, at: i64) -> Result<(), String> {
+        let key = self
+            .keys
+            .get_mut(key_id)
+            .ok_or_else(|| format!("Unknown signing key '{}'", key_id))?;
+        if at < key.not_before {
+            return Err(format!("Key '{}' cannot retire at {} before it became active", key_id, at));
+        }
+        key.not_after = Some(key.not_after.map_or(at, |end| end.min(at)));
+        Ok(())
+    }
+
+    /// Replaces `old` by `new_id` at time `at`: `new_id` is active from `at`, `old` retired.
+    pub fn rotate(&mut self, old: &str, new_id: &str, key: VerifyingKey, at: i64) -> Result<(), String> {
+        let previous = self
+            .keys
+            .get(old)
+            .ok_or_else(|| format!("Unknown signing key '{}'", old))?;
+        if at < previous.not_before {
+            return Err(format!("Key '{}' cannot retire at {} before it became active", old, at));
+        }
+        self.add(new_id, key, at)?;
+        self.retire(old, at)
+    }
+
+    /// Checks that `key_id` is in the ring, was active at `issued_at` and made `signature`
+    /// on `message`.
+    pub(crate) fn verify_signature(
+        &self,
+        key_id: &str,
+        issued_at: i64,
+        message: &[u8],
+        signature: &Signature,
+    ) -> Result<(), String> {
+        let key = self
+            .get(key_id)
+            .ok_or_else(|| format!("Unknown or revoked signing key '{}'", key_id))?;
+        if !key.is_active_at(issued_at) {
+            return Err(format!("Key '{}' was not active at issue time {}", key_id, issued_at));
+        }
+        key.key
+            .verify_strict(message, signature)
+            .map_err(|_| format!("Invalid signature by key '{}'", key_id))
+    }
+
+    /// Removes a compromised key: every handle it signed fails verification.
+    pub fn revoke(&mut self, key_id: &str) -> Result<PublishedKey, String> {
+        self.keys
+            .remove(key_id)
+            .ok_or_else(|| format!("Unknown signing key '{}'", key_id))
+    }
+}
@@ -0,0 +1,135 @@
+use crate::canonical::Digest;
+use crate::merkle::{StateCommitment, EMPTY};
+use crate::operators::Operator;
+use serde::{Deserialize, Serialize};
+
+/// One state transition in the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, from 0.
+    pub seq: u64,
+    /// State time after the transition.
+    pub t: i64,
+    /// The operators that changed the state, in order.
+    pub operators: Vec<Operator>,
+    /// `Params::digest` of θ.
+    pub params: Digest,
+    /// Commitment to the inputs: `proof::events_commitment` of the ingested or amended
+    /// events, or the digest of a dispute action.
+    pub events: Digest,
+    /// Com(E) root before and after the transition.
+    pub pre: Digest,
+    pub post: Digest,
+    /// `hash` of the previous entry, `EMPTY` for the first.
+    pub prev: Digest,
+    /// Digest of all the fields above.
+    pub hash: Digest,
+}
+
+impl AuditEntry {
+    pub fn compute_hash(&self) -> Digest {
+        Digest::of(
+            "eqbsl/audit/entry/v1",
+            &(
+                (self.seq, self.t, &self.operators),
+                (self.params, self.events),
+                (self.pre, self.post, self.prev),
+            ),
+        )
+    }
+}
+
+/// Append-only, hash-chained log of every state transition: each entry commits to its
+/// predecessor, so altering, dropping or reordering entries breaks the chain. Publishing
+/// `head` from time to time also makes truncation evident.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditLog {
+    /// Com(E) root of the state the log starts from.
+    genesis: Digest,
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn new(genesis: &StateCommitment) -> Self {
+        Self {
+            genesis: genesis.root,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn genesis(&self) -> Digest {
+        self.genesis
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Hash of the last entry, `EMPTY` if there is none.
+    pub fn head(&self) -> Digest {
+        self.entries.last().map_or(EMPTY, |e| e.hash)
+    }
+
+    /// Com(E) root of the state after the last entry.
+    pub fn latest_root(&self) -> Digest {
+        self.entries.last().map_or(self.genesis, |e| e.post)
+    }
+
+    /// Records a transition to `post` made by `operators` on inputs committed to by `events`.
+    pub fn append(&mut self, operators: Vec<Operator>, events: Digest, post: &StateCommitment) -> &AuditEntry {
+        let mut entry = AuditEntry {
+            seq: self.entries.len() as u64,
+            t: post.t,
+            operators,
+            params: post.params,
+            events,
+            pre: self.latest_root(),
+            post: post.root,
+            prev: self.head(),
+            hash: EMPTY,
+        };
+        entry.hash = entry.compute_hash();
+        self.entries.push(entry);
+        self.entries.last().expect("entry pushed above")
+    }
+
+    /// Walks the chain; see `verify_chain`.
+    pub fn verify(&self) -> Result<Digest, String> {
+        verify_chain(&self.genesis, &self.entries)
+    }
+}
+
+/// Checks that `entries` form an unbroken chain from the state with root `genesis`: sequence
+/// numbers without gaps, every hash recomputed and linked to its predecessor, each transition
+/// starting where the last one ended, and time never going back. Returns the head hash, to
+/// compare with a published one.
+pub fn verify_chain(genesis: &Digest, entries: &[AuditEntry]) -> Result<Digest, String> {
+    let (mut prev, mut root, mut t) = (EMPTY, *genesis, i64::MIN);
+    for (n, entry) in entries.iter().enumerate() {
+        if entry.seq != n as u64 {
+            return Err(format!("Gap in the audit log: expected entry {}, found {}", n, entry.seq));
+        }
+        if entry.hash != entry.compute_hash() {
+            return Err(format!("Audit entry {} has been altered", n));
+        }
+        if entry.prev != prev {
+            return Err(format!("Audit entry {} does not link to its predecessor", n));
+        }
+        if entry.pre != root {
+            return Err(format!("State changed outside the audit log before entry {}", n));
+        }
+        if entry.t < t {
+            return Err(format!("Audit entry {} goes back from t={} to t={}", n, t, entry.t));
+        }
+        (prev, root, t) = (entry.hash, entry.post, entry.t);
+    }
+    Ok(prev)
+}
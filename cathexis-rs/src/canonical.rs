@@ -0,0 +1,341 @@
+use crate::aggregation::Aggregation;
+use crate::dispute::{Dispute, DisputeAction, DisputeStatus};
+use crate::eqbsl::EvidenceTensor;
+use crate::events::{Event, StepRecord};
+use crate::numeric::Scalar;
+use crate::operators::Operator;
+use crate::provenance::{Contribution, EdgeProvenance, Provenance};
+use crate::state::{EqbslState, Hyperedge, Params};
+use crate::witness::WitnessPolicyConfig;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Byte encoding that is identical on every platform and for every run:
+/// integers and f64 bit patterns are big-endian, strings and sequences are
+/// length-prefixed, and maps are written in key order.
+pub trait Canonical {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+}
+
+impl Canonical for u64 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl Canonical for i64 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl Canonical for usize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out);
+    }
+}
+
+impl Canonical for f64 {
+    /// Raw IEEE-754 bits, with -0.0 folded into +0.0 so equal values encode equally.
+    fn encode(&self, out: &mut Vec<u8>) {
+        let x = if *self == 0.0 { 0.0f64 } else { *self };
+        x.to_bits().encode(out);
+    }
+}
+
+impl Canonical for str {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Canonical for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_str().encode(out);
+    }
+}
+
+impl<T: Canonical> Canonical for [T] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        for x in self {
+            x.encode(out);
+        }
+    }
+}
+
+impl<T: Canonical> Canonical for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode(out);
+    }
+}
+
+impl<A: Canonical, B: Canonical> Canonical for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+}
+
+impl<A: Canonical, B: Canonical, C: Canonical> Canonical for (A, B, C) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+        self.2.encode(out);
+    }
+}
+
+impl<T: Canonical + ?Sized> Canonical for &T {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (**self).encode(out);
+    }
+}
+
+impl<T: Canonical> Canonical for Option<T> {
+    /// A 0 byte for `None`, a 1 byte and the value for `Some`.
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            None => out.push(0),
+            Some(x) => {
+                out.push(1);
+                x.encode(out);
+            }
+        }
+    }
+}
+
+impl<K: Canonical, V: Canonical> Canonical for BTreeMap<K, V> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        for (k, v) in self {
+            k.encode(out);
+            v.encode(out);
+        }
+    }
+}
+
+impl<S: Scalar> Canonical for EvidenceTensor<S> {
+    /// Shape, then elements in logical (row-major) order regardless of memory layout.
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.shape().encode(out);
+        for x in self.data.iter() {
+            x.encode(out);
+        }
+    }
+}
+
+impl<S: Scalar> Canonical for Hyperedge<S> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.id.encode(out);
+        self.nodes.encode(out);
+        self.roles.len().encode(out);
+        for (node, role) in &self.roles {
+            node.encode(out);
+            role.encode(out);
+        }
+        self.e.encode(out);
+    }
+}
+
+impl<S: Scalar> Canonical for EqbslState<S> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.t.encode(out);
+        self.edges.len().encode(out);
+        for ((src, dst), e) in &self.edges {
+            src.encode(out);
+            dst.encode(out);
+            e.encode(out);
+        }
+        self.hyperedges.len().encode(out);
+        for h in self.hyperedges.values() {
+            h.encode(out);
+        }
+        self.provenance.encode(out);
+    }
+}
+
+impl Canonical for Contribution {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (&self.event_id, self.t, &self.via).encode(out);
+        (&self.mass, &self.withheld).encode(out);
+    }
+}
+
+impl Canonical for EdgeProvenance {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (&self.contributions, &self.compacted, self.compacted_events).encode(out);
+    }
+}
+
+impl Canonical for Dispute {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let status = match self.status {
+            DisputeStatus::Pending => "pending",
+            DisputeStatus::Confirmed => "confirmed",
+            DisputeStatus::Reversed => "reversed",
+        };
+        (status, self.weight, self.opened_at).encode(out);
+        (self.resolved_at, &self.edges).encode(out);
+    }
+}
+
+impl Canonical for Provenance {
+    /// The compaction bounds, every edge's contributions and the dispute records.
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.config.max_events_per_edge, self.config.min_mass).encode(out);
+        (&self.edges, &self.disputes).encode(out);
+    }
+}
+
+impl Canonical for Aggregation {
+    /// Enum variants are written as their snake_case names, then their fields.
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Aggregation::Sum => "sum".encode(out),
+            Aggregation::InfluenceCap { cap } => ("influence_cap", cap).encode(out),
+            Aggregation::TrimmedMean { trim } => ("trimmed_mean", trim).encode(out),
+            Aggregation::MedianOfMeans { groups } => ("median_of_means", groups).encode(out),
+        }
+    }
+}
+
+impl Canonical for WitnessPolicyConfig {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            WitnessPolicyConfig::TopK(p) => ("top_k", p.k).encode(out),
+            WitnessPolicyConfig::MinCertainty(p) => ("min_certainty", p.min_certainty).encode(out),
+            WitnessPolicyConfig::SameContext(p) => ("same_context", &p.contexts).encode(out),
+            WitnessPolicyConfig::SeededSample(p) => ("seeded_sample", p.seed, p.size).encode(out),
+            WitnessPolicyConfig::ExcludeClusters(p) => ("exclude_clusters", &p.clusters).encode(out),
+            WitnessPolicyConfig::Custom(p) => ("custom", &p.name).encode(out),
+        }
+    }
+}
+
+impl Canonical for Params {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.k.encode(out);
+        self.w_pos.encode(out);
+        self.w_neg.encode(out);
+        self.decay_beta.encode(out);
+        self.damping_lambda.encode(out);
+        self.witness_top_k.encode(out);
+        self.propagation_depth.encode(out);
+        self.base_rate.encode(out);
+        self.witness_policy.encode(out);
+        self.aggregation.encode(out);
+    }
+}
+
+impl Canonical for Event {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Event::Pair(e) => {
+                ("pair", e.id.as_str(), e.t).encode(out);
+                (&e.src, &e.dst, &e.de).encode(out);
+            }
+            Event::Hyper(e) => {
+                ("hyper", e.id.as_str(), e.t).encode(out);
+                (&e.hid, &e.nodes, &e.roles).encode(out);
+                e.de.encode(out);
+            }
+        }
+    }
+}
+
+impl Canonical for DisputeAction {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            DisputeAction::Open { event_id, weight } => ("open", event_id, weight).encode(out),
+            DisputeAction::Confirm { event_id } => ("confirm", event_id).encode(out),
+            DisputeAction::Reverse { event_id } => ("reverse", event_id).encode(out),
+        }
+    }
+}
+
+impl Canonical for Operator {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Operator::Decay => "decay",
+            Operator::Ingest => "ingest",
+            Operator::Attribute => "attribute",
+            Operator::Propagate => "propagate",
+            Operator::Lift => "lift",
+            Operator::Embed => "embed",
+            Operator::Dispute => "dispute",
+            Operator::Replay => "replay",
+        }
+        .encode(out)
+    }
+}
+
+impl Canonical for StepRecord {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.t, &self.events, &self.disputes).encode(out);
+    }
+}
+
+/// SHA-256 digest of a canonical encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Digest(pub [u8; 32]);
+
+impl Digest {
+    /// Hashes `value` under a domain tag, so digests of different kinds of object never collide.
+    pub fn of<T: Canonical + ?Sized>(domain: &str, value: &T) -> Self {
+        let mut bytes = Vec::new();
+        domain.encode(&mut bytes);
+        value.encode(&mut bytes);
+        Self(Sha256::digest(&bytes).into())
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Canonical for Digest {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.0);
+    }
+}
+
+impl<S: Scalar> EqbslState<S> {
+    /// Digest of the full state (t, edges, hyperedges and provenance, dispute records
+    /// included), for comparing replays with live state. States of a number type other than
+    /// f64 hash under their own domain.
+    pub fn digest(&self) -> Digest {
+        match S::label().as_str() {
+            "f64" => Digest::of("eqbsl/state/v2", self),
+            label => Digest::of(&format!("eqbsl/state/v2/{}", label), self),
+        }
+    }
+}
+
+impl Params {
+    /// Digest of the parameters θ, committing to the operators a state evolved under.
+    pub fn digest(&self) -> Digest {
+        Digest::of("eqbsl/params/v1", self)
+    }
+}
+
+impl StepRecord {
+    /// Digest of one logged step: its time, events and dispute actions.
+    pub fn digest(&self) -> Digest {
+        Digest::of("eqbsl/step/v1", self)
+    }
+}
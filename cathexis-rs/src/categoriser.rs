@@ -1,10 +1,12 @@
 use crate::features::FeatureState;
+use crate::parallel;
 use ndarray::{Array1, Array2};
 use serde::{Deserialize, Serialize};
 
 /// Trait for a Categoriser Network (Section 3, Equation 14).
 /// f_theta: R^d -> Delta^{K-1}
-pub trait Categoriser {
+/// `Sync` so that batches can be categorised in parallel (`parallel` feature).
+pub trait Categoriser: Sync {
     /// Maps a feature state to a probability distribution over K categories.
     fn forward(&self, features: &FeatureState) -> Result<Array1<f64>, String>;
 
@@ -18,6 +20,12 @@ pub trait Categoriser {
             .map(|(index, _)| index)
             .ok_or_else(|| "Empty probability vector".to_string())
     }
+
+    /// Hard category assignments for a batch, in input order. On failure the error of the
+    /// first failing feature state is returned.
+    fn predict_batch(&self, features: &[FeatureState]) -> Result<Vec<usize>, String> {
+        parallel::map(features, |f| self.predict(f)).into_iter().collect()
+    }
 }
 
 /// A simple MLP baseline categoriser (Section 4, Equation 20).
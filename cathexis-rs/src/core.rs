@@ -1,58 +1,73 @@
+use crate::numeric::Scalar;
 use serde::{Deserialize, Serialize};
 
 /// Represents an opinion in Subjective Logic (Section 2.1).
 /// $\omega_X^A = (b, d, u, a)$ where $b + d + u = 1$.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub struct Opinion {
+pub struct Opinion<S = f64> {
     /// Belief mass
-    pub b: f64,
+    pub b: S,
     /// Disbelief mass
-    pub d: f64,
+    pub d: S,
     /// Uncertainty mass
-    pub u: f64,
+    pub u: S,
     /// Base rate (prior probability)
-    pub a: f64,
+    pub a: S,
 }
 
-impl Opinion {
-    /// Creates a new opinion, ensuring the constraint b + d + u = 1 (approx).
-    pub fn new(b: f64, d: f64, u: f64, a: f64) -> Result<Self, String> {
-        if (b + d + u - 1.0).abs() > 1e-6 {
-            return Err(format!("Invalid opinion: b+d+u must be 1, got {}", b + d + u));
+impl<S: Scalar> Opinion<S> {
+    /// Creates a new opinion, ensuring b, d, u ∈ [0, 1] and b + d + u = 1 (approx).
+    pub fn new(b: S, d: S, u: S, a: S) -> Result<Self, String> {
+        let opinion = Self { b, d, u, a };
+        opinion.check(1e-6)?;
+        Ok(opinion)
+    }
+
+    /// Checks the opinion invariants (Primer Section 9, items 2-3) within `tolerance`.
+    pub fn check(&self, tolerance: f64) -> Result<(), String> {
+        for (name, x) in [("b", &self.b), ("d", &self.d), ("u", &self.u), ("a", &self.a)] {
+            let x = x.to_f64();
+            if !(x >= -tolerance && x <= 1.0 + tolerance) {
+                return Err(format!("Invalid opinion: {} must be in [0,1], got {}", name, x));
+            }
+        }
+        let sum = (self.b.clone() + self.d.clone() + self.u.clone()).to_f64();
+        if (sum - 1.0).abs() > tolerance {
+            return Err(format!("Invalid opinion: b+d+u must be 1, got {}", sum));
         }
-        Ok(Self { b, d, u, a })
+        Ok(())
     }
 
     /// Vacuous opinion (complete uncertainty).
-    pub fn vacuous(a: f64) -> Self {
+    pub fn vacuous(a: S) -> Self {
         Self {
-            b: 0.0,
-            d: 0.0,
-            u: 1.0,
+            b: S::zero(),
+            d: S::zero(),
+            u: S::one(),
             a,
         }
     }
     
     /// Expected probability E = b + a * u
-    pub fn expected_probability(&self) -> f64 {
-        self.b + self.a * self.u
+    pub fn expected_probability(&self) -> S {
+        self.b.clone() + self.a.clone() * self.u.clone()
     }
 }
 
 /// Represents evidence counts for Evidence-Based Subjective Logic (EBSL) (Section 2.2).
 /// r = positive evidence, s = negative evidence.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub struct Evidence {
+pub struct Evidence<S = f64> {
     /// Positive evidence count
-    pub r: f64,
+    pub r: S,
     /// Negative evidence count
-    pub s: f64,
+    pub s: S,
     /// Normalisation constant K > 0 (usually 2.0 for binary, or W for weighted)
-    pub k: f64,
+    pub k: S,
 }
 
-impl Evidence {
-    pub fn new(r: f64, s: f64, k: f64) -> Self {
+impl<S: Scalar> Evidence<S> {
+    pub fn new(r: S, s: S, k: S) -> Self {
         Self { r, s, k }
     }
 
@@ -60,23 +75,23 @@ impl Evidence {
     /// b = r / (r + s + K)
     /// d = s / (r + s + K)
     /// u = K / (r + s + K)
-    pub fn to_opinion(&self, base_rate: f64) -> Opinion {
-        let sum = self.r + self.s + self.k;
-        let b = self.r / sum;
-        let d = self.s / sum;
-        let u = self.k / sum;
+    pub fn to_opinion(&self, base_rate: S) -> Opinion<S> {
+        let sum = self.r.clone() + self.s.clone() + self.k.clone();
+        let b = self.r.clone() / sum.clone();
+        let d = self.s.clone() / sum.clone();
+        let u = self.k.clone() / sum;
         
         Opinion { b, d, u, a: base_rate }
     }
     
     /// Combine with another evidence (additive property).
-    pub fn combine(&self, other: &Evidence) -> Evidence {
+    pub fn combine(&self, other: &Evidence<S>) -> Evidence<S> {
         // Assuming K should match, or we take self's K. 
         // In EBSL, evidence is additive: (r, s) + (r', s') = (r+r', s+s')
         Evidence {
-            r: self.r + other.r,
-            s: self.s + other.s,
-            k: self.k,
+            r: self.r.clone() + other.r.clone(),
+            s: self.s.clone() + other.s.clone(),
+            k: self.k.clone(),
         }
     }
 }
@@ -0,0 +1,177 @@
+use crate::eqbsl::EvidenceTensor;
+use crate::state::{EdgeKey, EqbslState, Params};
+use ndarray::Axis;
+use serde::{Deserialize, Serialize};
+
+/// A step in the dispute workflow of one negative-evidence event. Recorded in the event
+/// log alongside the events, so replays reproduce it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DisputeAction {
+    /// Holds the event's evidence at `weight` × its mass until resolved.
+    Open { event_id: String, weight: f64 },
+    /// Upholds the evidence: the withheld mass is restored.
+    Confirm { event_id: String },
+    /// Strikes the evidence: what remains of it is removed from the evidence field.
+    Reverse { event_id: String },
+}
+
+impl DisputeAction {
+    pub fn event_id(&self) -> &str {
+        match self {
+            DisputeAction::Open { event_id, .. }
+            | DisputeAction::Confirm { event_id }
+            | DisputeAction::Reverse { event_id } => event_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    Pending,
+    Confirmed,
+    Reversed,
+}
+
+/// Dispute record of one event, kept in the state's provenance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dispute {
+    pub status: DisputeStatus,
+    /// Weight the evidence was held at while pending.
+    pub weight: f64,
+    pub opened_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<i64>,
+    /// Edges the event contributed to.
+    pub edges: Vec<EdgeKey>,
+}
+
+/// Applies one dispute action at the state's current time and returns the edges whose
+/// evidence changed. Requires provenance, which locates the event's (decayed) mass on
+/// every edge; on error the state is unchanged.
+///
+/// The held-back mass of a pending dispute decays with the channel's β like any other
+/// evidence, so confirming restores exactly what the evidence would be worth had it never
+/// been disputed, and reversing removes exactly what is left of it; decay, propagation and
+/// the derived caches see the corrected edges from then on.
+pub fn apply(state: &mut EqbslState, params: &Params, action: &DisputeAction) -> Result<Vec<EdgeKey>, String> {
+    let t = state.t;
+    let provenance = state
+        .provenance
+        .as_mut()
+        .ok_or_else(|| "Disputes need provenance tracking".to_string())?;
+    let event_id = action.event_id();
+    let existing = provenance.disputes.get(event_id).map(|d| d.status);
+    match (action, existing) {
+        (DisputeAction::Open { weight, .. }, None) => {
+            if !(0.0..1.0).contains(weight) {
+                return Err(format!("Dispute weight must be in [0, 1), got {}", weight));
+            }
+        }
+        (DisputeAction::Open { .. }, Some(status)) => {
+            return Err(format!("Event '{}' has already been disputed ({:?})", event_id, status));
+        }
+        (_, Some(DisputeStatus::Pending)) => {}
+        (_, Some(status)) => return Err(format!("Dispute of event '{}' is already resolved ({:?})", event_id, status)),
+        (_, None) => return Err(format!("Event '{}' is not disputed", event_id)),
+    }
+
+    let edges: Vec<EdgeKey> = provenance.event(event_id).into_iter().map(|(key, _)| key.clone()).collect();
+    if edges.is_empty() {
+        return Err(format!("Event '{}' has no tracked evidence (unknown or compacted)", event_id));
+    }
+    if let DisputeAction::Open { .. } = action {
+        let (r, s) = provenance
+            .event(event_id)
+            .iter()
+            .map(|(_, c)| c.rs(params))
+            .fold((0.0, 0.0), |acc, x| (acc.0 + x.0, acc.1 + x.1));
+        if s <= r {
+            return Err(format!("Event '{}' is not negative evidence (r={}, s={})", event_id, r, s));
+        }
+    }
+
+    // Provenance and state must agree before anything is changed
+    let missing = |(i, j): &EdgeKey| format!("Event '{}' is tracked on edge ({}, {}), which is not in the state", event_id, i, j);
+    if let Some(key) = edges.iter().find(|key| !state.edges.contains_key(*key)) {
+        return Err(missing(key));
+    }
+
+    for key in &edges {
+        let (Some(p), Some(e)) = (provenance.edges.get_mut(key), state.edges.get_mut(key)) else {
+            return Err(missing(key));
+        };
+        for c in p.contributions.iter_mut().filter(|c| c.event_id == event_id) {
+            // Signed per-channel change to the edge evidence
+            let delta: Vec<f64> = match action {
+                DisputeAction::Open { weight, .. } => {
+                    let withheld: Vec<f64> = c.mass.iter().map(|m| m * (1.0 - weight)).collect();
+                    for (m, w) in c.mass.iter_mut().zip(&withheld) {
+                        *m -= w;
+                    }
+                    let delta = withheld.iter().map(|w| -w).collect();
+                    c.withheld = Some(withheld);
+                    delta
+                }
+                DisputeAction::Confirm { .. } => {
+                    let withheld = c.withheld.take().unwrap_or_default();
+                    for (m, w) in c.mass.iter_mut().zip(&withheld) {
+                        *m += w;
+                    }
+                    withheld
+                }
+                DisputeAction::Reverse { .. } => {
+                    c.withheld = None;
+                    c.mass.iter_mut().map(|m| -std::mem::take(m)).collect()
+                }
+            };
+            adjust_channels(e, &delta);
+        }
+    }
+
+    match action {
+        DisputeAction::Open { weight, .. } => {
+            provenance.disputes.insert(
+                event_id.to_string(),
+                Dispute {
+                    status: DisputeStatus::Pending,
+                    weight: *weight,
+                    opened_at: t,
+                    resolved_at: None,
+                    edges: edges.clone(),
+                },
+            );
+        }
+        DisputeAction::Confirm { .. } | DisputeAction::Reverse { .. } => {
+            let dispute = provenance.disputes.get_mut(event_id).expect("pending dispute");
+            dispute.status = match action {
+                DisputeAction::Confirm { .. } => DisputeStatus::Confirmed,
+                _ => DisputeStatus::Reversed,
+            };
+            dispute.resolved_at = Some(t);
+        }
+    }
+    Ok(edges)
+}
+
+/// Adds `delta[c]` to the mass of every channel c, clamped at zero. On tensors of rank > 1
+/// the channel slice is rescaled, keeping its distribution over the other axes.
+fn adjust_channels(e: &mut EvidenceTensor, delta: &[f64]) {
+    for (c, &d) in delta.iter().enumerate() {
+        if d == 0.0 {
+            continue;
+        }
+        let mut slice = e.data.index_axis_mut(Axis(0), c);
+        let total = slice.sum();
+        if slice.ndim() == 0 {
+            slice.mapv_inplace(|x| (x + d).max(0.0));
+        } else if total > 0.0 {
+            let factor = ((total + d) / total).max(0.0);
+            slice.mapv_inplace(|x| x * factor);
+        } else if d > 0.0 {
+            let share = d / slice.len() as f64;
+            slice.fill(share);
+        }
+    }
+}
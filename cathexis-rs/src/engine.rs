@@ -0,0 +1,546 @@
+use crate::attestation::HandleSigner;
+use crate::audit::AuditLog;
+use crate::canonical::Digest;
+use crate::dispute::{self, DisputeAction};
+use crate::eqbsl::TrustGraph;
+use crate::events::{Event, StepRecord};
+use crate::explain::{self, Explanation};
+use crate::features::FeatureState;
+use crate::history::{History, Snapshot};
+use crate::incremental::{self, DerivedCache, DirtySet};
+use crate::invariants::{AuditMode, Auditor, Violation};
+use crate::merkle::{InclusionProof, SparseMerkleTree, StateCommitment};
+use crate::numeric::Q32;
+use crate::operators::{self, Operator};
+use crate::proof::{self, ProofCarryingUpdate, Prover, UpdateWitness};
+use crate::provenance::{Provenance, ProvenanceConfig};
+use crate::replay::{self, EventLog, ReplayReport};
+use crate::schema::ChannelSchema;
+use crate::state::{EdgeKey, EqbslState, Params};
+use crate::sybil::{self, SybilConfig, SybilReport};
+use crate::threshold::{IssuedCommitment, OpinionOpening};
+use crate::whitewash::{self, Activity, WhitewashConfig, WhitewashReport};
+
+/// Live EQBSL engine: owns the state E_t and applies the update operator F_θ step by step.
+#[derive(Debug, Clone)]
+pub struct EqbslEngine {
+    params: Params,
+    state: EqbslState,
+    history: Option<History>,
+    dirty: DirtySet,
+    cache: Option<DerivedCache>,
+    sybil: Option<(SybilConfig, SybilReport)>,
+    whitewash: Option<(WhitewashConfig, Activity, WhitewashReport)>,
+    auditor: Option<Auditor>,
+    audit_log: Option<AuditLog>,
+    schema: Option<ChannelSchema>,
+    /// Merkle tree over the edges, kept up to date with the audit log so that each entry
+    /// only rehashes the edges its transition touched.
+    tree: Option<SparseMerkleTree>,
+}
+
+impl EqbslEngine {
+    pub fn new(params: Params, genesis: EqbslState) -> Result<Self, String> {
+        params.validate()?;
+        Ok(Self {
+            params,
+            state: genesis,
+            history: None,
+            dirty: DirtySet::default(),
+            cache: None,
+            sybil: None,
+            whitewash: None,
+            auditor: None,
+            audit_log: None,
+            schema: None,
+            tree: None,
+        })
+    }
+
+    /// Keeps versioned snapshots from the current state on, one checkpoint every `interval` steps.
+    pub fn with_history(mut self, interval: usize) -> Result<Self, String> {
+        self.history = Some(History::new(self.state.clone(), interval)?);
+        Ok(self)
+    }
+
+    /// Maintains derived results incrementally after every step; with `verify` each update
+    /// is checked against a full recomputation (test mode).
+    pub fn with_derived_cache(mut self, verify: bool) -> Result<Self, String> {
+        let mut cache = DerivedCache::build(&self.state, &self.params)?;
+        cache.verify = verify;
+        self.cache = Some(cache);
+        Ok(self)
+    }
+
+    /// Tracks per-edge event provenance from the current state on. Must be enabled before
+    /// `with_history` so that replays from genesis reproduce it.
+    pub fn with_provenance(mut self, config: ProvenanceConfig) -> Result<Self, String> {
+        if self.history.is_some() {
+            return Err("Enable provenance before history".to_string());
+        }
+        self.state.enable_provenance(config);
+        Ok(self)
+    }
+
+    /// Runs the Sybil analysis after every step and adds each agent's Sybil score to its
+    /// `TrustFeatures`. Scores are global, so a step can change any agent's score; those
+    /// that change are added to the dirty set. Seeds may join the state later; until one
+    /// does, every agent scores 1.
+    pub fn with_sybil_analysis(mut self, config: SybilConfig) -> Result<Self, String> {
+        let report = sybil::analyse(&self.state, &self.params, &config)?;
+        self.sybil = Some((config, report));
+        Ok(self)
+    }
+
+    /// Latest Sybil analysis, if enabled.
+    pub fn sybil_report(&self) -> Option<&SybilReport> {
+        self.sybil.as_ref().map(|(_, report)| report)
+    }
+
+    /// Tracks agent activity from the current state on, runs the whitewashing detector after
+    /// every step and adds each agent's whitewash score to its `TrustFeatures`. As with Sybil
+    /// scores, agents whose score changes are added to the dirty set.
+    pub fn with_whitewash_detection(mut self, config: WhitewashConfig) -> Result<Self, String> {
+        let activity = Activity::default();
+        let report = whitewash::detect(&self.state, &self.params, Some(&activity), &config)?;
+        self.whitewash = Some((config, activity, report));
+        Ok(self)
+    }
+
+    /// Latest whitewashing candidate links, if enabled.
+    pub fn whitewash_report(&self) -> Option<&WhitewashReport> {
+        self.whitewash.as_ref().map(|(_, _, report)| report)
+    }
+
+    /// Agent activity observed for the whitewashing detector, if enabled.
+    pub fn activity(&self) -> Option<&Activity> {
+        self.whitewash.as_ref().map(|(_, activity, _)| activity)
+    }
+
+    /// Checks the Primer Section 9 invariants after every operator (and, with the derived
+    /// cache, on the propagated evidence). All checks run on the candidate state before it
+    /// is committed, so a strict auditor makes the failing step an error and leaves the
+    /// engine unchanged; a monitoring one collects violations.
+    pub fn with_auditor(mut self, mode: AuditMode) -> Self {
+        self.auditor = Some(Auditor::new(mode));
+        self
+    }
+
+    pub fn auditor(&self) -> Option<&Auditor> {
+        self.auditor.as_ref()
+    }
+
+    /// Returns and clears the violations collected in monitoring mode.
+    pub fn take_violations(&mut self) -> Vec<Violation> {
+        self.auditor.as_mut().map(Auditor::take_violations).unwrap_or_default()
+    }
+
+    /// Records every state transition from the current state on in a hash-chained
+    /// `AuditLog`: steps (including proof-carrying ones), dispute actions and amendments.
+    pub fn with_audit_log(mut self) -> Self {
+        self.tree = Some(SparseMerkleTree::from_edges(&self.state.edges));
+        self.audit_log = Some(AuditLog::new(&self.commitment()));
+        self
+    }
+
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit_log.as_ref()
+    }
+
+    /// Walks the audit log's chain and checks it ends at the live state (requires
+    /// `with_audit_log`). Returns the head hash.
+    pub fn verify_audit_log(&self) -> Result<Digest, String> {
+        let log = self
+            .audit_log
+            .as_ref()
+            .ok_or_else(|| "Audit log is not enabled on this engine".to_string())?;
+        let head = log.verify()?;
+        if log.latest_root() != self.commitment().root {
+            return Err("Audit log does not end at the live state".to_string());
+        }
+        Ok(head)
+    }
+
+    /// Checks every ingested or amended event against `schema`, whose channel count and
+    /// projection weights must match θ.
+    pub fn with_schema(mut self, schema: ChannelSchema) -> Result<Self, String> {
+        if schema.len() != self.params.channels() {
+            return Err(format!(
+                "Schema '{}' v{} has {} channels, params expect {}",
+                schema.id,
+                schema.version,
+                schema.len(),
+                self.params.channels()
+            ));
+        }
+        if schema.w_pos() != self.params.w_pos || schema.w_neg() != self.params.w_neg {
+            return Err(format!(
+                "Schema '{}' v{} projection weights differ from params",
+                schema.id, schema.version
+            ));
+        }
+        self.schema = Some(schema);
+        Ok(self)
+    }
+
+    pub fn schema(&self) -> Option<&ChannelSchema> {
+        self.schema.as_ref()
+    }
+
+    fn check_schema(&self, events: &[Event]) -> Result<(), String> {
+        if let Some(schema) = &self.schema {
+            for event in events {
+                schema
+                    .validate(event.de())
+                    .map_err(|e| format!("Event '{}': {}", event.id(), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    pub fn state(&self) -> &EqbslState {
+        &self.state
+    }
+
+    /// Com(E_t) of the current state and parameters, for publishing.
+    pub fn commitment(&self) -> StateCommitment {
+        match &self.tree {
+            Some(tree) => StateCommitment::with_tree(&self.state, &self.params, tree),
+            None => StateCommitment::new(&self.state, &self.params),
+        }
+    }
+
+    /// Inclusion proof of edge (src, dst) against `commitment().edges`.
+    pub fn prove_edge(&self, src: &str, dst: &str) -> Option<InclusionProof> {
+        match &self.tree {
+            Some(tree) => tree.prove(src, dst),
+            None => SparseMerkleTree::from_edges(&self.state.edges).prove(src, dst),
+        }
+    }
+
+    /// Commits to the opinion of `observer` about `subject` in the current state, lifted in
+    /// Q32, and signs the commitment together with the state root. The opening goes
+    /// privately to the subject, who can then prove threshold claims about the opinion
+    /// with a `ThresholdAttestation`.
+    pub fn issue_commitment(
+        &self,
+        observer: &str,
+        subject: &str,
+        signer: &HandleSigner,
+        issued_at: i64,
+    ) -> Result<(IssuedCommitment, OpinionOpening), String> {
+        self.params.validate_for::<Q32>()?;
+        let e = self
+            .state
+            .edge(observer, subject)
+            .ok_or_else(|| format!("No evidence from '{}' about '{}'", observer, subject))?;
+        let (r, s) = self.params.rs(&e.cast::<Q32>())?;
+        let opening = OpinionOpening::new(&self.params.lift(r, s));
+        let issued = IssuedCommitment::issue(signer, observer, subject, self.commitment().root, issued_at, &opening);
+        Ok((issued, opening))
+    }
+
+    /// Event provenance of the evidence field, if enabled.
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.state.provenance.as_ref()
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    pub fn derived(&self) -> Option<&DerivedCache> {
+        self.cache.as_ref()
+    }
+
+    /// Edges and nodes changed since the last `take_dirty`.
+    pub fn dirty(&self) -> &DirtySet {
+        &self.dirty
+    }
+
+    /// Returns and clears the accumulated dirty set, e.g. to refresh a `CathexisPipeline`.
+    pub fn take_dirty(&mut self) -> DirtySet {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Advances the state to time `t`, ingesting `events`. The step and everything derived
+    /// from it are computed on a candidate state and committed last, so on error the engine
+    /// is unchanged.
+    pub fn step(&mut self, t: i64, events: Vec<Event>) -> Result<(), String> {
+        self.check_schema(&events)?;
+        let touched = incremental::touched(&self.state, &self.params, t, &events);
+        let mut ran = Vec::new();
+        if t > self.state.t {
+            ran.push(Operator::Decay);
+        }
+        ran.push(Operator::Ingest);
+        if events.iter().any(|e| matches!(e, Event::Hyper(_))) {
+            ran.push(Operator::Attribute);
+        }
+        let mut next = self.state.clone();
+        match &mut self.auditor {
+            Some(auditor) => operators::apply_step_audited(&mut next, &self.params, t, &events, auditor)?,
+            None => operators::apply_step(&mut next, &self.params, t, &events)?,
+        }
+        let transition = self.derive(next, touched, &events)?;
+        let inputs = proof::events_commitment(&events);
+        if let Some(history) = &mut self.history {
+            history.record(
+                StepRecord {
+                    t,
+                    events,
+                    disputes: Vec::new(),
+                },
+                &transition.state,
+            )?;
+        }
+        self.commit(transition, ran, inputs);
+        Ok(())
+    }
+
+    /// `step` with the proof-carrying statement of the update (Primer Section 10.1), proved by
+    /// `prover` and disclosing the opinions of the `disclose` edges. On error the engine is
+    /// unchanged.
+    pub fn step_with_proof(
+        &mut self,
+        t: i64,
+        events: Vec<Event>,
+        prover: &impl Prover,
+        disclose: &[EdgeKey],
+    ) -> Result<ProofCarryingUpdate, String> {
+        self.check_schema(&events)?;
+        let (_, statement) = proof::state_update(&self.state, &self.params, t, &events, disclose)?;
+        let proof = prover.prove(&statement, &UpdateWitness::new(&self.state, events.clone()))?;
+        self.step(t, events)?;
+        Ok(ProofCarryingUpdate { statement, proof })
+    }
+
+    /// Disputes a negative-evidence event (requires `with_provenance`): its evidence is held
+    /// at `weight` × its mass until `confirm_dispute` or `reverse_dispute`.
+    pub fn dispute(&mut self, event_id: &str, weight: f64) -> Result<(), String> {
+        self.resolve(DisputeAction::Open {
+            event_id: event_id.to_string(),
+            weight,
+        })
+    }
+
+    /// Upholds a disputed event, restoring its full (decayed) evidence.
+    pub fn confirm_dispute(&mut self, event_id: &str) -> Result<(), String> {
+        self.resolve(DisputeAction::Confirm {
+            event_id: event_id.to_string(),
+        })
+    }
+
+    /// Strikes a disputed event, removing what is left of its evidence.
+    pub fn reverse_dispute(&mut self, event_id: &str) -> Result<(), String> {
+        self.resolve(DisputeAction::Reverse {
+            event_id: event_id.to_string(),
+        })
+    }
+
+    /// Applies a dispute action at the current time and logs it as a step without events.
+    /// Like `step`, it commits only once everything has succeeded.
+    fn resolve(&mut self, action: DisputeAction) -> Result<(), String> {
+        let mut next = self.state.clone();
+        let edges = dispute::apply(&mut next, &self.params, &action)?;
+        if let Some(auditor) = &mut self.auditor {
+            auditor.check_state(&next, &self.params, Operator::Dispute)?;
+        }
+        let mut touched = DirtySet::default();
+        for (i, j) in &edges {
+            touched.mark_edge(i, j);
+        }
+        let transition = self.derive(next, touched, &[])?;
+        let inputs = Digest::of("eqbsl/dispute/v1", &action);
+        if let Some(history) = &mut self.history {
+            history.record(
+                StepRecord {
+                    t: transition.state.t,
+                    events: Vec::new(),
+                    disputes: vec![action],
+                },
+                &transition.state,
+            )?;
+        }
+        self.commit(transition, vec![Operator::Dispute], inputs);
+        Ok(())
+    }
+
+    /// Brings the derived results (cache, Sybil and whitewash reports) up to date with the
+    /// candidate state `next`, without changing the engine. With an auditor, the propagated
+    /// evidence is checked here, before anything is committed.
+    fn derive(&mut self, next: EqbslState, mut touched: DirtySet, events: &[Event]) -> Result<Transition, String> {
+        let cache = match &self.cache {
+            Some(cache) => {
+                let mut cache = cache.clone();
+                cache.update(&next, &self.params, &touched)?;
+                if let Some(auditor) = &mut self.auditor {
+                    auditor.check_propagation(&next, &self.params, &operators::flatten_rows(&cache.propagated))?;
+                }
+                Some(cache)
+            }
+            None => None,
+        };
+        // Sybil and whitewash scores are global: mark every agent whose score moved, not
+        // only the neighbourhood of the dirty edges
+        let sybil = match &self.sybil {
+            Some((config, report)) if !touched.is_empty() => {
+                let fresh = sybil::analyse(&next, &self.params, config)?;
+                touched.mark_scores(&report.scores, &fresh.scores);
+                Some(fresh)
+            }
+            _ => None,
+        };
+        let whitewash = match &self.whitewash {
+            Some((config, activity, report)) if !touched.is_empty() || !events.is_empty() => {
+                let mut activity = activity.clone();
+                activity.observe(config, &next, events);
+                let fresh = whitewash::detect(&next, &self.params, Some(&activity), config)?;
+                touched.mark_scores(&report.scores(), &fresh.scores());
+                Some((activity, fresh))
+            }
+            _ => None,
+        };
+        Ok(Transition {
+            state: next,
+            touched,
+            cache,
+            sybil,
+            whitewash,
+        })
+    }
+
+    /// Installs a derived transition and appends it to the audit log. Cannot fail.
+    fn commit(&mut self, transition: Transition, operators: Vec<Operator>, inputs: Digest) {
+        let Transition {
+            state,
+            touched,
+            cache,
+            sybil,
+            whitewash,
+        } = transition;
+        self.state = state;
+        if cache.is_some() {
+            self.cache = cache;
+        }
+        if let (Some((_, report)), Some(fresh)) = (&mut self.sybil, sybil) {
+            *report = fresh;
+        }
+        if let (Some((_, activity, report)), Some(fresh)) = (&mut self.whitewash, whitewash) {
+            (*activity, *report) = fresh;
+        }
+        if let (Some(log), Some(tree)) = (&mut self.audit_log, &mut self.tree) {
+            tree.update(&self.state.edges, &touched.edges);
+            log.append(operators, inputs, &StateCommitment::with_tree(&self.state, &self.params, tree));
+        }
+        self.dirty.merge(touched);
+    }
+
+    /// Corrects the state for a late event via replay: the event is filed under its true
+    /// time in the history and all later steps are reapplied (requires `with_history`).
+    /// On error the engine, history included, is unchanged.
+    pub fn amend(&mut self, event: Event) -> Result<(), String> {
+        self.check_schema(std::slice::from_ref(&event))?;
+        if event.t() > self.state.t {
+            return Err(format!(
+                "Event '{}' at t={} is not late (state is at t={}); use step",
+                event.id(),
+                event.t(),
+                self.state.t
+            ));
+        }
+        let mut history = self
+            .history
+            .clone()
+            .ok_or_else(|| "History is not enabled on this engine".to_string())?;
+        let observed = [event.clone()];
+        let corrected = history.amend(&self.params, event)?;
+        if let Some(auditor) = &mut self.auditor {
+            auditor.check_state(&corrected, &self.params, Operator::Replay)?;
+        }
+        let touched = DirtySet::between(&self.state, &corrected);
+        let transition = self.derive(corrected, touched, &observed)?;
+        self.history = Some(history);
+        self.commit(transition, vec![Operator::Replay], proof::events_commitment(&observed));
+        Ok(())
+    }
+
+    /// Rebuilds an engine by replaying `log` from `genesis`, keeping the log as history.
+    /// Dispute actions are re-issued after their step's events, which needs provenance on
+    /// `genesis` (as in `history().genesis()` of an engine with provenance).
+    pub fn from_log(params: Params, genesis: EqbslState, log: &EventLog, interval: usize) -> Result<Self, String> {
+        if genesis.provenance.is_none() && log.entries().iter().any(|r| !r.disputes.is_empty()) {
+            return Err("Event log has dispute actions but the genesis state tracks no provenance".to_string());
+        }
+        let mut engine = Self::new(params, genesis)?.with_history(interval)?;
+        for record in log.entries() {
+            // A record of dispute actions alone applies no step
+            if !record.events.is_empty() || record.disputes.is_empty() || record.t > engine.state.t {
+                engine.step(record.t, record.events.clone())?;
+            }
+            for action in &record.disputes {
+                engine.resolve(action.clone())?;
+            }
+        }
+        Ok(engine)
+    }
+
+    /// Replays the recorded history from genesis and checks the result matches the
+    /// live state bit for bit (requires `with_history`).
+    pub fn verify_replay(&self) -> Result<ReplayReport, String> {
+        let history = self
+            .history
+            .as_ref()
+            .ok_or_else(|| "History is not enabled on this engine".to_string())?;
+        replay::verify(&self.params, history.genesis(), history.log().entries(), &self.state)
+    }
+
+    /// Read-only view of the current state.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.state.clone(), self.params.clone())
+    }
+
+    /// Read-only view of the state as of time `t` (requires `with_history`).
+    pub fn state_at(&self, t: i64) -> Result<Snapshot, String> {
+        self.history
+            .as_ref()
+            .ok_or_else(|| "History is not enabled on this engine".to_string())?
+            .state_at(&self.params, t)
+    }
+}
+
+/// A transition computed on a candidate state by `EqbslEngine::derive`, ready to commit.
+#[derive(Debug)]
+struct Transition {
+    state: EqbslState,
+    touched: DirtySet,
+    cache: Option<DerivedCache>,
+    /// Fresh reports; `None` where the previous one still holds.
+    sybil: Option<SybilReport>,
+    whitewash: Option<(Activity, WhitewashReport)>,
+}
+
+impl TrustGraph for EqbslEngine {
+    fn get_nodes(&self) -> Vec<String> {
+        self.state.nodes()
+    }
+
+    fn compute_features(&self, agent_id: &str) -> Result<FeatureState, String> {
+        let mut features = FeatureState::extract(&self.state, &self.params, agent_id)?;
+        if let Some(report) = self.sybil_report() {
+            features.trust.sybil_score = report.score(agent_id);
+        }
+        if let Some(report) = self.whitewash_report() {
+            features.trust.whitewash_score = Some(report.score(agent_id));
+        }
+        Ok(features)
+    }
+
+    fn explain(&self, src: &str, dst: &str, limit: usize) -> Result<Explanation, String> {
+        explain::explain(&self.state, &self.params, src, dst, limit)
+    }
+}
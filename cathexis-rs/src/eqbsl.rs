@@ -1,8 +1,10 @@
+use crate::numeric::Scalar;
+use ndarray::{Array1, ArrayD, Axis, Ix1, IxDyn};
 use serde::{Deserialize, Serialize};
 
 /// Represents an EQBSL trust embedding for an agent i at time t.
 /// u_i(t) ∈ R^d_u (Section 2.3, Equation 9).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TrustEmbedding {
     /// The raw embedding vector.
     pub vector: Vec<f64>,
@@ -19,18 +21,227 @@ impl TrustEmbedding {
     }
 }
 
-/// Represents an evidence tensor e_ij(t) ∈ R^m (Section 2.3, Equation 4).
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EvidenceTensor {
-    pub components: Vec<f64>,
+/// Represents an evidence tensor e_ij(t) (Section 2.3, Equation 4).
+/// Axis 0 is the channel axis (see `schema::ChannelSchema` for naming); any further axes
+/// (e.g. context × severity) are kept as-is and contracted away only when the rank-1
+/// channel vector e_ij(t) ∈ R^m is needed for the (r, s) mapping (Primer Section 1.2).
+/// Entries are f64 by default; see `numeric` for the fixed-point number type.
+/// Deserialisation goes through `from_array`, so a decoded tensor always has a channel axis.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawEvidenceTensor<S>", bound(deserialize = "S: Scalar"))]
+pub struct EvidenceTensor<S = f64> {
+    pub data: ArrayD<S>,
+}
+
+/// Unchecked wire form of an `EvidenceTensor`.
+#[derive(Deserialize)]
+#[serde(bound = "S: Scalar")]
+struct RawEvidenceTensor<S> {
+    data: ArrayD<S>,
+}
+
+impl<S: Scalar> TryFrom<RawEvidenceTensor<S>> for EvidenceTensor<S> {
+    type Error = String;
+
+    fn try_from(raw: RawEvidenceTensor<S>) -> Result<Self, String> {
+        Self::from_array(raw.data)
+    }
+}
+
+impl<S: Scalar> EvidenceTensor<S> {
+    /// Rank-1 tensor from per-channel masses.
+    pub fn new(components: Vec<S>) -> Self {
+        Self {
+            data: Array1::from(components).into_dyn(),
+        }
+    }
+
+    /// Wraps an array of rank >= 1 whose first axis is the channel axis.
+    pub fn from_array(data: ArrayD<S>) -> Result<Self, String> {
+        if data.ndim() == 0 {
+            return Err("Evidence tensor must have rank >= 1 (axis 0 is the channel axis)".to_string());
+        }
+        Ok(Self { data })
+    }
+
+    /// All-zero rank-1 tensor with m channels.
+    pub fn zeros(m: usize) -> Self {
+        Self {
+            data: ArrayD::zeros(IxDyn(&[m])),
+        }
+    }
+
+    /// All-zero tensor of the given shape; `shape[0]` is the channel count.
+    pub fn zeros_shaped(shape: &[usize]) -> Result<Self, String> {
+        Self::from_array(ArrayD::zeros(IxDyn(shape)))
+    }
+
+    /// Number of channels m (length of axis 0).
+    pub fn len(&self) -> usize {
+        self.data.shape()[0]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn rank(&self) -> usize {
+        self.data.ndim()
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        self.data.shape()
+    }
+
+    /// Contracts `axis` against `weights`: out[..] = Σ_n weights[n] * self[.., n, ..].
+    /// The channel axis cannot be contracted.
+    pub fn contract(&self, axis: usize, weights: &[S]) -> Result<EvidenceTensor<S>, String> {
+        if axis == 0 || axis >= self.rank() {
+            return Err(format!(
+                "Cannot contract axis {} of a rank-{} evidence tensor (axis 0 is the channel axis)",
+                axis,
+                self.rank()
+            ));
+        }
+        if weights.len() != self.shape()[axis] {
+            return Err(format!(
+                "Contraction weights have length {}, axis {} has length {}",
+                weights.len(),
+                axis,
+                self.shape()[axis]
+            ));
+        }
+        let mut out: ArrayD<S> = ArrayD::zeros(self.data.index_axis(Axis(axis), 0).raw_dim());
+        for (n, w) in weights.iter().enumerate() {
+            out.zip_mut_with(&self.data.index_axis(Axis(axis), n), |y: &mut S, x: &S| {
+                *y = y.clone() + w.clone() * x.clone()
+            });
+        }
+        Ok(EvidenceTensor { data: out })
+    }
+
+    /// Sums out every non-channel axis, yielding the rank-1 channel vector e ∈ R^m.
+    pub fn channel_masses(&self) -> Array1<S> {
+        let mut data = self.data.clone();
+        while data.ndim() > 1 {
+            data = data.sum_axis(Axis(data.ndim() - 1));
+        }
+        data.into_dimensionality::<Ix1>()
+            .expect("rank-1 after summing non-channel axes")
+    }
+
+    /// Rank-1 projection of this tensor (see `channel_masses`).
+    pub fn flatten_channels(&self) -> EvidenceTensor<S> {
+        EvidenceTensor {
+            data: self.channel_masses().into_dyn(),
+        }
+    }
+
+    /// Linear (r, s) projection r = <w^+, e>, s = <w^-, e> on the rank-1 form (Primer Section 1.4),
+    /// clamped at zero. The weights are converted to `S` before multiplying.
+    pub fn project(&self, w_pos: &[f64], w_neg: &[f64]) -> Result<(S, S), String> {
+        if w_pos.len() != self.len() || w_neg.len() != self.len() {
+            return Err(format!(
+                "Projection weights have lengths ({}, {}), tensor has {} channels",
+                w_pos.len(),
+                w_neg.len(),
+                self.len()
+            ));
+        }
+        let e = self.channel_masses();
+        let dot = |w: &[f64]| {
+            let x = e.iter().zip(w).fold(S::zero(), |acc, (x, &w)| acc + x.clone() * S::from_f64(w));
+            if x > S::zero() {
+                x
+            } else {
+                S::zero()
+            }
+        };
+        Ok((dot(w_pos), dot(w_neg)))
+    }
+
+    /// Element-wise accumulation e += other; shapes must match.
+    pub fn add_assign(&mut self, other: &EvidenceTensor<S>) -> Result<(), String> {
+        if self.shape() != other.shape() {
+            return Err(format!(
+                "Evidence tensor shape mismatch: {:?} vs {:?}",
+                self.shape(),
+                other.shape()
+            ));
+        }
+        self.data += &other.data;
+        Ok(())
+    }
+
+    /// Scales each channel slice by its factor, e.g. decay e ← β ⊙ e (Primer Section 5).
+    pub fn scale_channels(&mut self, factors: &[S]) -> Result<(), String> {
+        if factors.len() != self.len() {
+            return Err(format!(
+                "Got {} channel factors for a tensor with {} channels",
+                factors.len(),
+                self.len()
+            ));
+        }
+        for (mut slice, f) in self.data.axis_iter_mut(Axis(0)).zip(factors) {
+            slice.mapv_inplace(|x| x * f.clone());
+        }
+        Ok(())
+    }
+
+    /// Entry-wise conversion to another number type (through f64).
+    pub fn cast<T: Scalar>(&self) -> EvidenceTensor<T> {
+        EvidenceTensor {
+            data: self.data.mapv(|x| T::from_f64(x.to_f64())),
+        }
+    }
 }
 
 /// Interface for the underlying Trust Graph / EQBSL engine.
-pub trait TrustGraph {
+/// `Sync` so that features can be computed for many agents at once (`parallel` feature).
+pub trait TrustGraph: Sync {
     /// Returns a list of all agent IDs in the graph.
     fn get_nodes(&self) -> Vec<String>;
     
     /// Returns the feature state for a given agent.
     /// In a real implementation, this would compute features from G_t and U_t.
     fn compute_features(&self, agent_id: &str) -> Result<crate::features::FeatureState, String>;
+
+    /// Explains how `src` comes to trust `dst`: the top `limit` direct and witness paths
+    /// behind the propagated opinion. Graphs without path-level evidence return an error.
+    fn explain(&self, src: &str, dst: &str, _limit: usize) -> Result<crate::explain::Explanation, String> {
+        Err(format!("Trust explanations are not available for ({}, {})", src, dst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_higher_order_contraction() {
+        // 2 channels × 3 contexts × 2 severities
+        let data = Array3::from_shape_fn((2, 3, 2), |(c, x, s)| (c * 6 + x * 2 + s) as f64);
+        let e = EvidenceTensor::from_array(data.into_dyn()).unwrap();
+        assert_eq!(e.rank(), 3);
+        assert_eq!(e.len(), 2);
+
+        // Weight severity 0 as 1.0 and severity 1 as 2.0 -> channel × context
+        let by_context = e.contract(2, &[1.0, 2.0]).unwrap();
+        assert_eq!(by_context.shape(), &[2, 3]);
+        assert_eq!(by_context.data[[0, 1]], 2.0 + 2.0 * 3.0);
+
+        // Channel 0 holds 0..6, channel 1 holds 6..12
+        assert_eq!(e.channel_masses().to_vec(), vec![15.0, 51.0]);
+        assert_eq!(e.project(&[1.0, 0.0], &[0.0, 1.0]).unwrap(), (15.0, 51.0));
+        assert!(e.contract(0, &[1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_deserialisation_requires_a_channel_axis() {
+        let e: EvidenceTensor = serde_json::from_str(r#"{"data":{"v":1,"dim":[2],"data":[1.0,2.0]}}"#).unwrap();
+        assert_eq!(e.len(), 2);
+        let scalar = serde_json::from_str::<EvidenceTensor>(r#"{"data":{"v":1,"dim":[],"data":[1.0]}}"#);
+        assert!(scalar.unwrap_err().to_string().contains("rank >= 1"));
+    }
 }
@@ -0,0 +1,71 @@
+use crate::dispute::DisputeAction;
+use crate::eqbsl::EvidenceTensor;
+use crate::state::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Pairwise event: evidence delta Δe_ij observed by `src` about `dst` (Primer Section 4.1).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairEvent {
+    pub id: String,
+    /// Event time t_event.
+    pub t: i64,
+    pub src: NodeId,
+    pub dst: NodeId,
+    pub de: EvidenceTensor,
+}
+
+/// Hyperedge event: evidence delta Δe_h for hyperedge `hid` (Primer Section 4.2).
+/// The first event for an unknown hyperedge must list its participants in `nodes`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HyperEvent {
+    pub id: String,
+    pub t: i64,
+    pub hid: String,
+    #[serde(default)]
+    pub nodes: Vec<NodeId>,
+    #[serde(default)]
+    pub roles: BTreeMap<NodeId, String>,
+    pub de: EvidenceTensor,
+}
+
+/// Input event of the update operator F_θ.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Pair(PairEvent),
+    Hyper(HyperEvent),
+}
+
+impl Event {
+    pub fn id(&self) -> &str {
+        match self {
+            Event::Pair(e) => &e.id,
+            Event::Hyper(e) => &e.id,
+        }
+    }
+
+    pub fn t(&self) -> i64 {
+        match self {
+            Event::Pair(e) => e.t,
+            Event::Hyper(e) => e.t,
+        }
+    }
+
+    pub fn de(&self) -> &EvidenceTensor {
+        match self {
+            Event::Pair(e) => &e.de,
+            Event::Hyper(e) => &e.de,
+        }
+    }
+}
+
+/// One update step: the batch of events ingested at time t.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepRecord {
+    pub t: i64,
+    pub events: Vec<Event>,
+    /// Dispute actions applied after the events.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disputes: Vec<DisputeAction>,
+}
@@ -0,0 +1,208 @@
+use crate::canonical::Canonical;
+use crate::eqbsl::EvidenceTensor;
+use crate::events::StepRecord;
+use crate::numeric::Scalar;
+use crate::operators;
+use crate::state::{EdgeKey, EqbslState, Hyperedge, Params};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+use serde::{Deserialize, Serialize};
+
+/// Exact rational number (big-integer fraction): the slow reference backend of the
+/// opinion mapping, fusion and the decay/ingest/attribution operators.
+pub type Rational = BigRational;
+
+impl Scalar for BigRational {
+    /// Reads the shortest decimal that round-trips to `x` (what `x` prints as) exactly, so a
+    /// decay rate of 0.9 is 9/10 rather than its nearest binary fraction. Non-finite values
+    /// convert to zero.
+    fn from_f64(x: f64) -> Self {
+        if !x.is_finite() {
+            return Self::zero();
+        }
+        let text = format!("{:e}", x);
+        let (mantissa, exponent) = text.split_once('e').expect("exponent notation");
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits: BigInt = format!("{}{}", int, frac).parse().expect("decimal digits");
+        let exponent = exponent.parse::<i32>().expect("decimal exponent") - frac.len() as i32;
+        let scale = BigInt::from(10).pow(exponent.unsigned_abs());
+        if exponent >= 0 {
+            Self::from_integer(digits * scale)
+        } else {
+            Self::new(digits, scale)
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        num_traits::ToPrimitive::to_f64(self).unwrap_or(f64::NAN)
+    }
+
+    fn label() -> String {
+        "rational".to_string()
+    }
+}
+
+impl Canonical for BigRational {
+    /// Numerator and denominator of the reduced fraction, each as length-prefixed big-endian
+    /// two's-complement bytes.
+    fn encode(&self, out: &mut Vec<u8>) {
+        for n in [self.numer(), self.denom()] {
+            let bytes = n.to_signed_bytes_be();
+            bytes.len().encode(out);
+            out.extend_from_slice(&bytes);
+        }
+    }
+}
+
+/// The exact value of an f64 (its binary fraction), unlike `Scalar::from_f64`.
+pub fn exact(x: f64) -> Rational {
+    Rational::from_float(x).unwrap_or_else(Rational::zero)
+}
+
+/// `state` as an exact reference input: every entry is read with `Scalar::from_f64`, the
+/// same convention `operators::apply_step` uses for θ and event masses; provenance is dropped.
+pub fn exact_state(state: &EqbslState) -> EqbslState<Rational> {
+    let tensor = |e: &EvidenceTensor| EvidenceTensor {
+        data: e.data.mapv(Rational::from_f64),
+    };
+    EqbslState {
+        t: state.t,
+        edges: state.edges.iter().map(|(key, e)| (key.clone(), tensor(e))).collect(),
+        hyperedges: state
+            .hyperedges
+            .iter()
+            .map(|(hid, h)| {
+                let exact_h = Hyperedge {
+                    id: h.id.clone(),
+                    nodes: h.nodes.clone(),
+                    roles: h.roles.clone(),
+                    e: tensor(&h.e),
+                };
+                (hid.clone(), exact_h)
+            })
+            .collect(),
+        provenance: None,
+    }
+}
+
+/// Exact reference run: `initial` (see `exact_state`) advanced through the logged `steps`.
+/// All inputs (initial entries, θ and event masses) are read as the decimals they print as,
+/// so 0.1 is 1/10 throughout; `compare` takes run values at their binary value instead, as
+/// those are the numbers the run actually holds. Dispute actions have no exact counterpart,
+/// so records carrying them are rejected.
+pub fn replay_exact(initial: &EqbslState, params: &Params, steps: &[StepRecord]) -> Result<EqbslState<Rational>, String> {
+    let mut state = exact_state(initial);
+    for record in steps {
+        if !record.disputes.is_empty() {
+            return Err(format!("Step t={} carries dispute actions, which have no exact replay", record.t));
+        }
+        operators::apply_step(&mut state, params, record.t, &record.events)?;
+    }
+    Ok(state)
+}
+
+/// Largest deviation of one quantity from the exact reference, and where it occurs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaxDeviation {
+    pub value: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge: Option<EdgeKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hyperedge: Option<String>,
+}
+
+impl MaxDeviation {
+    fn update(&mut self, deviation: &Rational, edge: Option<&EdgeKey>, hyperedge: Option<&str>) {
+        let value = deviation.to_f64();
+        if value > self.value {
+            *self = Self {
+                value,
+                edge: edge.cloned(),
+                hyperedge: hyperedge.map(str::to_string),
+            };
+        }
+    }
+}
+
+/// Deviation of a run from the exact reference: the numeric error budget (see `compare`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviationReport {
+    /// |Δ| of the evidence tensor entries of edges and hyperedges.
+    pub evidence: MaxDeviation,
+    /// |Δ| / |exact| of the nonzero entries.
+    pub relative_evidence: MaxDeviation,
+    /// |Δ| of the projected r and s of every edge.
+    pub rs: MaxDeviation,
+    /// |Δ| of b, d and u of the lifted opinions.
+    pub opinion: MaxDeviation,
+    /// |Δ| of the expected probabilities b + a u.
+    pub expectation: MaxDeviation,
+    /// Number of tensor entries compared.
+    pub entries: usize,
+}
+
+/// Compares a run in any number type with the exact reference of the same inputs. Run
+/// values are taken at their f64 value (exact for f64 runs, rounded to nearest for
+/// Q31.32 runs, whose 63 significant bits exceed f64's 53) and every difference is
+/// computed in rationals, so the report carries no rounding error beyond that conversion
+/// and the final one to f64. Both states must have the same time, edges and hyperedges.
+pub fn compare<S: Scalar>(run: &EqbslState<S>, reference: &EqbslState<Rational>, params: &Params) -> Result<DeviationReport, String> {
+    if run.t != reference.t {
+        return Err(format!("Run is at t={}, reference at t={}", run.t, reference.t));
+    }
+    if !run.edges.keys().eq(reference.edges.keys()) || !run.hyperedges.keys().eq(reference.hyperedges.keys()) {
+        return Err("Run and reference have different edges or hyperedges".to_string());
+    }
+    let mut report = DeviationReport::default();
+    for (key, e) in &run.edges {
+        let exact_e = &reference.edges[key];
+        tensor_deviation(&mut report, e, exact_e, Some(key), None)?;
+
+        let ((r, s), (exact_r, exact_s)) = (params.rs(e)?, params.rs(exact_e)?);
+        for (x, y) in [(&r, &exact_r), (&s, &exact_s)] {
+            report.rs.update(&(as_exact(x) - y).abs(), Some(key), None);
+        }
+        let (opinion, exact_opinion) = (params.lift(r, s), params.lift(exact_r, exact_s));
+        for (x, y) in [(&opinion.b, &exact_opinion.b), (&opinion.d, &exact_opinion.d), (&opinion.u, &exact_opinion.u)] {
+            report.opinion.update(&(as_exact(x) - y).abs(), Some(key), None);
+        }
+        let deviation = as_exact(&opinion.expected_probability()) - exact_opinion.expected_probability();
+        report.expectation.update(&deviation.abs(), Some(key), None);
+    }
+    for (hid, h) in &run.hyperedges {
+        tensor_deviation(&mut report, &h.e, &reference.hyperedges[hid].e, None, Some(hid))?;
+    }
+    Ok(report)
+}
+
+fn as_exact<S: Scalar>(x: &S) -> Rational {
+    exact(x.to_f64())
+}
+
+fn tensor_deviation<S: Scalar>(
+    report: &mut DeviationReport,
+    run: &EvidenceTensor<S>,
+    reference: &EvidenceTensor<Rational>,
+    edge: Option<&EdgeKey>,
+    hyperedge: Option<&str>,
+) -> Result<(), String> {
+    if run.shape() != reference.shape() {
+        return Err(format!(
+            "Run has shape {:?}, reference {:?} (edge {:?}, hyperedge {:?})",
+            run.shape(),
+            reference.shape(),
+            edge,
+            hyperedge
+        ));
+    }
+    for (x, y) in run.data.iter().zip(reference.data.iter()) {
+        let deviation = (as_exact(x) - y).abs();
+        if !y.is_zero() {
+            report.relative_evidence.update(&(&deviation / y.abs()), edge, hyperedge);
+        }
+        report.evidence.update(&deviation, edge, hyperedge);
+        report.entries += 1;
+    }
+    Ok(())
+}
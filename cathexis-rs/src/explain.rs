@@ -0,0 +1,262 @@
+use crate::core::Opinion;
+use crate::operators::{self, RsRows, WitnessMap};
+use crate::state::{EqbslState, NodeId, Params};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+
+/// One evidence path i → k_1 → … → k_m → j behind a propagated opinion. The direct
+/// evidence i → j is the path with no witnesses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrustPath {
+    /// Nodes from the source i to the target j.
+    pub nodes: Vec<NodeId>,
+    /// Discount δ of every witness hop (one per intermediate node).
+    pub discounts: Vec<f64>,
+    /// Path weight Π λ δ; 1 for direct evidence.
+    pub weight: f64,
+    /// (r, s) this path adds to rs_ij: weight × rs^direct of its last edge.
+    pub rs: (f64, f64),
+}
+
+impl TrustPath {
+    /// Evidence mass r + s, the ranking key.
+    pub fn contribution(&self) -> f64 {
+        self.rs.0 + self.rs.1
+    }
+
+    pub fn is_direct(&self) -> bool {
+        self.nodes.len() == 2
+    }
+}
+
+/// Why i trusts j: the propagated (r, s) and opinion, and the paths that make it up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub src: NodeId,
+    pub dst: NodeId,
+    /// Direct (r, s) of the edge i → j, if any.
+    pub direct: Option<(f64, f64)>,
+    /// Propagated (r, s) (Primer Section 7), the sum over all paths.
+    pub propagated: (f64, f64),
+    pub opinion: Opinion,
+    /// Top contributing paths, by contribution descending.
+    pub paths: Vec<TrustPath>,
+    /// Number of contributing paths before truncation to the requested limit.
+    pub total_paths: usize,
+}
+
+/// Decomposes the propagated (r, s) of (src, dst) into direct evidence and witness paths
+/// of length up to `propagation_depth`, ranked by contribution, keeping the top `limit`.
+///
+/// A path through witnesses k_1 … k_m contributes Π λ δ × rs^direct_{k_m j}, and j may not
+/// be any source on the path (the propagation operator skips self-entries at every level),
+/// so with `Aggregation::Sum` the contributions of all paths sum to the propagated (r, s).
+/// Robust aggregation modes combine the same contributions non-linearly; the paths are
+/// then the raw reports and `propagated` is the robust result.
+///
+/// Only row i is propagated, and the number of paths (up to ~top_k^D) is counted rather
+/// than enumerated: paths are searched best-first, guided by the largest contribution
+/// reachable from every (node, remaining depth), and the search stops after `limit` paths.
+pub fn explain(state: &EqbslState, params: &Params, src: &str, dst: &str, limit: usize) -> Result<Explanation, String> {
+    if !state.contains_node(src) {
+        return Err(format!("Unknown agent '{}'", src));
+    }
+    if !state.contains_node(dst) {
+        return Err(format!("Unknown agent '{}'", dst));
+    }
+    if src == dst {
+        return Err(format!("No trust relation from '{}' to itself", src));
+    }
+    let direct_map = operators::direct_rs(state, params)?;
+    let opinions = direct_map
+        .iter()
+        .map(|(key, &(r, s))| (key.clone(), params.lift(r, s)))
+        .collect();
+    let witnesses = operators::witness_sets(&opinions, params);
+    let direct = operators::rs_rows(&direct_map);
+
+    let source = BTreeSet::from([src.to_string()]);
+    let propagated = operators::propagate_rows(&direct, &witnesses, params, &source)
+        .get(src)
+        .and_then(|row| row.get(dst))
+        .copied()
+        .unwrap_or((0.0, 0.0));
+
+    let mut search = Search {
+        direct: &direct,
+        witnesses: &witnesses,
+        params,
+        dst,
+        memo: BTreeMap::new(),
+    };
+    let depth = params.propagation_depth;
+    let (_, total_paths) = search.summary(src, depth);
+    let paths = search.top(src, limit);
+    Ok(Explanation {
+        src: src.to_string(),
+        dst: dst.to_string(),
+        direct: direct.get(src).and_then(|row| row.get(dst)).copied(),
+        propagated,
+        opinion: params.lift(propagated.0, propagated.1),
+        paths,
+        total_paths,
+    })
+}
+
+/// Best-first enumeration of witness paths ending in `dst`.
+struct Search<'a> {
+    direct: &'a RsRows,
+    witnesses: &'a WitnessMap,
+    params: &'a Params,
+    dst: &'a str,
+    /// (node, remaining witness hops) → (largest contribution per unit weight, path count)
+    memo: BTreeMap<(&'a str, usize), (f64, usize)>,
+}
+
+/// A path in the search frontier: complete (ending in `dst`) or still open.
+struct Candidate {
+    /// Contribution of a complete path; an upper bound on those extending an open one.
+    priority: f64,
+    complete: bool,
+    nodes: Vec<NodeId>,
+    discounts: Vec<f64>,
+    weight: f64,
+}
+
+impl Candidate {
+    fn key(&self) -> (bool, std::cmp::Reverse<usize>, std::cmp::Reverse<&[NodeId]>) {
+        (self.complete, std::cmp::Reverse(self.nodes.len()), std::cmp::Reverse(&self.nodes))
+    }
+}
+
+impl Ord for Candidate {
+    /// Highest contribution first; ties go to complete, shorter, then lexically smaller paths.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .total_cmp(&other.priority)
+            .then_with(|| self.key().cmp(&other.key()))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl<'a> Search<'a> {
+    /// Direct (r, s) of `node` → `dst`, if nonzero.
+    fn last_hop(&self, node: &str) -> Option<(f64, f64)> {
+        let &(r, s) = self.direct.get(node)?.get(self.dst)?;
+        (r != 0.0 || s != 0.0).then_some((r, s))
+    }
+
+    /// Largest contribution per unit weight of, and number of, the paths from `node` to
+    /// `dst` through at most `hops` more witnesses.
+    fn summary(&mut self, node: &'a str, hops: usize) -> (f64, usize) {
+        if let Some(&cached) = self.memo.get(&(node, hops)) {
+            return cached;
+        }
+        let (mut best, mut count) = match self.last_hop(node) {
+            Some((r, s)) => (r + s, 1usize),
+            None => (0.0, 0),
+        };
+        if hops > 0 {
+            let witnesses: &'a WitnessMap = self.witnesses;
+            for (delta, k) in witnesses.get(node).into_iter().flatten() {
+                // j never counts as evidence about itself at any level
+                if k == self.dst {
+                    continue;
+                }
+                let (b, c) = self.summary(k, hops - 1);
+                best = best.max(self.params.damping_lambda * delta * b);
+                count = count.saturating_add(c);
+            }
+        }
+        self.memo.insert((node, hops), (best, count));
+        (best, count)
+    }
+
+    /// The `limit` paths from `src` with the largest contributions, in ranking order.
+    fn top(&mut self, src: &'a str, limit: usize) -> Vec<TrustPath> {
+        let depth = self.params.propagation_depth;
+        let mut paths = Vec::new();
+        let mut frontier = BinaryHeap::new();
+        if limit > 0 && self.summary(src, depth).1 > 0 {
+            frontier.push(Candidate {
+                priority: self.summary(src, depth).0,
+                complete: false,
+                nodes: vec![src.to_string()],
+                discounts: Vec::new(),
+                weight: 1.0,
+            });
+        }
+        while paths.len() < limit {
+            let Some(candidate) = frontier.pop() else {
+                break;
+            };
+            let Candidate {
+                complete,
+                mut nodes,
+                discounts,
+                weight,
+                ..
+            } = candidate;
+            if complete {
+                let (r, s) = self.last_hop(&nodes[nodes.len() - 2]).expect("complete paths end in a direct hop");
+                paths.push(TrustPath {
+                    nodes,
+                    discounts,
+                    weight,
+                    rs: (weight * r, weight * s),
+                });
+                continue;
+            }
+            let last = nodes.last().expect("path starts at the source").clone();
+            if let Some((r, s)) = self.last_hop(&last) {
+                let mut full = nodes.clone();
+                full.push(self.dst.to_string());
+                frontier.push(Candidate {
+                    priority: weight * r + weight * s,
+                    complete: true,
+                    nodes: full,
+                    discounts: discounts.clone(),
+                    weight,
+                });
+            }
+            let hops = depth - discounts.len();
+            if hops == 0 {
+                continue;
+            }
+            let witnesses: &'a WitnessMap = self.witnesses;
+            for (delta, k) in witnesses.get(&last).into_iter().flatten() {
+                let (best, count) = self.summary(k, hops - 1);
+                if k == self.dst || count == 0 {
+                    continue;
+                }
+                let weight = weight * self.params.damping_lambda * delta;
+                nodes.push(k.clone());
+                let mut discounts = discounts.clone();
+                discounts.push(*delta);
+                frontier.push(Candidate {
+                    priority: weight * best,
+                    complete: false,
+                    nodes: nodes.clone(),
+                    discounts,
+                    weight,
+                });
+                nodes.pop();
+            }
+        }
+        paths
+    }
+}
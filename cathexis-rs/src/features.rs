@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
+use crate::dispute::DisputeStatus;
 use crate::eqbsl::TrustEmbedding;
+use crate::operators;
+use crate::provenance::Provenance;
+use crate::state::{EqbslState, Params};
 
 /// Assembled feature state x_i(t) for an agent i (Section 3, Equation 10).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FeatureState {
     /// a) Trust features (embedding, global reputation, uncertainty, etc.)
     pub trust: TrustFeatures,
@@ -12,16 +16,36 @@ pub struct FeatureState {
     pub behavioural: BehaviouralFeatures,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TrustFeatures {
     /// The EQBSL embedding u_i(t)
     pub embedding: TrustEmbedding,
     /// Other global trust metrics (e.g. reputation score)
     pub reputation_score: f64,
     pub uncertainty: f64,
+    /// Sybil score in [0, 1] from `sybil::analyse`, when the analysis is configured.
+    #[serde(default)]
+    pub sybil_score: Option<f64>,
+    /// Confidence that the agent is a reset identity of a penalised one, from
+    /// `whitewash::detect`, when the detector is configured.
+    #[serde(default)]
+    pub whitewash_score: Option<f64>,
+    /// Disputes over evidence received by the agent, when provenance is tracked.
+    #[serde(default)]
+    pub disputes: Option<DisputeFeatures>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Dispute counts over the agent's inbound evidence, and the total mass held back by
+/// pending disputes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DisputeFeatures {
+    pub pending: f64,
+    pub confirmed: f64,
+    pub reversed: f64,
+    pub withheld_mass: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GraphFeatures {
     pub degree: f64,
     pub centrality: f64,
@@ -29,7 +53,7 @@ pub struct GraphFeatures {
     pub extra_metrics: Vec<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BehaviouralFeatures {
     pub temporal_activity: f64,
     pub platform_metrics: Vec<f64>,
@@ -43,6 +67,15 @@ impl FeatureState {
         vec.extend(&self.trust.embedding.vector);
         vec.push(self.trust.reputation_score);
         vec.push(self.trust.uncertainty);
+        if let Some(score) = self.trust.sybil_score {
+            vec.push(score);
+        }
+        if let Some(score) = self.trust.whitewash_score {
+            vec.push(score);
+        }
+        if let Some(d) = &self.trust.disputes {
+            vec.extend([d.pending, d.confirmed, d.reversed, d.withheld_mass]);
+        }
         
         // Graph
         vec.push(self.graph.degree);
@@ -57,3 +90,112 @@ impl FeatureState {
         vec
     }
 }
+
+impl FeatureState {
+    /// Assembles x_i(t) for `agent_id` from an EQBSL state.
+    ///
+    /// * trust: baseline embedding, plus expectation and uncertainty of the summed inbound evidence
+    /// * graph: undirected degree, degree centrality, local clustering;
+    ///   extra = [in_degree, out_degree, hyperedge participations]
+    /// * behavioural: total incident evidence mass; platform = inbound mass per channel
+    pub fn extract(state: &EqbslState, params: &Params, agent_id: &str) -> Result<FeatureState, String> {
+        if !state.contains_node(agent_id) {
+            return Err(format!("Unknown agent '{}'", agent_id));
+        }
+        let m = params.channels();
+        let mut opinions = std::collections::BTreeMap::new();
+        let (mut r_in, mut s_in) = (0.0, 0.0);
+        let (mut in_degree, mut out_degree) = (0.0, 0.0);
+        let mut activity = 0.0;
+        let mut inbound_channels = vec![0.0; m];
+        for (key, e) in &state.edges {
+            let (src, dst) = key;
+            if src != agent_id && dst != agent_id {
+                continue;
+            }
+            let (r, s) = params.rs(e)?;
+            opinions.insert(key.clone(), params.lift(r, s));
+            let masses = e.channel_masses();
+            activity += masses.sum();
+            if dst == agent_id {
+                r_in += r;
+                s_in += s;
+                in_degree += 1.0;
+                for (acc, x) in inbound_channels.iter_mut().zip(masses.iter()) {
+                    *acc += x;
+                }
+            } else {
+                out_degree += 1.0;
+            }
+        }
+        let reputation = params.lift(r_in, s_in);
+
+        let adj = operators::neighbours(state);
+        let empty = std::collections::BTreeSet::new();
+        let own = adj.get(&agent_id.to_string()).unwrap_or(&empty);
+        let degree = own.len() as f64;
+        let n = state.nodes().len();
+        let centrality = if n > 1 { degree / (n - 1) as f64 } else { 0.0 };
+        let mut links = 0usize;
+        for a in own {
+            for b in own {
+                if a < b && adj.get(a).is_some_and(|na| na.contains(b)) {
+                    links += 1;
+                }
+            }
+        }
+        let clustering_coefficient = if own.len() > 1 {
+            2.0 * links as f64 / (degree * (degree - 1.0))
+        } else {
+            0.0
+        };
+        let hyper_count = state
+            .hyperedges
+            .values()
+            .filter(|h| h.nodes.iter().any(|x| x == agent_id))
+            .count() as f64;
+
+        Ok(FeatureState {
+            trust: TrustFeatures {
+                embedding: operators::embed_node(&opinions, agent_id),
+                reputation_score: reputation.expected_probability(),
+                uncertainty: reputation.u,
+                sybil_score: None,
+                whitewash_score: None,
+                disputes: state.provenance.as_ref().map(|p| dispute_features(p, agent_id)),
+            },
+            graph: GraphFeatures {
+                degree,
+                centrality,
+                clustering_coefficient,
+                extra_metrics: vec![in_degree, out_degree, hyper_count],
+            },
+            behavioural: BehaviouralFeatures {
+                temporal_activity: activity,
+                platform_metrics: inbound_channels,
+            },
+        })
+    }
+}
+
+fn dispute_features(provenance: &Provenance, agent_id: &str) -> DisputeFeatures {
+    let mut features = DisputeFeatures::default();
+    for dispute in provenance.disputes.values() {
+        if !dispute.edges.iter().any(|(_, dst)| dst == agent_id) {
+            continue;
+        }
+        match dispute.status {
+            DisputeStatus::Pending => features.pending += 1.0,
+            DisputeStatus::Confirmed => features.confirmed += 1.0,
+            DisputeStatus::Reversed => features.reversed += 1.0,
+        }
+    }
+    features.withheld_mass = provenance
+        .edges
+        .iter()
+        .filter(|((_, dst), _)| dst == agent_id)
+        .flat_map(|(_, p)| &p.contributions)
+        .flat_map(|c| c.withheld.iter().flatten())
+        .sum();
+    features
+}
@@ -0,0 +1,214 @@
+use crate::core::Opinion;
+use crate::eqbsl::{TrustEmbedding, TrustGraph};
+use crate::events::{Event, StepRecord};
+use crate::explain::{self, Explanation};
+use crate::features::FeatureState;
+use crate::operators;
+use crate::replay::{self, EventLog};
+use crate::state::{EdgeKey, EqbslState, NodeId, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Versioned history of the EQBSL state.
+/// A full checkpoint is kept every `interval` steps; states in between are rebuilt by
+/// replaying the recorded steps from the nearest earlier checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct History {
+    interval: usize,
+    /// (number of steps applied, state after those steps), ascending.
+    checkpoints: Vec<(usize, EqbslState)>,
+    log: EventLog,
+}
+
+impl History {
+    /// Starts a history at `genesis`; `interval = 1` snapshots every step.
+    pub fn new(genesis: EqbslState, interval: usize) -> Result<Self, String> {
+        if interval == 0 {
+            return Err("Snapshot interval must be at least 1".to_string());
+        }
+        Ok(Self {
+            interval,
+            checkpoints: vec![(0, genesis)],
+            log: EventLog::new(),
+        })
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    /// Every step recorded since genesis.
+    pub fn log(&self) -> &EventLog {
+        &self.log
+    }
+
+    pub fn genesis(&self) -> &EqbslState {
+        &self.checkpoints[0].1
+    }
+
+    /// The most recent checkpoint as (number of steps applied, state).
+    pub fn latest_checkpoint(&self) -> (usize, &EqbslState) {
+        let (n, state) = self.checkpoints.last().expect("genesis checkpoint is always present");
+        (*n, state)
+    }
+
+    /// Records a step that has been applied, producing `post`.
+    pub fn record(&mut self, record: StepRecord, post: &EqbslState) -> Result<(), String> {
+        self.log.append(record)?;
+        if self.log.len().is_multiple_of(self.interval) {
+            self.checkpoints.push((self.log.len(), post.clone()));
+        }
+        Ok(())
+    }
+
+    /// Files a late event at its true time and rebuilds every later checkpoint by replay.
+    /// Returns the corrected live state; on error the history is unchanged.
+    pub fn amend(&mut self, params: &Params, event: Event) -> Result<EqbslState, String> {
+        let genesis_t = self.genesis().t;
+        if event.t() < genesis_t {
+            return Err(format!(
+                "Event '{}' at t={} predates genesis t={}",
+                event.id(),
+                event.t(),
+                genesis_t
+            ));
+        }
+        let mut log = self.log.clone();
+        let from = log.insert_late(event);
+        let mut checkpoints: Vec<(usize, EqbslState)> =
+            self.checkpoints.iter().filter(|(n, _)| *n <= from).cloned().collect();
+        let (start, base) = checkpoints.last().cloned().expect("genesis checkpoint is always present");
+
+        let mut state = base;
+        for (offset, step) in log.entries()[start..].iter().enumerate() {
+            operators::apply_record(&mut state, params, step)?;
+            let n = start + offset + 1;
+            if n.is_multiple_of(self.interval) {
+                checkpoints.push((n, state.clone()));
+            }
+        }
+        self.log = log;
+        self.checkpoints = checkpoints;
+        Ok(state)
+    }
+
+    /// The state as of time `t`: every step with `step.t <= t` applied, then decayed to `t`.
+    pub fn state_at(&self, params: &Params, t: i64) -> Result<Snapshot, String> {
+        let genesis_t = self.genesis().t;
+        if t < genesis_t {
+            return Err(format!("No state before genesis t={}, requested t={}", genesis_t, t));
+        }
+        let steps = self.log.entries();
+        let applied = steps.partition_point(|step| step.t <= t);
+        let (start, checkpoint) = self
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|(n, _)| *n <= applied)
+            .expect("genesis checkpoint is always present");
+        let mut state = replay::replay(params, checkpoint, &steps[*start..applied])?.state;
+        operators::apply_step(&mut state, params, t, &[])?;
+        Ok(Snapshot::new(state, params.clone()))
+    }
+}
+
+/// Read-only view of the EQBSL state at a point in time, consumable by the
+/// derived operators (lift, propagation, embedding) and as a `TrustGraph`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    state: EqbslState,
+    params: Params,
+}
+
+impl Snapshot {
+    pub fn new(state: EqbslState, params: Params) -> Self {
+        Self { state, params }
+    }
+
+    pub fn t(&self) -> i64 {
+        self.state.t
+    }
+
+    pub fn state(&self) -> &EqbslState {
+        &self.state
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    pub fn opinions(&self) -> Result<BTreeMap<EdgeKey, Opinion>, String> {
+        operators::lift_opinions(&self.state, &self.params)
+    }
+
+    pub fn propagated(&self) -> Result<BTreeMap<EdgeKey, (f64, f64)>, String> {
+        operators::propagate(&self.state, &self.params)
+    }
+
+    pub fn embeddings(&self) -> Result<BTreeMap<NodeId, TrustEmbedding>, String> {
+        operators::embed_nodes(&self.state, &self.params)
+    }
+
+    /// Per-edge changes from `self` to `later` (see `diff`).
+    pub fn diff(&self, later: &Snapshot) -> Result<Vec<EdgeDiff>, String> {
+        diff(&self.state, &later.state, &self.params)
+    }
+}
+
+impl TrustGraph for Snapshot {
+    fn get_nodes(&self) -> Vec<String> {
+        self.state.nodes()
+    }
+
+    fn compute_features(&self, agent_id: &str) -> Result<FeatureState, String> {
+        FeatureState::extract(&self.state, &self.params, agent_id)
+    }
+
+    fn explain(&self, src: &str, dst: &str, limit: usize) -> Result<Explanation, String> {
+        explain::explain(&self.state, &self.params, src, dst, limit)
+    }
+}
+
+/// Change of one edge between two states; `None` means the edge did not exist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdgeDiff {
+    pub src: NodeId,
+    pub dst: NodeId,
+    pub before: Option<(f64, f64)>,
+    pub after: Option<(f64, f64)>,
+    pub before_opinion: Option<Opinion>,
+    pub after_opinion: Option<Opinion>,
+}
+
+impl EdgeDiff {
+    /// Change in (r, s), treating a missing edge as zero evidence.
+    pub fn delta_rs(&self) -> (f64, f64) {
+        let (r0, s0) = self.before.unwrap_or((0.0, 0.0));
+        let (r1, s1) = self.after.unwrap_or((0.0, 0.0));
+        (r1 - r0, s1 - s0)
+    }
+}
+
+/// Lists every edge whose evidence differs between `before` and `after`, in edge order.
+pub fn diff(before: &EqbslState, after: &EqbslState, params: &Params) -> Result<Vec<EdgeDiff>, String> {
+    let keys: BTreeSet<&EdgeKey> = before.edges.keys().chain(after.edges.keys()).collect();
+    let mut out = Vec::new();
+    for key in keys {
+        let e0 = before.edges.get(key);
+        let e1 = after.edges.get(key);
+        if e0 == e1 {
+            continue;
+        }
+        let rs0 = e0.map(|e| params.rs(e)).transpose()?;
+        let rs1 = e1.map(|e| params.rs(e)).transpose()?;
+        out.push(EdgeDiff {
+            src: key.0.clone(),
+            dst: key.1.clone(),
+            before: rs0,
+            after: rs1,
+            before_opinion: rs0.map(|(r, s)| params.lift(r, s)),
+            after_opinion: rs1.map(|(r, s)| params.lift(r, s)),
+        });
+    }
+    Ok(out)
+}
@@ -0,0 +1,290 @@
+use crate::core::Opinion;
+use crate::eqbsl::TrustEmbedding;
+use crate::events::Event;
+use crate::operators::{self, RsRows, WitnessMap};
+use crate::state::{EdgeKey, EqbslState, NodeId, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Edges and nodes whose evidence changed since the derived results were last refreshed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DirtySet {
+    pub edges: BTreeSet<EdgeKey>,
+    /// Nodes that did not exist before; they change n and therefore every centrality.
+    pub new_nodes: BTreeSet<NodeId>,
+    /// Agents whose global scores (Sybil, whitewash) changed, whether or not any of their
+    /// edges did.
+    #[serde(default)]
+    pub agents: BTreeSet<NodeId>,
+}
+
+impl DirtySet {
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty() && self.new_nodes.is_empty() && self.agents.is_empty()
+    }
+
+    /// Every edge that differs between two states, e.g. after a replay correction.
+    pub fn between(before: &EqbslState, after: &EqbslState) -> DirtySet {
+        let mut dirty = DirtySet::default();
+        for (key, e) in &after.edges {
+            if before.edges.get(key) != Some(e) {
+                dirty.edges.insert(key.clone());
+            }
+        }
+        for key in before.edges.keys() {
+            if !after.edges.contains_key(key) {
+                dirty.edges.insert(key.clone());
+            }
+        }
+        let known: BTreeSet<NodeId> = before.nodes().into_iter().collect();
+        dirty.new_nodes = after.nodes().into_iter().filter(|n| !known.contains(n)).collect();
+        dirty
+    }
+
+    pub fn mark_edge(&mut self, src: &str, dst: &str) {
+        self.edges.insert((src.to_string(), dst.to_string()));
+    }
+
+    pub fn merge(&mut self, other: DirtySet) {
+        self.edges.extend(other.edges);
+        self.new_nodes.extend(other.new_nodes);
+        self.agents.extend(other.agents);
+    }
+
+    /// Endpoints of dirty edges.
+    pub fn nodes(&self) -> BTreeSet<NodeId> {
+        let mut nodes: BTreeSet<NodeId> = self.new_nodes.clone();
+        for (i, j) in &self.edges {
+            nodes.insert(i.clone());
+            nodes.insert(j.clone());
+        }
+        nodes
+    }
+
+    /// Sources whose propagated rows may change: sources of dirty edges (their direct row
+    /// and witness ranking changed), then every node with one of those as a witness,
+    /// repeated `propagation_depth` times.
+    pub fn propagation_sources(&self, witnesses: &WitnessMap, params: &Params) -> BTreeSet<NodeId> {
+        let mut affected: BTreeSet<NodeId> = self.edges.iter().map(|(i, _)| i.clone()).collect();
+        let mut witnessed_by: BTreeMap<&NodeId, Vec<&NodeId>> = BTreeMap::new();
+        for (i, list) in witnesses {
+            for (_, k) in list {
+                witnessed_by.entry(k).or_default().push(i);
+            }
+        }
+        for _ in 0..params.propagation_depth {
+            let mut next = affected.clone();
+            for k in &affected {
+                next.extend(witnessed_by.get(k).into_iter().flatten().map(|i| (*i).clone()));
+            }
+            if next.len() == affected.len() {
+                break;
+            }
+            affected = next;
+        }
+        affected
+    }
+
+    /// Agents whose `FeatureState` may change: endpoints of dirty edges and their neighbours
+    /// (degree and clustering) and agents with changed scores, or every node if new nodes
+    /// appeared (centrality).
+    pub fn feature_agents(&self, state: &EqbslState) -> BTreeSet<NodeId> {
+        if !self.new_nodes.is_empty() {
+            return state.nodes().into_iter().collect();
+        }
+        let adj = operators::neighbours(state);
+        let mut agents = self.nodes();
+        for n in self.nodes() {
+            agents.extend(adj.get(&n).into_iter().flatten().map(|x| (*x).clone()));
+        }
+        agents.extend(self.agents.iter().cloned());
+        agents
+    }
+
+    /// Marks every agent whose score differs between `before` and `after`, including agents
+    /// scored in only one of them.
+    pub fn mark_scores(&mut self, before: &BTreeMap<NodeId, f64>, after: &BTreeMap<NodeId, f64>) {
+        for (agent, score) in after {
+            if before.get(agent) != Some(score) {
+                self.agents.insert(agent.clone());
+            }
+        }
+        for agent in before.keys() {
+            if !after.contains_key(agent) {
+                self.agents.insert(agent.clone());
+            }
+        }
+    }
+}
+
+/// The edges and nodes that `apply_step(state, params, t, events)` will change.
+pub fn touched(state: &EqbslState, params: &Params, t: i64, events: &[Event]) -> DirtySet {
+    let mut dirty = DirtySet::default();
+    if t > state.t {
+        let decaying: Vec<bool> = params.decay_beta.iter().map(|&b| b < 1.0).collect();
+        let decays = |e: &crate::eqbsl::EvidenceTensor| {
+            e.data
+                .outer_iter()
+                .zip(&decaying)
+                .any(|(slice, &d)| d && slice.iter().any(|x| *x != 0.0))
+        };
+        for ((i, j), e) in &state.edges {
+            if decays(e) {
+                dirty.mark_edge(i, j);
+            }
+        }
+    }
+    let known: BTreeSet<NodeId> = state.nodes().into_iter().collect();
+    let mut members: BTreeMap<&str, &[NodeId]> = state
+        .hyperedges
+        .iter()
+        .map(|(id, h)| (id.as_str(), h.nodes.as_slice()))
+        .collect();
+    for event in events {
+        let nodes: Vec<&NodeId> = match event {
+            Event::Pair(e) => {
+                dirty.mark_edge(&e.src, &e.dst);
+                vec![&e.src, &e.dst]
+            }
+            Event::Hyper(e) => {
+                if !e.nodes.is_empty() {
+                    members.entry(&e.hid).or_insert(&e.nodes);
+                }
+                let nodes = members.get(e.hid.as_str()).copied().unwrap_or(&[]);
+                for i in nodes {
+                    for j in nodes {
+                        if i != j {
+                            dirty.mark_edge(i, j);
+                        }
+                    }
+                }
+                nodes.iter().collect()
+            }
+        };
+        for n in nodes {
+            if !known.contains(n) {
+                dirty.new_nodes.insert(n.clone());
+            }
+        }
+    }
+    dirty
+}
+
+/// Derived results (opinion lift, witness sets, propagation, embedding) kept up to date
+/// by recomputing only what a `DirtySet` can have affected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DerivedCache {
+    pub direct: RsRows,
+    pub opinions: BTreeMap<EdgeKey, Opinion>,
+    pub witnesses: WitnessMap,
+    pub propagated: RsRows,
+    pub embeddings: BTreeMap<NodeId, TrustEmbedding>,
+    /// Test mode: after every update, compare against a full recomputation and fail on mismatch.
+    pub verify: bool,
+}
+
+impl DerivedCache {
+    /// Full computation from scratch.
+    pub fn build(state: &EqbslState, params: &Params) -> Result<Self, String> {
+        let direct_map = operators::direct_rs(state, params)?;
+        let opinions: BTreeMap<EdgeKey, Opinion> = direct_map
+            .iter()
+            .map(|(key, &(r, s))| (key.clone(), params.lift(r, s)))
+            .collect();
+        let witnesses = operators::witness_sets(&opinions, params);
+        let direct = operators::rs_rows(&direct_map);
+        let sources: BTreeSet<NodeId> = direct.keys().cloned().collect();
+        let propagated = operators::propagate_rows(&direct, &witnesses, params, &sources);
+        let embeddings = operators::embed_nodes(state, params)?;
+        Ok(Self {
+            direct,
+            opinions,
+            witnesses,
+            propagated,
+            embeddings,
+            verify: false,
+        })
+    }
+
+    /// Propagated (r, s) of every pair, flattened.
+    pub fn propagated_rs(&self) -> BTreeMap<EdgeKey, (f64, f64)> {
+        operators::flatten_rows(&self.propagated)
+    }
+
+    /// Brings the cache up to date with `state` after the changes in `dirty`.
+    pub fn update(&mut self, state: &EqbslState, params: &Params, dirty: &DirtySet) -> Result<(), String> {
+        // Lift: dirty edges only
+        for key in &dirty.edges {
+            match state.edges.get(key) {
+                Some(e) => {
+                    let (r, s) = params.rs(e)?;
+                    self.direct.entry(key.0.clone()).or_default().insert(key.1.clone(), (r, s));
+                    self.opinions.insert(key.clone(), params.lift(r, s));
+                }
+                None => {
+                    if let Some(row) = self.direct.get_mut(&key.0) {
+                        row.remove(&key.1);
+                        if row.is_empty() {
+                            self.direct.remove(&key.0);
+                        }
+                    }
+                    self.opinions.remove(key);
+                }
+            }
+        }
+
+        // Witness sets: only sources of dirty edges
+        let sources: BTreeSet<&NodeId> = dirty.edges.iter().map(|(i, _)| i).collect();
+        for i in sources {
+            let outgoing = self
+                .opinions
+                .range((i.clone(), String::new())..)
+                .take_while(|((src, _), _)| src == i)
+                .map(|((_, k), op)| (k, op));
+            let list = operators::witness_set(i, outgoing, params);
+            if list.is_empty() {
+                self.witnesses.remove(i);
+            } else {
+                self.witnesses.insert(i.clone(), list);
+            }
+        }
+
+        // Propagation: the affected neighbourhood, bounded by depth
+        let rows = dirty.propagation_sources(&self.witnesses, params);
+        let fresh = operators::propagate_rows(&self.direct, &self.witnesses, params, &rows);
+        for i in &rows {
+            match fresh.get(i) {
+                Some(row) => self.propagated.insert(i.clone(), row.clone()),
+                None => self.propagated.remove(i),
+            };
+        }
+
+        // Embedding: endpoints of dirty edges
+        let nodes = dirty.nodes();
+        let mut incident: BTreeMap<&NodeId, BTreeMap<EdgeKey, Opinion>> = BTreeMap::new();
+        for ((i, j), op) in &self.opinions {
+            for n in [i, j] {
+                if nodes.contains(n) {
+                    incident.entry(n).or_default().insert((i.clone(), j.clone()), *op);
+                }
+            }
+        }
+        for n in &nodes {
+            if state.contains_node(n) {
+                let ops = incident.remove(n).unwrap_or_default();
+                self.embeddings.insert(n.clone(), operators::embed_node(&ops, n));
+            } else {
+                self.embeddings.remove(n);
+            }
+        }
+
+        if self.verify {
+            let mut full = Self::build(state, params)?;
+            full.verify = true;
+            if *self != full {
+                return Err("Incremental recomputation diverged from full recomputation".to_string());
+            }
+        }
+        Ok(())
+    }
+}
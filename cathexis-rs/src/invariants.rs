@@ -0,0 +1,263 @@
+use crate::numeric::Scalar;
+use crate::operators::{self, Operator};
+use crate::state::{EdgeKey, EqbslState, NodeId, Params};
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// What the auditor does with a violation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditMode {
+    /// Fail the operation with the first violation.
+    Strict,
+    /// Record violations and carry on.
+    Monitor,
+}
+
+/// The invariants of Primer Section 9, in its order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Invariant {
+    /// 1. r, s ≥ 0 (checked on every evidence tensor entry).
+    NonNegativeEvidence,
+    /// 2. b, d, u ∈ [0, 1].
+    OpinionRange,
+    /// 3. b + d + u = 1.
+    OpinionAdditivity,
+    /// 4. u decreases as r + s increases.
+    MonotoneUncertainty,
+    /// 5. Decay does not increase evidence.
+    DecayNonIncreasing,
+    /// 6. Propagation is bounded.
+    BoundedPropagation,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    pub invariant: Invariant,
+    /// The operator after which the check ran.
+    pub operator: Operator,
+    /// State time of the check.
+    pub t: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge: Option<EdgeKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hyperedge: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<NodeId>,
+    pub detail: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invariant {:?} violated after {:?} at t={}", self.invariant, self.operator, self.t)?;
+        if let Some((i, j)) = &self.edge {
+            write!(f, " on edge ({}, {})", i, j)?;
+        }
+        if let Some(h) = &self.hyperedge {
+            write!(f, " on hyperedge '{}'", h)?;
+        }
+        if let Some(agent) = &self.agent {
+            write!(f, " for agent '{}'", agent)?;
+        }
+        write!(f, ": {}", self.detail)
+    }
+}
+
+/// Runtime checker for the Primer Section 9 invariants. Attach it to an engine with
+/// `EqbslEngine::with_auditor`, or call the checks directly on a state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Auditor {
+    pub mode: AuditMode,
+    /// Numeric tolerance of the opinion and monotonicity checks.
+    pub tolerance: f64,
+    violations: Vec<Violation>,
+    checks: usize,
+}
+
+impl Auditor {
+    pub fn new(mode: AuditMode) -> Self {
+        Self {
+            mode,
+            tolerance: 1e-9,
+            violations: Vec::new(),
+            checks: 0,
+        }
+    }
+
+    /// Violations collected in monitor mode.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    pub fn take_violations(&mut self) -> Vec<Violation> {
+        std::mem::take(&mut self.violations)
+    }
+
+    /// Number of checks run.
+    pub fn checks(&self) -> usize {
+        self.checks
+    }
+
+    /// Invariants 1-4 on every edge and hyperedge of `state`, checked in f64.
+    pub fn check_state<S: Scalar>(
+        &mut self,
+        state: &EqbslState<S>,
+        params: &Params,
+        operator: Operator,
+    ) -> Result<(), String> {
+        let tol = self.tolerance;
+        let mut found = Vec::new();
+        let violation = |invariant, edge: Option<&EdgeKey>, detail: String| Violation {
+            invariant,
+            operator,
+            t: state.t,
+            edge: edge.cloned(),
+            hyperedge: None,
+            agent: None,
+            detail,
+        };
+        let mut masses: Vec<(f64, f64, &EdgeKey)> = Vec::with_capacity(state.edges.len());
+        for (key, e) in &state.edges {
+            if let Some(x) = e.data.iter().map(Scalar::to_f64).find(|x| !(*x >= 0.0 && x.is_finite())) {
+                found.push(violation(Invariant::NonNegativeEvidence, Some(key), format!("evidence entry {}", x)));
+                continue;
+            }
+            let (r, s) = params.rs(e)?;
+            let (r, s) = (r.to_f64(), s.to_f64());
+            if !(r >= 0.0 && s >= 0.0) {
+                found.push(violation(Invariant::NonNegativeEvidence, Some(key), format!("(r, s) = ({}, {})", r, s)));
+                continue;
+            }
+            let op = params.lift(r, s);
+            if [op.b, op.d, op.u].iter().any(|x| !(*x >= -tol && *x <= 1.0 + tol)) {
+                found.push(violation(Invariant::OpinionRange, Some(key), format!("(b, d, u) = ({}, {}, {})", op.b, op.d, op.u)));
+            }
+            let sum = op.b + op.d + op.u;
+            if (sum - 1.0).abs() > tol {
+                found.push(violation(Invariant::OpinionAdditivity, Some(key), format!("b + d + u = {}", sum)));
+            }
+            masses.push((r + s, op.u, key));
+        }
+        masses.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.2.cmp(b.2)));
+        for pair in masses.windows(2) {
+            let ((m0, u0, _), (m1, u1, key)) = (pair[0], pair[1]);
+            if m1 > m0 && u1 > u0 + tol {
+                found.push(violation(
+                    Invariant::MonotoneUncertainty,
+                    Some(key),
+                    format!("u = {} at r + s = {} exceeds u = {} at r + s = {}", u1, m1, u0, m0),
+                ));
+            }
+        }
+        for (hid, h) in &state.hyperedges {
+            if let Some(x) = h.e.data.iter().map(Scalar::to_f64).find(|x| !(*x >= 0.0 && x.is_finite())) {
+                found.push(Violation {
+                    hyperedge: Some(hid.clone()),
+                    ..violation(Invariant::NonNegativeEvidence, None, format!("evidence entry {}", x))
+                });
+            }
+        }
+        self.report(found)
+    }
+
+    /// Invariant 5: no channel mass of any edge grew in magnitude between `before` (channel
+    /// masses before decay) and `state`.
+    pub(crate) fn check_decay<S: Scalar>(
+        &mut self,
+        before: &BTreeMap<EdgeKey, Array1<f64>>,
+        state: &EqbslState<S>,
+        params: &Params,
+    ) -> Result<(), String> {
+        let mut found = Vec::new();
+        if let Some(b) = params.decay_beta.iter().find(|b| !(**b >= 0.0 && **b <= 1.0)) {
+            found.push(Violation {
+                invariant: Invariant::DecayNonIncreasing,
+                operator: Operator::Decay,
+                t: state.t,
+                edge: None,
+                hyperedge: None,
+                agent: None,
+                detail: format!("decay factor β = {}", b),
+            });
+        }
+        for (key, old) in before {
+            let new = state.edges.get(key).map(|e| e.channel_masses().mapv(|x| x.to_f64()));
+            let grew = match &new {
+                Some(new) => new
+                    .iter()
+                    .zip(old.iter())
+                    .any(|(n, o)| n.abs() > o.abs() + self.tolerance * o.abs().max(1.0)),
+                None => false,
+            };
+            if grew {
+                found.push(Violation {
+                    invariant: Invariant::DecayNonIncreasing,
+                    operator: Operator::Decay,
+                    t: state.t,
+                    edge: Some(key.clone()),
+                    hyperedge: None,
+                    agent: None,
+                    detail: format!("channel masses {} -> {}", old, new.expect("grew implies present")),
+                });
+            }
+        }
+        self.report(found)
+    }
+
+    /// Invariant 6 (and 1) on propagated evidence: every (r, s) is finite, non-negative and
+    /// within the bound B_D of depth D, where B_0 = M is the largest direct r + s and
+    /// B_{n+1} = M + λ W B_n for the largest witness set W (δ ≤ 1, and every aggregation
+    /// mode returns at most the sum of its |N(i)| reports' bound).
+    pub fn check_propagation(
+        &mut self,
+        state: &EqbslState,
+        params: &Params,
+        propagated: &BTreeMap<EdgeKey, (f64, f64)>,
+    ) -> Result<(), String> {
+        let direct = operators::direct_rs(state, params)?;
+        let opinions = operators::lift_opinions(state, params)?;
+        let witnesses = operators::witness_sets(&opinions, params);
+        let max_direct = direct.values().map(|(r, s)| r + s).fold(0.0, f64::max);
+        let max_witnesses = witnesses.values().map(Vec::len).max().unwrap_or(0) as f64;
+        let mut bound = max_direct;
+        for _ in 0..params.propagation_depth {
+            bound = max_direct + params.damping_lambda * max_witnesses * bound;
+        }
+        let bound = bound * (1.0 + self.tolerance);
+
+        let mut found = Vec::new();
+        for (key, &(r, s)) in propagated {
+            let (invariant, detail) = if !(r >= 0.0 && s >= 0.0 && r.is_finite() && s.is_finite()) {
+                (Invariant::NonNegativeEvidence, format!("propagated (r, s) = ({}, {})", r, s))
+            } else if r + s > bound {
+                (Invariant::BoundedPropagation, format!("propagated r + s = {} exceeds bound {}", r + s, bound))
+            } else {
+                continue;
+            };
+            found.push(Violation {
+                invariant,
+                operator: Operator::Propagate,
+                t: state.t,
+                edge: Some(key.clone()),
+                hyperedge: None,
+                agent: Some(key.0.clone()),
+                detail,
+            });
+        }
+        self.report(found)
+    }
+
+    fn report(&mut self, found: Vec<Violation>) -> Result<(), String> {
+        self.checks += 1;
+        match (self.mode, found.first()) {
+            (AuditMode::Strict, Some(first)) => Err(first.to_string()),
+            _ => {
+                self.violations.extend(found);
+                Ok(())
+            }
+        }
+    }
+}
@@ -15,7 +15,7 @@ pub struct CategorySummary {
 }
 
 /// The output from the Labeling LLM (Section 5).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LabelInfo {
     /// A short handle (e.g. "high-risk flaky OTC counterparty")
     pub handle: String,
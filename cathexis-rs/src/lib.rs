@@ -1,9 +1,41 @@
 pub mod core;
+pub mod numeric;
+pub mod exact;
 pub mod eqbsl;
+pub mod schema;
+pub mod state;
+pub mod events;
+pub mod provenance;
+pub mod dispute;
+pub mod operators;
+pub mod witness;
+pub mod aggregation;
+pub mod history;
+pub mod canonical;
+pub mod merkle;
+pub mod proof;
+pub mod r1cs;
+pub mod threshold;
+pub mod replay;
+pub mod watermark;
+pub mod incremental;
+pub mod invariants;
+pub mod audit;
+pub mod sparse;
+pub mod sybil;
+pub mod whitewash;
+pub mod anomaly;
+pub mod engine;
+pub mod explain;
 pub mod features;
 pub mod categoriser;
 pub mod labeling;
 pub mod pipeline;
+pub mod attestation;
+mod parallel;
 
 pub use core::{Opinion, Evidence};
 pub use eqbsl::TrustEmbedding;
+pub use schema::{ChannelSchema, ChannelSpec, Polarity};
+pub use state::{EqbslState, Params};
+pub use engine::EqbslEngine;
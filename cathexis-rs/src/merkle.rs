@@ -0,0 +1,194 @@
+use crate::canonical::Digest;
+use crate::eqbsl::EvidenceTensor;
+use crate::numeric::Scalar;
+use crate::state::{EdgeKey, EqbslState, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Digest of an empty subtree.
+pub const EMPTY: Digest = Digest([0; 32]);
+
+/// Sparse Merkle tree over the edge field E_t, keyed by the hash of (i, j) (Primer Section 10).
+///
+/// Each edge sits on the 256-bit path `Digest::of("eqbsl/smt/key/v1", (i, j))`. A subtree
+/// holding no edge hashes to `EMPTY`, a subtree holding one edge to that edge's leaf digest
+/// (which commits to the full key), and any other subtree to the node digest of its two
+/// halves; so the root only depends on the set of (key, evidence) pairs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMerkleTree {
+    /// (path, key, leaf digest), sorted by path.
+    leaves: Vec<(Digest, EdgeKey, Digest)>,
+    root: Digest,
+}
+
+impl SparseMerkleTree {
+    pub fn from_edges<S: Scalar>(edges: &BTreeMap<EdgeKey, EvidenceTensor<S>>) -> Self {
+        let mut leaves: Vec<(Digest, EdgeKey, Digest)> = edges
+            .iter()
+            .map(|(key, e)| (key_path(key), key.clone(), leaf_digest(key, e)))
+            .collect();
+        leaves.sort();
+        let root = subtree(&leaves, 0);
+        Self { leaves, root }
+    }
+
+    /// Brings the tree up to date with `edges` after the edges in `changed` were added,
+    /// updated or removed. Only their leaf digests are recomputed; the other leaves are
+    /// kept.
+    pub fn update<'a, S: Scalar>(
+        &mut self,
+        edges: &BTreeMap<EdgeKey, EvidenceTensor<S>>,
+        changed: impl IntoIterator<Item = &'a EdgeKey>,
+    ) {
+        for key in changed {
+            let path = key_path(key);
+            let found = self.leaves.binary_search_by(|leaf| leaf.0.cmp(&path));
+            match (found, edges.get(key)) {
+                (Ok(p), Some(e)) => self.leaves[p].2 = leaf_digest(key, e),
+                (Ok(p), None) => {
+                    self.leaves.remove(p);
+                }
+                (Err(p), Some(e)) => self.leaves.insert(p, (path, key.clone(), leaf_digest(key, e))),
+                (Err(_), None) => {}
+            }
+        }
+        self.root = subtree(&self.leaves, 0);
+    }
+
+    pub fn root(&self) -> Digest {
+        self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Inclusion proof of edge (src, dst), or `None` if the tree does not hold it.
+    pub fn prove(&self, src: &str, dst: &str) -> Option<InclusionProof> {
+        let key = (src.to_string(), dst.to_string());
+        let path = key_path(&key);
+        let mut slice = &self.leaves[..];
+        let mut siblings = Vec::new();
+        let mut depth = 0;
+        while slice.len() > 1 {
+            let mid = slice.partition_point(|leaf| !bit(&leaf.0, depth));
+            let (left, right) = slice.split_at(mid);
+            let (own, other) = if bit(&path, depth) { (right, left) } else { (left, right) };
+            siblings.push(subtree(other, depth + 1));
+            slice = own;
+            depth += 1;
+        }
+        match slice {
+            [(_, k, _)] if *k == key => Some(InclusionProof { key, siblings }),
+            _ => None,
+        }
+    }
+}
+
+/// Proof that an edge's evidence is in the tree with a given root: the sibling digests on
+/// the edge's path, root first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub key: EdgeKey,
+    pub siblings: Vec<Digest>,
+}
+
+impl InclusionProof {
+    /// Whether `e` is the evidence of `self.key` in the tree with root `root`.
+    pub fn verify<S: Scalar>(&self, root: &Digest, e: &EvidenceTensor<S>) -> bool {
+        if self.siblings.len() > 256 {
+            return false;
+        }
+        let path = key_path(&self.key);
+        let mut acc = leaf_digest(&self.key, e);
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            acc = if bit(&path, depth) { node(sibling, &acc) } else { node(&acc, sibling) };
+        }
+        acc == *root
+    }
+}
+
+/// Com(E_t): a commitment to the state at time t and the parameters it evolves under
+/// (Primer Section 10). Publish it; counterparties check single edges against `edges`
+/// with an `InclusionProof`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateCommitment {
+    pub t: i64,
+    /// Root of the sparse Merkle tree over the edges.
+    pub edges: Digest,
+    /// Digest of the hyperedge field.
+    pub hyperedges: Digest,
+    /// `Params::digest` of θ.
+    pub params: Digest,
+    /// Digest of the four fields above.
+    pub root: Digest,
+}
+
+impl StateCommitment {
+    pub fn new<S: Scalar>(state: &EqbslState<S>, params: &Params) -> Self {
+        Self::with_tree(state, params, &SparseMerkleTree::from_edges(&state.edges))
+    }
+
+    /// `new` with the edge root taken from `tree`, which must hold `state.edges`.
+    pub fn with_tree<S: Scalar>(state: &EqbslState<S>, params: &Params, tree: &SparseMerkleTree) -> Self {
+        Self::from_parts(
+            state.t,
+            tree.root(),
+            Digest::of("eqbsl/hyperedges/v1", &(S::label(), &state.hyperedges)),
+            params.digest(),
+        )
+    }
+
+    fn from_parts(t: i64, edges: Digest, hyperedges: Digest, params: Digest) -> Self {
+        Self {
+            t,
+            edges,
+            hyperedges,
+            params,
+            root: Digest::of("eqbsl/commitment/v1", &(t, (edges, hyperedges), params)),
+        }
+    }
+
+    /// Whether `root` is the digest of the other fields.
+    pub fn is_consistent(&self) -> bool {
+        *self == Self::from_parts(self.t, self.edges, self.hyperedges, self.params)
+    }
+
+    /// Whether `e` is the evidence of the proof's edge under this commitment.
+    pub fn verify_edge<S: Scalar>(&self, proof: &InclusionProof, e: &EvidenceTensor<S>) -> bool {
+        self.is_consistent() && proof.verify(&self.edges, e)
+    }
+}
+
+fn key_path(key: &EdgeKey) -> Digest {
+    Digest::of("eqbsl/smt/key/v1", key)
+}
+
+fn leaf_digest<S: Scalar>(key: &EdgeKey, e: &EvidenceTensor<S>) -> Digest {
+    Digest::of("eqbsl/smt/leaf/v1", &(S::label(), key, e))
+}
+
+fn node(left: &Digest, right: &Digest) -> Digest {
+    Digest::of("eqbsl/smt/node/v1", &(left, right))
+}
+
+/// Bit `depth` of a path, most significant first.
+fn bit(path: &Digest, depth: usize) -> bool {
+    path.0[depth / 8] >> (7 - depth % 8) & 1 == 1
+}
+
+fn subtree(leaves: &[(Digest, EdgeKey, Digest)], depth: usize) -> Digest {
+    match leaves {
+        [] => EMPTY,
+        [(_, _, leaf)] => *leaf,
+        _ => {
+            assert!(depth < 256, "distinct edge keys with equal paths");
+            let mid = leaves.partition_point(|leaf| !bit(&leaf.0, depth));
+            node(&subtree(&leaves[..mid], depth + 1), &subtree(&leaves[mid..], depth + 1))
+        }
+    }
+}
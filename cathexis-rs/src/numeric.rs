@@ -0,0 +1,263 @@
+use crate::canonical::Canonical;
+use ndarray::ScalarOperand;
+use num_traits::{One, Zero};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Number type of the evidence field, opinions and update operators (Primer Sections 1-6).
+///
+/// `Params` and incoming events stay in f64; operators convert them with `from_f64` at the
+/// point of use, so a state of any `Scalar` evolves under the same parameters and events.
+/// Only `Clone` is required, so arbitrary-precision types (`exact::Rational`) qualify.
+pub trait Scalar:
+    Clone
+    + Zero
+    + One
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + PartialOrd
+    + fmt::Debug
+    + fmt::Display
+    + Send
+    + Sync
+    + 'static
+    + Canonical
+    + Serialize
+    + DeserializeOwned
+{
+    /// Conversion from f64, the entry point of parameters and event masses.
+    fn from_f64(x: f64) -> Self;
+
+    /// Conversion to f64, for reporting and for the analyses that run in f64.
+    fn to_f64(&self) -> f64;
+
+    /// Name of the number type, e.g. "f64" or "Q31.32".
+    fn label() -> String;
+
+    /// Total order used wherever values are sorted.
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+
+    /// x^n by binary exponentiation, so β^dt only depends on the type's multiplication.
+    fn pow_steps(self, mut n: u64) -> Self {
+        let mut acc = Self::one();
+        let mut x = self;
+        while n > 0 {
+            if n & 1 == 1 {
+                acc *= x.clone();
+            }
+            n >>= 1;
+            if n > 0 {
+                x = x.clone() * x;
+            }
+        }
+        acc
+    }
+}
+
+impl Scalar for f64 {
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn label() -> String {
+        "f64".to_string()
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+/// Signed fixed-point number with `FRAC` fractional bits stored in an `i64`, i.e. a
+/// Q(63-FRAC).FRAC value `raw / 2^FRAC`. Every operation is integer arithmetic, so results
+/// are bit-identical on every machine. Rounding rules:
+///
+/// - `+` and `-` are exact.
+/// - `*` and `/` compute the exact result in i128 and round it to the nearest multiple of
+///   2^-FRAC, ties away from zero.
+/// - `from_f64` rounds the same way (`f64::round` on x · 2^FRAC); NaN converts to zero.
+/// - Results outside the i64 range saturate at `Fixed::MIN` / `Fixed::MAX`.
+///
+/// Division by zero panics, like integer division.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Fixed<const FRAC: u32 = 32>(pub i64);
+
+/// 31 integer bits, 32 fractional bits: resolution 2^-32 ≈ 2.3e-10, range ±2^31.
+pub type Q32 = Fixed<32>;
+
+impl<const FRAC: u32> Fixed<FRAC> {
+    pub const MIN: Self = Self(i64::MIN);
+    pub const MAX: Self = Self(i64::MAX);
+    /// Smallest positive value 2^-FRAC.
+    pub const EPSILON: Self = Self(1);
+    const ONE: i64 = {
+        assert!(FRAC > 0 && FRAC < 63, "Fixed needs 0 < FRAC < 63");
+        1 << FRAC
+    };
+
+    pub fn from_raw(raw: i64) -> Self {
+        Self(raw)
+    }
+
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+
+    fn saturate(x: i128) -> Self {
+        Self(x.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+/// n / d rounded to nearest, ties away from zero.
+fn div_round(n: i128, d: i128) -> i128 {
+    let (q, r) = (n / d, n % d);
+    if 2 * r.abs() >= d.abs() {
+        if (n < 0) == (d < 0) {
+            q + 1
+        } else {
+            q - 1
+        }
+    } else {
+        q
+    }
+}
+
+impl<const FRAC: u32> Add for Fixed<FRAC> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl<const FRAC: u32> Sub for Fixed<FRAC> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl<const FRAC: u32> Mul for Fixed<FRAC> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::saturate(div_round(self.0 as i128 * rhs.0 as i128, Self::ONE as i128))
+    }
+}
+
+impl<const FRAC: u32> Div for Fixed<FRAC> {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        assert!(rhs.0 != 0, "Fixed-point division by zero");
+        Self::saturate(div_round((self.0 as i128) << FRAC, rhs.0 as i128))
+    }
+}
+
+impl<const FRAC: u32> Neg for Fixed<FRAC> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl<const FRAC: u32> AddAssign for Fixed<FRAC> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const FRAC: u32> SubAssign for Fixed<FRAC> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const FRAC: u32> MulAssign for Fixed<FRAC> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl<const FRAC: u32> Zero for Fixed<FRAC> {
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl<const FRAC: u32> One for Fixed<FRAC> {
+    fn one() -> Self {
+        Self(Self::ONE)
+    }
+}
+
+impl<const FRAC: u32> ScalarOperand for Fixed<FRAC> {}
+
+impl<const FRAC: u32> Canonical for Fixed<FRAC> {
+    /// The raw integer, big-endian.
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+    }
+}
+
+impl<const FRAC: u32> fmt::Display for Fixed<FRAC> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_f64())
+    }
+}
+
+impl<const FRAC: u32> Scalar for Fixed<FRAC> {
+    fn from_f64(x: f64) -> Self {
+        // `as` saturates and maps NaN to 0
+        Self((x * Self::ONE as f64).round() as i64)
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.0 as f64 / Self::ONE as f64
+    }
+
+    fn label() -> String {
+        format!("Q{}.{}", 63 - FRAC, FRAC)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_rounding() {
+        type Q2 = Fixed<2>; // quarters
+        let q = |x: f64| Q2::from_f64(x);
+        // 0.75 * 0.25 = 0.1875 -> 0.25 (nearest); 0.5 * 0.25 = 0.125 -> 0.25 (tie, away from zero)
+        assert_eq!(q(0.75) * q(0.25), q(0.25));
+        assert_eq!(q(0.5) * q(0.25), q(0.25));
+        assert_eq!(q(-0.5) * q(0.25), q(-0.25));
+        // 1 / 3 = 0.333 -> 0.25; 1.5 / 4 = 0.375 -> 0.5 (tie)
+        assert_eq!(q(1.0) / q(3.0), q(0.25));
+        assert_eq!(q(1.5) / q(4.0), q(0.5));
+        assert_eq!(q(-1.5) / q(4.0), q(-0.5));
+        assert_eq!(q(0.125), q(0.25));
+        assert_eq!(Q2::from_f64(f64::NAN), Q2::zero());
+        assert_eq!(Q2::MAX + Q2::one(), Q2::MAX);
+        assert_eq!(Q2::MAX * q(2.0), Q2::MAX);
+        assert_eq!(Q32::label(), "Q31.32");
+        assert_eq!(Q32::from_f64(0.5).pow_steps(3), Q32::from_f64(0.125));
+    }
+}
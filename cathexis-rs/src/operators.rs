@@ -0,0 +1,570 @@
+use crate::core::Opinion;
+use crate::dispute;
+use crate::eqbsl::{EvidenceTensor, TrustEmbedding};
+use crate::events::{Event, StepRecord};
+use crate::invariants::Auditor;
+use crate::numeric::Scalar;
+use crate::parallel;
+use crate::state::{EdgeKey, EqbslState, Hyperedge, NodeId, Params};
+use crate::witness;
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The sub-operators of F_θ and Γ_ψ, in pipeline order (Primer Section 3), followed by
+/// the ledger operations that change the state outside a regular step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Decay,
+    Ingest,
+    Attribute,
+    Propagate,
+    Lift,
+    Embed,
+    /// A dispute action (`dispute::apply`).
+    Dispute,
+    /// A replay of logged steps (late-event amendment).
+    Replay,
+}
+
+/// Hyperedge evidence ingested in the current step, awaiting attribution to pairs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperDelta<S = f64> {
+    pub event_id: String,
+    pub t: i64,
+    pub hid: String,
+    pub de: EvidenceTensor<S>,
+}
+
+/// Checks a batch of events against the state before any operator runs, so a step
+/// either applies completely or not at all. Event ids must be unique within the batch
+/// and, when the state tracks provenance, not reuse an id it still tracks.
+pub fn validate_events<S: Scalar>(state: &EqbslState<S>, params: &Params, t: i64, events: &[Event]) -> Result<(), String> {
+    params.validate_for::<S>()?;
+    if t < state.t {
+        return Err(format!("Cannot step backwards from t={} to t={}", state.t, t));
+    }
+    let mut declared: BTreeMap<&str, &[NodeId]> = BTreeMap::new();
+    // Ids address events in provenance and disputes, so they must be unique
+    let mut ids: BTreeSet<&str> = BTreeSet::new();
+    let applied = match &state.provenance {
+        Some(p) if !events.is_empty() => p.event_ids(),
+        _ => BTreeSet::new(),
+    };
+    for event in events {
+        if !ids.insert(event.id()) {
+            return Err(format!("Duplicate event id '{}' in step t={}", event.id(), t));
+        }
+        if applied.contains(event.id()) {
+            return Err(format!("Event id '{}' has already been applied", event.id()));
+        }
+        if event.t() > t {
+            return Err(format!(
+                "Event '{}' at t={} is in the future of step t={}",
+                event.id(),
+                event.t(),
+                t
+            ));
+        }
+        let de = event.de();
+        if de.len() != params.channels() {
+            return Err(format!(
+                "Event '{}' has {} channels, params expect {}",
+                event.id(),
+                de.len(),
+                params.channels()
+            ));
+        }
+        if de.data.iter().any(|x| !(*x >= 0.0 && x.is_finite())) {
+            return Err(format!("Event '{}' has negative or non-finite evidence", event.id()));
+        }
+        match event {
+            Event::Pair(e) => {
+                if e.src == e.dst {
+                    return Err(format!("Event '{}' is a self-edge on '{}'", e.id, e.src));
+                }
+                if let Some(existing) = state.edge(&e.src, &e.dst) {
+                    if existing.shape() != de.shape() {
+                        return Err(format!(
+                            "Event '{}' has shape {:?}, edge ({}, {}) has {:?}",
+                            e.id,
+                            de.shape(),
+                            e.src,
+                            e.dst,
+                            existing.shape()
+                        ));
+                    }
+                }
+            }
+            Event::Hyper(e) => {
+                if let Some(h) = state.hyperedges.get(&e.hid) {
+                    if h.e.shape() != de.shape() {
+                        return Err(format!(
+                            "Event '{}' has shape {:?}, hyperedge '{}' has {:?}",
+                            e.id,
+                            de.shape(),
+                            e.hid,
+                            h.e.shape()
+                        ));
+                    }
+                }
+                let known = state
+                    .hyperedges
+                    .get(&e.hid)
+                    .map(|h| h.nodes.as_slice())
+                    .or_else(|| declared.get(e.hid.as_str()).copied());
+                match known {
+                    Some(nodes) => {
+                        if !e.nodes.is_empty() && e.nodes != nodes {
+                            return Err(format!(
+                                "Event '{}' redeclares hyperedge '{}' with different nodes",
+                                e.id, e.hid
+                            ));
+                        }
+                    }
+                    None => {
+                        if e.nodes.is_empty() {
+                            return Err(format!("Unknown hyperedge id: {}", e.hid));
+                        }
+                        Hyperedge::new(&e.hid, e.nodes.clone(), de.clone())?;
+                        declared.insert(&e.hid, &e.nodes);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decay operator e ← β^dt ⊙ e on every edge and hyperedge (Primer Section 5).
+pub fn decay<S: Scalar>(state: &mut EqbslState<S>, params: &Params, dt: u64) -> Result<(), String> {
+    if dt == 0 {
+        return Ok(());
+    }
+    let beta_dt: Vec<S> = params.decay_factors(dt);
+    for e in state.edges.values_mut() {
+        e.scale_channels(&beta_dt)?;
+    }
+    for h in state.hyperedges.values_mut() {
+        h.e.scale_channels(&beta_dt)?;
+    }
+    if let Some(provenance) = &mut state.provenance {
+        provenance.decay(&beta_dt.iter().map(Scalar::to_f64).collect::<Vec<_>>());
+    }
+    Ok(())
+}
+
+/// Ingest operator: adds event deltas at the current state time. Each delta is first
+/// decayed by β^(t - t_event), so an event ingested late carries the mass it would have
+/// had if it had arrived on time. Returns hyperedge deltas for attribution.
+pub fn ingest<S: Scalar>(
+    state: &mut EqbslState<S>,
+    params: &Params,
+    events: &[Event],
+) -> Result<Vec<HyperDelta<S>>, String> {
+    let mut hyper_deltas = Vec::new();
+    for event in events {
+        let mut de: EvidenceTensor<S> = event.de().cast();
+        let lag = (state.t - event.t()).max(0) as u64;
+        if lag > 0 {
+            de.scale_channels(&params.decay_factors(lag))?;
+        }
+        match event {
+            Event::Pair(e) => {
+                let key = (e.src.clone(), e.dst.clone());
+                if let Some(provenance) = &mut state.provenance {
+                    provenance.record(&key, &e.id, e.t, None, &de);
+                }
+                match state.edges.get_mut(&key) {
+                    Some(existing) => existing.add_assign(&de)?,
+                    None => {
+                        state.edges.insert(key, de);
+                    }
+                }
+            }
+            Event::Hyper(e) => {
+                if !state.hyperedges.contains_key(&e.hid) {
+                    let mut h = Hyperedge::new(&e.hid, e.nodes.clone(), EvidenceTensor::zeros_shaped(de.shape())?)?;
+                    h.roles = e.roles.clone();
+                    state.hyperedges.insert(e.hid.clone(), h);
+                }
+                let h = state.hyperedges.get_mut(&e.hid).expect("hyperedge registered above");
+                h.e.add_assign(&de)?;
+                hyper_deltas.push(HyperDelta {
+                    event_id: e.id.clone(),
+                    t: e.t,
+                    hid: e.hid.clone(),
+                    de,
+                });
+            }
+        }
+    }
+    Ok(hyper_deltas)
+}
+
+/// Hyperedge attribution e_ij += α_ijh * Δe_h for every ordered pair in h (Primer Section 6).
+/// Only the step's deltas are attributed, so repeated steps never double-count e_h.
+pub fn attribute<S: Scalar>(state: &mut EqbslState<S>, deltas: &[HyperDelta<S>]) -> Result<(), String> {
+    for delta in deltas {
+        let h = state
+            .hyperedges
+            .get(&delta.hid)
+            .ok_or_else(|| format!("Unknown hyperedge id: {}", delta.hid))?
+            .clone();
+        for i in &h.nodes {
+            for j in &h.nodes {
+                let alpha = h.alpha(i, j);
+                if alpha <= S::zero() {
+                    continue;
+                }
+                let mut scaled = delta.de.clone();
+                scaled.data.mapv_inplace(|x| x * alpha.clone());
+                if let Some(provenance) = &mut state.provenance {
+                    provenance.record(&(i.clone(), j.clone()), &delta.event_id, delta.t, Some(&delta.hid), &scaled);
+                }
+                match state.edges.get_mut(&(i.clone(), j.clone())) {
+                    Some(existing) => existing.add_assign(&scaled)?,
+                    None => {
+                        state.edges.insert((i.clone(), j.clone()), scaled);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs F_θ for one step: decay prior evidence to `t`, ingest `events`, attribute hyperedges,
+/// then compact provenance if it is tracked.
+pub fn apply_step<S: Scalar>(state: &mut EqbslState<S>, params: &Params, t: i64, events: &[Event]) -> Result<(), String> {
+    run_step(state, params, t, events, None)
+}
+
+/// `apply_step` with the Primer Section 9 invariants checked after each operator. A strict
+/// auditor fails on the first violation, possibly leaving `state` mid-step.
+pub fn apply_step_audited<S: Scalar>(
+    state: &mut EqbslState<S>,
+    params: &Params,
+    t: i64,
+    events: &[Event],
+    auditor: &mut Auditor,
+) -> Result<(), String> {
+    run_step(state, params, t, events, Some(auditor))
+}
+
+fn run_step<S: Scalar>(
+    state: &mut EqbslState<S>,
+    params: &Params,
+    t: i64,
+    events: &[Event],
+    mut auditor: Option<&mut Auditor>,
+) -> Result<(), String> {
+    validate_events(state, params, t, events)?;
+    let before: Option<BTreeMap<EdgeKey, Array1<f64>>> = auditor.as_ref().map(|_| {
+        state
+            .edges
+            .iter()
+            .map(|(key, e)| (key.clone(), e.channel_masses().mapv(|x| x.to_f64())))
+            .collect()
+    });
+    decay(state, params, (t - state.t) as u64)?;
+    state.t = t;
+    if let (Some(auditor), Some(before)) = (auditor.as_deref_mut(), &before) {
+        auditor.check_decay(before, state, params)?;
+        auditor.check_state(state, params, Operator::Decay)?;
+    }
+    let hyper_deltas = ingest(state, params, events)?;
+    if let Some(auditor) = auditor.as_deref_mut() {
+        auditor.check_state(state, params, Operator::Ingest)?;
+    }
+    attribute(state, &hyper_deltas)?;
+    if let Some(auditor) = auditor {
+        auditor.check_state(state, params, Operator::Attribute)?;
+    }
+    if let Some(provenance) = &mut state.provenance {
+        provenance.compact();
+    }
+    Ok(())
+}
+
+/// Applies a logged step: `apply_step` with its events, then its dispute actions.
+pub fn apply_record(state: &mut EqbslState, params: &Params, record: &StepRecord) -> Result<(), String> {
+    apply_step(state, params, record.t, &record.events)?;
+    for action in &record.disputes {
+        dispute::apply(state, params, action)?;
+    }
+    Ok(())
+}
+
+/// Direct (r_ij, s_ij) for every edge.
+pub fn direct_rs(state: &EqbslState, params: &Params) -> Result<BTreeMap<EdgeKey, (f64, f64)>, String> {
+    state
+        .edges
+        .iter()
+        .map(|(key, e)| Ok((key.clone(), params.rs(e)?)))
+        .collect()
+}
+
+/// Opinion lift ω_ij from direct evidence (Primer Section 1.5).
+pub fn lift_opinions(state: &EqbslState, params: &Params) -> Result<BTreeMap<EdgeKey, Opinion>, String> {
+    Ok(direct_rs(state, params)?
+        .into_iter()
+        .map(|(key, (r, s))| (key, params.lift(r, s)))
+        .collect())
+}
+
+/// Per-source rows of (r, s): `rows[i][j] = (r_ij, s_ij)`.
+pub type RsRows = BTreeMap<NodeId, BTreeMap<NodeId, (f64, f64)>>;
+
+/// Witness sets N(i) as (δ_ik, k), in selection order.
+pub type WitnessMap = BTreeMap<NodeId, Vec<(f64, NodeId)>>;
+
+/// Groups an edge-keyed (r, s) map into per-source rows.
+pub fn rs_rows(rs: &BTreeMap<EdgeKey, (f64, f64)>) -> RsRows {
+    let mut rows = RsRows::new();
+    for ((i, j), &v) in rs {
+        rows.entry(i.clone()).or_default().insert(j.clone(), v);
+    }
+    rows
+}
+
+/// Flattens per-source rows back into an edge-keyed map.
+pub fn flatten_rows(rows: &RsRows) -> BTreeMap<EdgeKey, (f64, f64)> {
+    rows.iter()
+        .flat_map(|(i, row)| row.iter().map(move |(j, &v)| ((i.clone(), j.clone()), v)))
+        .collect()
+}
+
+/// Witness set N(i) from i's outgoing opinions: ranked by discount δ_ik = E(ω_ik), ties
+/// broken by node id, filtered by `params.witness_policy` and capped at top-K.
+pub fn witness_set<'a>(
+    source: &str,
+    outgoing: impl IntoIterator<Item = (&'a NodeId, &'a Opinion)>,
+    params: &Params,
+) -> Vec<(f64, NodeId)> {
+    witness::select_witnesses(source, outgoing, params.witness_policy.as_slice(), params.witness_top_k)
+}
+
+/// Witness sets N(i) for every source.
+pub fn witness_sets(opinions: &BTreeMap<EdgeKey, Opinion>, params: &Params) -> WitnessMap {
+    let mut by_source: BTreeMap<&NodeId, Vec<(&NodeId, &Opinion)>> = BTreeMap::new();
+    for ((i, k), op) in opinions {
+        by_source.entry(i).or_default().push((k, op));
+    }
+    by_source
+        .into_iter()
+        .map(|(i, outgoing)| (i.clone(), witness_set(i, outgoing, params)))
+        .collect()
+}
+
+/// Transitive aggregation in (r, s) space (Primer Section 7), iterated `propagation_depth` times:
+/// rs^{n+1}_ij = rs^direct_ij + Σ_{k∈N(i)} λ δ_ik rs^n_kj, where the sum over witnesses is
+/// replaced by `params.aggregation` when a robust mode is selected.
+pub fn propagate(state: &EqbslState, params: &Params) -> Result<BTreeMap<EdgeKey, (f64, f64)>, String> {
+    let direct = direct_rs(state, params)?;
+    let opinions: BTreeMap<EdgeKey, Opinion> = direct
+        .iter()
+        .map(|(key, &(r, s))| (key.clone(), params.lift(r, s)))
+        .collect();
+    let witnesses = witness_sets(&opinions, params);
+    let direct = rs_rows(&direct);
+    let sources: BTreeSet<NodeId> = direct.keys().cloned().collect();
+    Ok(flatten_rows(&propagate_rows(&direct, &witnesses, params, &sources)))
+}
+
+/// Propagated rows for the given sources only. Row i at depth n needs the rows of its
+/// witnesses at depth n-1, so only the sources reachable from `sources` through witness
+/// links within `propagation_depth` hops are evaluated. The arithmetic per entry is the
+/// same as for a full propagation, so results are bit-identical. Rows of one level are
+/// independent and are evaluated in parallel with the `parallel` feature.
+pub fn propagate_rows(direct: &RsRows, witnesses: &WitnessMap, params: &Params, sources: &BTreeSet<NodeId>) -> RsRows {
+    let depth = params.propagation_depth;
+    // levels[n] = rows needed at depth (depth - n)
+    let mut levels: Vec<BTreeSet<NodeId>> = vec![sources.clone()];
+    for _ in 0..depth {
+        let prev = levels.last().expect("levels is never empty");
+        let mut next = prev.clone();
+        for i in prev {
+            for (_, k) in witnesses.get(i).into_iter().flatten() {
+                next.insert(k.clone());
+            }
+        }
+        levels.push(next);
+    }
+
+    let mut current: RsRows = levels[depth]
+        .iter()
+        .filter_map(|i| direct.get(i).map(|row| (i.clone(), row.clone())))
+        .collect();
+    for level in (0..depth).rev() {
+        let rows: Vec<&NodeId> = levels[level].iter().collect();
+        let computed = parallel::map(&rows, |&i| {
+            let panel = witnesses.get(i).map_or(0, Vec::len);
+            let mut indirect: BTreeMap<&NodeId, Vec<(f64, f64)>> = BTreeMap::new();
+            for (delta_ik, k) in witnesses.get(i).into_iter().flatten() {
+                let w = params.damping_lambda * delta_ik;
+                for (j, &(rk, sk)) in current.get(k).into_iter().flatten() {
+                    if j == i || (rk == 0.0 && sk == 0.0) {
+                        continue;
+                    }
+                    indirect.entry(j).or_default().push((w * rk, w * sk));
+                }
+            }
+            let mut row = direct.get(i).cloned().unwrap_or_default();
+            for (j, contributions) in indirect {
+                let (rind, sind) = params.aggregation.aggregate(&contributions, panel);
+                let entry = row.entry(j.clone()).or_insert((0.0, 0.0));
+                *entry = (entry.0 + rind, entry.1 + sind);
+            }
+            (i.clone(), row)
+        });
+        current = computed.into_iter().filter(|(_, row)| !row.is_empty()).collect();
+    }
+    current.retain(|i, _| sources.contains(i));
+    current
+}
+
+/// Baseline embedding Γ (Primer Section 8, 12):
+/// [in_expect_mean, in_u_mean, out_expect_mean, out_u_mean, in_count, out_count].
+pub fn embed_node(opinions: &BTreeMap<EdgeKey, Opinion>, agent_id: &str) -> TrustEmbedding {
+    let mut acc = EmbeddingAcc::default();
+    for ((src, dst), op) in opinions {
+        if dst == agent_id {
+            acc.add_inbound(op);
+        }
+        if src == agent_id {
+            acc.add_outbound(op);
+        }
+    }
+    acc.finish()
+}
+
+/// Baseline embeddings for every node, in one pass over the opinions.
+pub fn embed_nodes(state: &EqbslState, params: &Params) -> Result<BTreeMap<NodeId, TrustEmbedding>, String> {
+    let opinions = lift_opinions(state, params)?;
+    let mut accs: BTreeMap<NodeId, EmbeddingAcc> = state
+        .nodes()
+        .into_iter()
+        .map(|n| (n, EmbeddingAcc::default()))
+        .collect();
+    for ((src, dst), op) in &opinions {
+        if let Some(acc) = accs.get_mut(dst) {
+            acc.add_inbound(op);
+        }
+        if let Some(acc) = accs.get_mut(src) {
+            acc.add_outbound(op);
+        }
+    }
+    Ok(accs.into_iter().map(|(n, acc)| (n, acc.finish())).collect())
+}
+
+#[derive(Default)]
+struct EmbeddingAcc {
+    in_exp: f64,
+    in_u: f64,
+    in_count: usize,
+    out_exp: f64,
+    out_u: f64,
+    out_count: usize,
+}
+
+impl EmbeddingAcc {
+    fn add_inbound(&mut self, op: &Opinion) {
+        self.in_exp += op.expected_probability();
+        self.in_u += op.u;
+        self.in_count += 1;
+    }
+
+    fn add_outbound(&mut self, op: &Opinion) {
+        self.out_exp += op.expected_probability();
+        self.out_u += op.u;
+        self.out_count += 1;
+    }
+
+    fn finish(self) -> TrustEmbedding {
+        let mean = |sum: f64, n: usize| if n > 0 { sum / n as f64 } else { 0.0 };
+        TrustEmbedding::new(vec![
+            mean(self.in_exp, self.in_count),
+            mean(self.in_u, self.in_count),
+            mean(self.out_exp, self.out_count),
+            mean(self.out_u, self.out_count),
+            self.in_count as f64,
+            self.out_count as f64,
+        ])
+    }
+}
+
+/// Undirected neighbour sets of every node.
+pub fn neighbours(state: &EqbslState) -> BTreeMap<&NodeId, BTreeSet<&NodeId>> {
+    let mut adj: BTreeMap<&NodeId, BTreeSet<&NodeId>> = BTreeMap::new();
+    for (i, j) in state.edges.keys() {
+        adj.entry(i).or_default().insert(j);
+        adj.entry(j).or_default().insert(i);
+    }
+    adj
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{HyperEvent, PairEvent};
+    use approx::assert_relative_eq;
+
+    fn params() -> Params {
+        Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.5, 1.0]).unwrap()
+    }
+
+    fn pair(id: &str, t: i64, src: &str, dst: &str, de: Vec<f64>) -> Event {
+        Event::Pair(PairEvent {
+            id: id.to_string(),
+            t,
+            src: src.to_string(),
+            dst: dst.to_string(),
+            de: EvidenceTensor::new(de),
+        })
+    }
+
+    #[test]
+    fn test_late_event_matches_on_time_event() {
+        let params = params();
+        let mut on_time = EqbslState::new(0);
+        apply_step(&mut on_time, &params, 1, &[pair("e1", 1, "A", "B", vec![8.0, 2.0])]).unwrap();
+        apply_step(&mut on_time, &params, 3, &[]).unwrap();
+
+        let mut late = EqbslState::new(0);
+        apply_step(&mut late, &params, 3, &[pair("e1", 1, "A", "B", vec![8.0, 2.0])]).unwrap();
+
+        assert_eq!(on_time, late);
+        assert_eq!(late.edge("A", "B").unwrap(), &EvidenceTensor::new(vec![2.0, 2.0]));
+    }
+
+    #[test]
+    fn test_attribution_and_propagation() {
+        let params = params();
+        let mut state = EqbslState::new(0);
+        let events = vec![
+            Event::Hyper(HyperEvent {
+                id: "h-open".to_string(),
+                t: 0,
+                hid: "h1".to_string(),
+                nodes: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+                roles: BTreeMap::new(),
+                de: EvidenceTensor::new(vec![6.0, 0.0]),
+            }),
+            pair("e1", 0, "B", "D", vec![4.0, 0.0]),
+        ];
+        apply_step(&mut state, &params, 0, &events).unwrap();
+
+        // 6 ordered pairs share Δe_h equally
+        assert_eq!(state.edge("A", "B").unwrap(), &EvidenceTensor::new(vec![1.0, 0.0]));
+        let total: f64 = state.edges.values().map(|e| e.channel_masses().sum()).sum();
+        assert_relative_eq!(total, 6.0 + 4.0);
+
+        // A learns about D through witness B: λ * E(ω_AB) * r_BD
+        let propagated = propagate(&state, &params).unwrap();
+        let delta_ab = params.lift(1.0, 0.0).expected_probability();
+        assert_relative_eq!(propagated[&("A".to_string(), "D".to_string())].0, 0.5 * delta_ab * 4.0);
+        assert!(apply_step(&mut state, &params, -1, &[]).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Data-parallel helpers behind the `parallel` feature. Every helper returns results in
+//! input order and each item is computed by the same sequential code, so output does not
+//! depend on the number of threads.
+
+/// Maps `f` over `items`, in parallel when the `parallel` feature is enabled.
+#[cfg(feature = "parallel")]
+pub(crate) fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    use rayon::prelude::*;
+    items.par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    F: Fn(&T) -> R,
+{
+    items.iter().map(f).collect()
+}
+
+/// Chunks per thread in `map_range_with`, for load balance.
+#[cfg(feature = "parallel")]
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// Maps `f` over `0..n` with scratch state created by `init`. The range is split into at
+/// most `CHUNKS_PER_THREAD` contiguous chunks per thread and `init` runs once per chunk,
+/// so large scratch (e.g. O(n) buffers) is allocated a bounded number of times.
+#[cfg(feature = "parallel")]
+pub(crate) fn map_range_with<S, R, I, F>(n: usize, init: I, f: F) -> Vec<R>
+where
+    R: Send,
+    I: Fn() -> S + Sync + Send,
+    F: Fn(&mut S, usize) -> R + Sync + Send,
+{
+    use rayon::prelude::*;
+    let chunk = n.div_ceil(rayon::current_num_threads() * CHUNKS_PER_THREAD).max(1);
+    let chunks: Vec<Vec<R>> = (0..n.div_ceil(chunk))
+        .into_par_iter()
+        .map(|c| {
+            let mut scratch = init();
+            (c * chunk..n.min((c + 1) * chunk)).map(|i| f(&mut scratch, i)).collect()
+        })
+        .collect();
+    chunks.into_iter().flatten().collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map_range_with<S, R, I, F>(n: usize, init: I, f: F) -> Vec<R>
+where
+    I: Fn() -> S,
+    F: Fn(&mut S, usize) -> R,
+{
+    let mut scratch = init();
+    (0..n).map(|i| f(&mut scratch, i)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_map_range_with_bounds_scratch_allocations() {
+        let inits = AtomicUsize::new(0);
+        let out = map_range_with(
+            10_000,
+            || {
+                inits.fetch_add(1, Ordering::Relaxed);
+                Vec::<usize>::new()
+            },
+            |scratch, i| {
+                scratch.push(i);
+                i * 2
+            },
+        );
+        assert_eq!(out, (0..10_000).map(|i| i * 2).collect::<Vec<_>>());
+        #[cfg(feature = "parallel")]
+        let bound = rayon::current_num_threads() * CHUNKS_PER_THREAD;
+        #[cfg(not(feature = "parallel"))]
+        let bound = 1;
+        assert!(inits.load(Ordering::Relaxed) <= bound);
+        assert!(map_range_with(0, Vec::<usize>::new, |_, i| i).is_empty());
+    }
+}
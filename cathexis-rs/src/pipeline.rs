@@ -1,10 +1,17 @@
+use crate::attestation::{HandleClaim, HandleSigner, SignedHandle};
 use crate::categoriser::Categoriser;
+use crate::engine::EqbslEngine;
 use crate::eqbsl::TrustGraph;
+use crate::explain::Explanation;
 use crate::labeling::{LabelingModel, LabelInfo, CategorySummary};
 use crate::features::FeatureState;
-use std::collections::HashMap;
+use crate::parallel;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use serde::{Deserialize, Serialize};
 
+/// Category assignments and feature states per agent.
+type Assignment = (BTreeMap<String, usize>, BTreeMap<String, FeatureState>);
+
 #[derive(Serialize, Deserialize)]
 pub struct AgentHandleResponse {
     pub category_id: usize,
@@ -12,6 +19,9 @@ pub struct AgentHandleResponse {
     pub label: String,
     pub description: String,
     pub guidance: Option<String>,
+    /// Why the querying agent trusts this agent, from `query_agent_handle_for`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -26,6 +36,15 @@ where
     pub labeler: L,
     /// Maps category ID to its LabelInfo
     pub category_labels: HashMap<usize, LabelInfo>,
+    /// Latest category assignment per agent, kept for incremental refresh.
+    #[serde(default)]
+    pub assignments: BTreeMap<String, usize>,
+    /// Latest feature state per agent, used to rebuild category summaries.
+    #[serde(default)]
+    pub features: BTreeMap<String, FeatureState>,
+    /// Test mode: every `refresh` is checked against a full recomputation.
+    #[serde(default)]
+    pub verify_incremental: bool,
 }
 
 impl<G, C, L> CathexisPipeline<G, C, L>
@@ -40,38 +59,98 @@ where
             categoriser,
             labeler,
             category_labels: HashMap::new(),
+            assignments: BTreeMap::new(),
+            features: BTreeMap::new(),
+            verify_incremental: false,
         }
     }
 
     /// Offline batch processing (Section 6).
     /// Computes features for all agents, assigns categories, and builds summaries/labels.
     pub fn batch_process(&mut self) -> Result<(), String> {
-        let nodes = self.graph.get_nodes();
-        let mut category_assignments: HashMap<String, usize> = HashMap::new();
-        let mut category_features: HashMap<usize, Vec<FeatureState>> = HashMap::new();
-
-        // 1. Compute features and assign categories
-        for agent_id in &nodes {
-            let features = self.graph.compute_features(agent_id)?;
-            let category_id = self.categoriser.predict(&features)?;
-            
-            category_assignments.insert(agent_id.clone(), category_id);
-            category_features.entry(category_id).or_default().push(features);
+        let (assignments, features) = self.assign(&self.graph.get_nodes())?;
+        self.assignments = assignments;
+        self.features = features;
+        self.category_labels.clear();
+
+        // Build summaries and generate labels
+        let categories: BTreeSet<usize> = self.assignments.values().copied().collect();
+        for category_id in categories {
+            self.relabel(category_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Incremental batch processing: recomputes features and categories for `agents` only
+    /// (e.g. `DirtySet::feature_agents` from the EQBSL engine) and relabels the categories
+    /// they left or joined. Equivalent to `batch_process` when `agents` covers every agent
+    /// whose features changed.
+    pub fn refresh<'a>(&mut self, agents: impl IntoIterator<Item = &'a String>) -> Result<(), String> {
+        let agents: Vec<String> = agents.into_iter().cloned().collect();
+        let (assignments, features) = self.assign(&agents)?;
+        let mut touched = BTreeSet::new();
+        for (agent_id, category_id) in assignments {
+            if let Some(previous) = self.assignments.insert(agent_id, category_id) {
+                touched.insert(previous);
+            }
+            touched.insert(category_id);
         }
+        self.features.extend(features);
 
-        // 2. Build summaries and generate labels
-        for (category_id, features_list) in category_features {
-            // Build summary (simplified implementation)
-            let summary = self.build_category_summary(category_id, &features_list);
-            
-            // Check if we need a new label (always for now)
-            let label_info = self.labeler.generate_label(&summary)?;
-            self.category_labels.insert(category_id, label_info);
+        for category_id in touched {
+            if self.assignments.values().any(|&k| k == category_id) {
+                self.relabel(category_id)?;
+            } else {
+                self.category_labels.remove(&category_id);
+            }
+        }
+
+        if self.verify_incremental {
+            let (full_assignments, full_features) = self.assign(&self.graph.get_nodes())?;
+            if full_assignments != self.assignments || full_features != self.features {
+                return Err("Incremental refresh diverged from full batch processing".to_string());
+            }
+            let categories: BTreeSet<usize> = full_assignments.values().copied().collect();
+            let labelled: BTreeSet<usize> = self.category_labels.keys().copied().collect();
+            if categories != labelled {
+                return Err(format!(
+                    "Incremental refresh labels categories {:?}, full batch processing labels {:?}",
+                    labelled, categories
+                ));
+            }
         }
 
         Ok(())
     }
 
+    /// Computes features and assigns categories for the given agents (in parallel with the
+    /// `parallel` feature; the result does not depend on the thread count).
+    fn assign(&self, agents: &[String]) -> Result<Assignment, String> {
+        let graph = &self.graph;
+        let states: Vec<FeatureState> = parallel::map(agents, |agent_id| graph.compute_features(agent_id))
+            .into_iter()
+            .collect::<Result<_, String>>()?;
+        let categories = self.categoriser.predict_batch(&states)?;
+        let assignments = agents.iter().cloned().zip(categories).collect();
+        let features = agents.iter().cloned().zip(states).collect();
+        Ok((assignments, features))
+    }
+
+    /// Rebuilds the summary of a category from its members and regenerates its label.
+    fn relabel(&mut self, category_id: usize) -> Result<(), String> {
+        let members: Vec<FeatureState> = self
+            .assignments
+            .iter()
+            .filter(|(_, &k)| k == category_id)
+            .filter_map(|(agent_id, _)| self.features.get(agent_id).cloned())
+            .collect();
+        let summary = self.build_category_summary(category_id, &members);
+        let label_info = self.labeler.generate_label(&summary)?;
+        self.category_labels.insert(category_id, label_info);
+        Ok(())
+    }
+
     /// Online query (Section 6).
     pub fn query_agent_handle(&self, agent_id: &str) -> Result<AgentHandleResponse, String> {
         let features = self.graph.compute_features(agent_id)?;
@@ -90,9 +169,23 @@ where
             label: label_info.handle.clone(),
             description: label_info.gloss.clone(),
             guidance: label_info.guidance.clone(),
+            explanation: None,
         })
     }
 
+    /// Online query from the point of view of `viewer`: the handle of `agent_id` together
+    /// with the top `limit` evidence paths behind viewer's trust in it.
+    pub fn query_agent_handle_for(&self, viewer: &str, agent_id: &str, limit: usize) -> Result<AgentHandleResponse, String> {
+        let mut response = self.query_agent_handle(agent_id)?;
+        response.explanation = Some(self.explain(viewer, agent_id, limit)?);
+        Ok(response)
+    }
+
+    /// "Why does `src` trust `dst`?": direct evidence and witness paths ranked by contribution.
+    pub fn explain(&self, src: &str, dst: &str, limit: usize) -> Result<Explanation, String> {
+        self.graph.explain(src, dst, limit)
+    }
+
     fn build_category_summary(&self, category_id: usize, features: &[FeatureState]) -> CategorySummary {
         // In a real implementation, this would compute means, deviations, etc.
         // It would also track drift in \mu_k(t) and membership to decide when to split, merge, or re-label categories (Section 5).
@@ -106,3 +199,24 @@ where
         }
     }
 }
+
+impl<C, L> CathexisPipeline<EqbslEngine, C, L>
+where
+    C: Categoriser,
+    L: LabelingModel,
+{
+    /// `query_agent_handle` signed by `signer` for offline verification by third parties,
+    /// bound to the root of Com(E_t) of the engine it was computed from and valid from
+    /// `issued_at` until `expires_at` (unix seconds).
+    pub fn query_signed_handle(
+        &self,
+        agent_id: &str,
+        signer: &HandleSigner,
+        issued_at: i64,
+        expires_at: i64,
+    ) -> Result<SignedHandle, String> {
+        let response = self.query_agent_handle(agent_id)?;
+        let state_root = self.graph.commitment().root;
+        Ok(signer.sign(HandleClaim::new(agent_id, &response, state_root, issued_at, expires_at)?))
+    }
+}
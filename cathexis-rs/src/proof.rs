@@ -0,0 +1,199 @@
+use crate::canonical::Digest;
+use crate::core::Opinion;
+use crate::events::Event;
+use crate::merkle::StateCommitment;
+use crate::operators;
+use crate::state::{EdgeKey, EqbslState, Params};
+use serde::{Deserialize, Serialize};
+
+/// Com(events_t): digest of a step's events in order.
+pub fn events_commitment(events: &[Event]) -> Digest {
+    Digest::of("eqbsl/events/v1", events)
+}
+
+/// Public outputs of an update step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateOutputs {
+    /// Com(E_t).
+    pub state: StateCommitment,
+    /// Disclosed opinions ω_ij(t) of selected edges.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub opinions: Vec<(EdgeKey, Opinion)>,
+}
+
+/// The statement of Primer Section 10.1: given Com(E_{t-1}), Com(events_t) and θ,
+/// E_t = F_θ(E_{t-1}, events_t) at time `t`, with the declared outputs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateStatement {
+    /// Com(E_{t-1}), which also commits to θ.
+    pub prior: StateCommitment,
+    /// Com(events_t).
+    pub events: Digest,
+    pub t: i64,
+    /// θ, in the clear.
+    pub params: Params,
+    pub outputs: UpdateOutputs,
+}
+
+/// Private witness of an update statement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateWitness {
+    /// E_{t-1}, without provenance (which the commitments do not cover).
+    pub prior: EqbslState,
+    pub events: Vec<Event>,
+}
+
+impl UpdateWitness {
+    pub fn new(prior: &EqbslState, events: Vec<Event>) -> Self {
+        let mut prior = prior.clone();
+        prior.provenance = None;
+        Self { prior, events }
+    }
+
+    /// Checks a decoded witness before F_θ runs on it: no provenance, and every prior tensor
+    /// has `params.channels()` channels and finite, non-negative entries. Events are checked
+    /// by `operators::validate_events`.
+    pub fn validate(&self, params: &Params) -> Result<(), String> {
+        if self.prior.provenance.is_some() {
+            return Err("Witness prior state must not carry provenance".to_string());
+        }
+        let tensors = self
+            .prior
+            .edges
+            .iter()
+            .map(|((i, j), e)| (format!("edge ({}, {})", i, j), e))
+            .chain(self.prior.hyperedges.iter().map(|(hid, h)| (format!("hyperedge '{}'", hid), &h.e)));
+        for (name, e) in tensors {
+            if e.rank() == 0 || e.len() != params.channels() {
+                return Err(format!("Witness {} has shape {:?}, params expect {} channels", name, e.shape(), params.channels()));
+            }
+            if e.data.iter().any(|x| !(*x >= 0.0 && x.is_finite())) {
+                return Err(format!("Witness {} has negative or non-finite evidence", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A proof in some proof system, opaque to everything but that system's verifier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proof {
+    /// Identifier of the proof system, e.g. `TransparentProver::SYSTEM`.
+    pub system: String,
+    pub data: Vec<u8>,
+}
+
+/// Produces proofs of update statements from their witnesses.
+pub trait Prover {
+    fn prove(&self, statement: &UpdateStatement, witness: &UpdateWitness) -> Result<Proof, String>;
+}
+
+/// Checks proofs of update statements without the witness.
+pub trait Verifier {
+    fn verify(&self, statement: &UpdateStatement, proof: &Proof) -> Result<(), String>;
+}
+
+/// A statement together with its proof, as published after each step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofCarryingUpdate {
+    pub statement: UpdateStatement,
+    pub proof: Proof,
+}
+
+impl ProofCarryingUpdate {
+    pub fn verify(&self, verifier: &impl Verifier) -> Result<(), String> {
+        verifier.verify(&self.statement, &self.proof)
+    }
+}
+
+/// Runs F_θ on `prior` and returns the resulting state with the statement of that step,
+/// disclosing the opinions of the `disclose` edges.
+pub fn state_update(
+    prior: &EqbslState,
+    params: &Params,
+    t: i64,
+    events: &[Event],
+    disclose: &[EdgeKey],
+) -> Result<(EqbslState, UpdateStatement), String> {
+    let mut next = prior.clone();
+    operators::apply_step(&mut next, params, t, events)?;
+    let statement = UpdateStatement {
+        prior: StateCommitment::new(prior, params),
+        events: events_commitment(events),
+        t,
+        params: params.clone(),
+        outputs: outputs(&next, params, disclose)?,
+    };
+    Ok((next, statement))
+}
+
+/// The outputs of `state`, disclosing the opinions of the `disclose` edges.
+pub fn outputs(state: &EqbslState, params: &Params, disclose: &[EdgeKey]) -> Result<UpdateOutputs, String> {
+    let opinions = disclose
+        .iter()
+        .map(|key| {
+            let e = state
+                .edges
+                .get(key)
+                .ok_or_else(|| format!("No edge ({}, {}) to disclose", key.0, key.1))?;
+            let (r, s) = params.rs(e)?;
+            Ok((key.clone(), params.lift(r, s)))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(UpdateOutputs {
+        state: StateCommitment::new(state, params),
+        opinions,
+    })
+}
+
+/// Reference proof system whose proof is the witness itself (JSON-encoded): nothing is
+/// hidden, and verification re-executes F_θ. It fixes the workflow and the interface that
+/// a zero-knowledge backend will implement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransparentProver;
+
+impl TransparentProver {
+    pub const SYSTEM: &'static str = "eqbsl/transparent/v1";
+}
+
+impl Prover for TransparentProver {
+    /// Refuses to prove a statement the witness does not satisfy.
+    fn prove(&self, statement: &UpdateStatement, witness: &UpdateWitness) -> Result<Proof, String> {
+        let proof = Proof {
+            system: Self::SYSTEM.to_string(),
+            data: serde_json::to_vec(witness).map_err(|e| format!("Cannot encode witness: {}", e))?,
+        };
+        self.verify(statement, &proof)?;
+        Ok(proof)
+    }
+}
+
+impl Verifier for TransparentProver {
+    fn verify(&self, statement: &UpdateStatement, proof: &Proof) -> Result<(), String> {
+        if proof.system != Self::SYSTEM {
+            return Err(format!("Expected a '{}' proof, got '{}'", Self::SYSTEM, proof.system));
+        }
+        let witness: UpdateWitness =
+            serde_json::from_slice(&proof.data).map_err(|e| format!("Malformed transparent proof: {}", e))?;
+        let params = &statement.params;
+        params.validate()?;
+        witness.validate(params)?;
+        if StateCommitment::new(&witness.prior, params) != statement.prior {
+            return Err("Prior state or params do not match the prior commitment".to_string());
+        }
+        if events_commitment(&witness.events) != statement.events {
+            return Err("Events do not match the events commitment".to_string());
+        }
+        let disclose: Vec<EdgeKey> = statement.outputs.opinions.iter().map(|(key, _)| key.clone()).collect();
+        let (_, expected) = state_update(&witness.prior, params, statement.t, &witness.events, &disclose)?;
+        if expected.outputs.state != statement.outputs.state {
+            return Err(format!("Re-executed F_θ does not reach the declared state at t={}", statement.t));
+        }
+        for ((key, declared), (_, actual)) in statement.outputs.opinions.iter().zip(&expected.outputs.opinions) {
+            if declared != actual {
+                return Err(format!("Declared opinion of ({}, {}) is {:?}, re-execution gives {:?}", key.0, key.1, declared, actual));
+            }
+        }
+        Ok(())
+    }
+}
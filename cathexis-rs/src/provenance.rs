@@ -0,0 +1,258 @@
+use crate::dispute::Dispute;
+use crate::eqbsl::EvidenceTensor;
+use crate::numeric::Scalar;
+use crate::state::{EdgeKey, NodeId, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Mass one event contributed to an edge, per channel, decayed to the state time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contribution {
+    pub event_id: String,
+    /// Event time.
+    pub t: i64,
+    /// Hyperedge the mass was attributed from, for hyperedge events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub via: Option<String>,
+    /// Current mass per channel.
+    pub mass: Vec<f64>,
+    /// Mass held back from the edge while the event is disputed, decaying alongside `mass`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withheld: Option<Vec<f64>>,
+}
+
+impl Contribution {
+    pub fn total(&self) -> f64 {
+        self.mass.iter().sum()
+    }
+
+    /// (r, s) of this contribution under the projection weights of `params`.
+    pub fn rs(&self, params: &Params) -> (f64, f64) {
+        let dot = |w: &[f64]| self.mass.iter().zip(w).map(|(m, w)| m * w).sum::<f64>();
+        (dot(&params.w_pos), dot(&params.w_neg))
+    }
+}
+
+/// Which events make up the evidence on one edge. Invariant: the contributions plus the
+/// compacted residual equal the edge's channel masses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EdgeProvenance {
+    /// Tracked contributions in arrival order.
+    pub contributions: Vec<Contribution>,
+    /// Per-channel mass of compacted (old or negligible) contributions and of evidence
+    /// present before tracking started.
+    pub compacted: Vec<f64>,
+    /// Number of contributions merged into `compacted`.
+    pub compacted_events: usize,
+}
+
+impl EdgeProvenance {
+    /// Per-channel mass of all contributions, tracked and compacted.
+    pub fn channel_masses(&self) -> Vec<f64> {
+        let mut total = self.compacted.clone();
+        for c in &self.contributions {
+            add_into(&mut total, &c.mass);
+        }
+        total
+    }
+
+    /// The `n` contributions carrying the most negative evidence s, largest first.
+    pub fn top_negative(&self, params: &Params, n: usize) -> Vec<&Contribution> {
+        let mut ranked: Vec<(f64, &Contribution)> = self
+            .contributions
+            .iter()
+            .map(|c| (c.rs(params).1, c))
+            .filter(|(s, _)| *s > 0.0)
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.event_id.cmp(&b.1.event_id)));
+        ranked.into_iter().take(n).map(|(_, c)| c).collect()
+    }
+
+    /// Contributions under a pending dispute are never compacted.
+    fn compact(&mut self, config: &ProvenanceConfig) {
+        let (keep, drop): (Vec<Contribution>, Vec<Contribution>) = std::mem::take(&mut self.contributions)
+            .into_iter()
+            .partition(|c| c.withheld.is_some() || c.total() >= config.min_mass);
+        let mut keep = keep;
+        for c in &drop {
+            self.absorb(c);
+        }
+        if keep.len() > config.max_events_per_edge {
+            // Oldest event times are compacted first; arrival order breaks ties
+            let mut order: Vec<usize> = (0..keep.len()).filter(|&n| keep[n].withheld.is_none()).collect();
+            order.sort_by_key(|&n| (keep[n].t, n));
+            let excess = (keep.len() - config.max_events_per_edge).min(order.len());
+            let mut merged = vec![false; keep.len()];
+            for &n in &order[..excess] {
+                self.absorb(&keep[n]);
+                merged[n] = true;
+            }
+            keep = keep.into_iter().zip(merged).filter_map(|(c, m)| (!m).then_some(c)).collect();
+        }
+        self.contributions = keep;
+    }
+
+    fn absorb(&mut self, c: &Contribution) {
+        add_into(&mut self.compacted, &c.mass);
+        self.compacted_events += 1;
+    }
+}
+
+fn add_into(total: &mut Vec<f64>, mass: &[f64]) {
+    if total.len() < mass.len() {
+        total.resize(mass.len(), 0.0);
+    }
+    for (t, m) in total.iter_mut().zip(mass) {
+        *t += m;
+    }
+}
+
+/// Bounds on the provenance kept per edge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvenanceConfig {
+    /// Maximum tracked contributions per edge; older ones are compacted.
+    pub max_events_per_edge: usize,
+    /// Contributions whose total mass has decayed below this are compacted.
+    pub min_mass: f64,
+}
+
+impl Default for ProvenanceConfig {
+    fn default() -> Self {
+        Self {
+            max_events_per_edge: 64,
+            min_mass: 1e-6,
+        }
+    }
+}
+
+/// Per-edge provenance of the evidence field, maintained by the F_θ operators when
+/// enabled on an `EqbslState`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub config: ProvenanceConfig,
+    #[serde(with = "edge_provenance_list")]
+    pub edges: BTreeMap<EdgeKey, EdgeProvenance>,
+    /// Dispute records by event id (see `dispute::apply`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub disputes: BTreeMap<String, Dispute>,
+}
+
+impl Provenance {
+    /// Starts tracking; evidence already on `edges` is accounted as compacted residual.
+    pub fn new<'a, S: Scalar>(
+        config: ProvenanceConfig,
+        edges: impl IntoIterator<Item = (&'a EdgeKey, &'a EvidenceTensor<S>)>,
+    ) -> Self {
+        let edges = edges
+            .into_iter()
+            .map(|(key, e)| {
+                let prov = EdgeProvenance {
+                    compacted: e.channel_masses().iter().map(|m| m.to_f64()).collect(),
+                    ..EdgeProvenance::default()
+                };
+                (key.clone(), prov)
+            })
+            .collect();
+        Self {
+            config,
+            edges,
+            disputes: BTreeMap::new(),
+        }
+    }
+
+    pub fn edge(&self, src: &str, dst: &str) -> Option<&EdgeProvenance> {
+        self.edges.get(&(src.to_string(), dst.to_string()))
+    }
+
+    /// Provenance of every edge incident to `agent_id` (inbound and outbound).
+    pub fn for_agent(&self, agent_id: &str) -> BTreeMap<&EdgeKey, &EdgeProvenance> {
+        self.edges
+            .iter()
+            .filter(|((i, j), _)| i == agent_id || j == agent_id)
+            .collect()
+    }
+
+    /// Contributions of `event_id` to every edge it reached.
+    pub fn event(&self, event_id: &str) -> Vec<(&EdgeKey, &Contribution)> {
+        self.edges
+            .iter()
+            .flat_map(|(key, p)| p.contributions.iter().map(move |c| (key, c)))
+            .filter(|(_, c)| c.event_id == event_id)
+            .collect()
+    }
+
+    /// Ids of every event with tracked contributions or a dispute record.
+    pub fn event_ids(&self) -> BTreeSet<&str> {
+        self.edges
+            .values()
+            .flat_map(|p| p.contributions.iter().map(|c| c.event_id.as_str()))
+            .chain(self.disputes.keys().map(String::as_str))
+            .collect()
+    }
+
+    /// Records mass added to an edge by an event (after lag decay and attribution).
+    pub(crate) fn record<S: Scalar>(&mut self, key: &EdgeKey, event_id: &str, t: i64, via: Option<&str>, de: &EvidenceTensor<S>) {
+        self.edges.entry(key.clone()).or_default().contributions.push(Contribution {
+            event_id: event_id.to_string(),
+            t,
+            via: via.map(str::to_string),
+            mass: de.channel_masses().iter().map(|m| m.to_f64()).collect(),
+            withheld: None,
+        });
+    }
+
+    /// Applies per-channel decay factors to every tracked mass.
+    pub(crate) fn decay(&mut self, factors: &[f64]) {
+        for p in self.edges.values_mut() {
+            for (m, b) in p.compacted.iter_mut().zip(factors) {
+                *m *= b;
+            }
+            for c in &mut p.contributions {
+                for (m, b) in c.mass.iter_mut().zip(factors) {
+                    *m *= b;
+                }
+                for (m, b) in c.withheld.iter_mut().flatten().zip(factors) {
+                    *m *= b;
+                }
+            }
+        }
+    }
+
+    /// Compacts every edge according to the configured bounds.
+    pub(crate) fn compact(&mut self) {
+        for p in self.edges.values_mut() {
+            p.compact(&self.config);
+        }
+    }
+}
+
+/// Serialises the edge map as `[{"src", "dst", ...}]`, like the state's edge list.
+mod edge_provenance_list {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry {
+        src: NodeId,
+        dst: NodeId,
+        #[serde(flatten)]
+        provenance: EdgeProvenance,
+    }
+
+    pub fn serialize<S: Serializer>(edges: &BTreeMap<EdgeKey, EdgeProvenance>, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<Entry> = edges
+            .iter()
+            .map(|((src, dst), p)| Entry {
+                src: src.clone(),
+                dst: dst.clone(),
+                provenance: p.clone(),
+            })
+            .collect();
+        entries.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<EdgeKey, EdgeProvenance>, D::Error> {
+        let entries = Vec::<Entry>::deserialize(deserializer)?;
+        Ok(entries.into_iter().map(|x| ((x.src, x.dst), x.provenance)).collect())
+    }
+}
@@ -0,0 +1,469 @@
+use crate::eqbsl::EvidenceTensor;
+use crate::events::Event;
+use crate::numeric::{Fixed, Scalar};
+use crate::operators;
+use crate::state::{EdgeKey, EqbslState, Hyperedge, Params};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Zero};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Order of the BN254 scalar field, the default field of circom, snarkjs and Noir.
+pub const BN254_PRIME: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+/// Bits of every evidence value and opinion component: values are raw fixed-point integers
+/// in [0, 2^63), so a saturating `Fixed` operation makes the witness unsatisfiable.
+const VALUE_BITS: usize = 63;
+
+/// Sparse linear combination Σ coeff · x_var over the field; variable 0 is the constant 1.
+pub type LinearCombination = BTreeMap<usize, BigUint>;
+
+/// One rank-1 constraint <a, x> · <b, x> = <c, x>.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination,
+    /// What the constraint enforces, e.g. "decay E[A,B][0]".
+    pub label: String,
+}
+
+/// Rank-1 constraint system. Variables are numbered as in circom: 0 is the constant 1,
+/// then the `n_public` public outputs, then the private inputs and internal signals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct R1cs {
+    pub prime: BigUint,
+    pub n_public: usize,
+    pub n_private_inputs: usize,
+    /// Name of every variable.
+    pub labels: Vec<String>,
+    pub constraints: Vec<Constraint>,
+}
+
+impl R1cs {
+    pub fn n_vars(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Checks `witness` (a full assignment, starting with 1) against every constraint and
+    /// reports the first one violated.
+    pub fn check(&self, witness: &[BigUint]) -> Result<(), String> {
+        if witness.len() != self.n_vars() {
+            return Err(format!("Witness has {} values, the system {} variables", witness.len(), self.n_vars()));
+        }
+        if !witness[0].is_one() {
+            return Err("Witness variable 0 must be 1".to_string());
+        }
+        if let Some(x) = witness.iter().position(|x| *x >= self.prime) {
+            return Err(format!("Witness value of '{}' is not a field element", self.labels[x]));
+        }
+        let eval = |lc: &LinearCombination| {
+            lc.iter().fold(BigUint::zero(), |acc, (&v, coeff)| (acc + coeff * &witness[v]) % &self.prime)
+        };
+        for (n, constraint) in self.constraints.iter().enumerate() {
+            let (a, b, c) = (eval(&constraint.a), eval(&constraint.b), eval(&constraint.c));
+            if (a * b) % &self.prime != c {
+                return Err(format!("Constraint {} ({}) is not satisfied", n, constraint.label));
+            }
+        }
+        Ok(())
+    }
+
+    /// The system in the JSON layout of `snarkjs r1cs export json`, with variable labels.
+    pub fn to_json(&self) -> Value {
+        let lc = |lc: &LinearCombination| -> Value {
+            lc.iter().map(|(v, coeff)| (v.to_string(), Value::String(coeff.to_string()))).collect::<serde_json::Map<_, _>>().into()
+        };
+        json!({
+            "n8": 32,
+            "prime": self.prime.to_string(),
+            "nVars": self.n_vars(),
+            "nOutputs": self.n_public,
+            "nPubInputs": 0,
+            "nPrvInputs": self.n_private_inputs,
+            "nLabels": self.n_vars(),
+            "nConstraints": self.constraints.len(),
+            "constraints": self.constraints.iter().map(|c| json!([lc(&c.a), lc(&c.b), lc(&c.c)])).collect::<Vec<_>>(),
+            "map": (0..self.n_vars()).collect::<Vec<_>>(),
+            "labels": self.labels,
+        })
+    }
+
+    /// Human-readable listing: one `(a) * (b) = (c)` line per constraint, with variable names
+    /// and coefficients above p/2 written as negatives.
+    pub fn to_text(&self) -> String {
+        let half = &self.prime >> 1;
+        let lc = |lc: &LinearCombination| -> String {
+            if lc.is_empty() {
+                return "0".to_string();
+            }
+            let terms: Vec<String> = lc
+                .iter()
+                .map(|(&v, coeff)| {
+                    let coeff = if *coeff > half {
+                        format!("-{}", &self.prime - coeff)
+                    } else {
+                        coeff.to_string()
+                    };
+                    match (v, coeff.as_str()) {
+                        (0, _) => coeff,
+                        (_, "1") => self.labels[v].clone(),
+                        _ => format!("{}*{}", coeff, self.labels[v]),
+                    }
+                })
+                .collect();
+            terms.join(" + ")
+        };
+        let mut out = format!(
+            "# prime {}\n# {} variables ({} public), {} constraints\n",
+            self.prime,
+            self.n_vars(),
+            self.n_public,
+            self.constraints.len()
+        );
+        for c in &self.constraints {
+            let _ = writeln!(out, "({}) * ({}) = ({})  # {}", lc(&c.a), lc(&c.b), lc(&c.c), c.label);
+        }
+        out
+    }
+}
+
+/// A constraint system with the witness generated alongside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Circuit {
+    pub r1cs: R1cs,
+    pub witness: Vec<BigUint>,
+}
+
+impl Circuit {
+    pub fn check(&self) -> Result<(), String> {
+        self.r1cs.check(&self.witness)
+    }
+
+    /// Public outputs by label.
+    pub fn public_outputs(&self) -> BTreeMap<&str, &BigUint> {
+        (1..=self.r1cs.n_public).map(|v| (self.r1cs.labels[v].as_str(), &self.witness[v])).collect()
+    }
+
+    /// The witness in the JSON layout of `snarkjs wtns export json`.
+    pub fn witness_json(&self) -> Value {
+        self.witness.iter().map(|x| Value::String(x.to_string())).collect()
+    }
+}
+
+/// Compiles one step of F_θ on a fixed-point state, E_t = F_θ(E_{t-1}, events), into a
+/// circuit (Primer Sections 1.5, 5, 6): decay of every edge and hyperedge, ingestion with
+/// lag decay, hyperedge attribution, and the EBSL lift of every resulting edge.
+///
+/// Private inputs are the prior evidence and the event deltas; public outputs are the
+/// resulting evidence E[i,j][c] and H[h][c] and opinions b/d/u[i,j]. θ, the step length and
+/// the hyperedge sizes are compiled in as constants. Every `Fixed` multiplication and
+/// division is constrained with its rounding rule (nearest, ties away from zero), so the
+/// public outputs equal `operators::apply_step` and `Params::lift` bit for bit. Commitments
+/// to the inputs are left to the proving backend. Rank-1 evidence tensors only.
+pub fn compile_step<const FRAC: u32>(
+    prior: &EqbslState<Fixed<FRAC>>,
+    params: &Params,
+    t: i64,
+    events: &[Event],
+) -> Result<Circuit, String> {
+    params.validate()?;
+    operators::validate_events(prior, params, t, events)?;
+    let rank = |e: &EvidenceTensor<Fixed<FRAC>>| e.rank() == 1;
+    if !prior.edges.values().all(rank)
+        || !prior.hyperedges.values().all(|h| rank(&h.e))
+        || !events.iter().all(|e| e.de().rank() == 1)
+    {
+        return Err("Circuits are compiled for rank-1 evidence tensors only".to_string());
+    }
+    let mut b = Builder::<FRAC>::default();
+    let dt = (t - prior.t) as u64;
+    let decay = |b: &mut Builder<FRAC>, label: &str, e: &EvidenceTensor<Fixed<FRAC>>| -> Result<Vec<Lc>, String> {
+        let factors: Vec<Fixed<FRAC>> = params.decay_factors(dt);
+        e.data
+            .iter()
+            .zip(&factors)
+            .enumerate()
+            .map(|(c, (x, f))| {
+                let x = b.input(&format!("{}[{}]", label, c), x.raw())?;
+                Ok(if dt == 0 { x } else { b.mul_const(&x, f.raw(), &format!("decay {}[{}]", label, c))? })
+            })
+            .collect()
+    };
+
+    let mut edges: BTreeMap<EdgeKey, Vec<Lc>> = BTreeMap::new();
+    for (key, e) in &prior.edges {
+        edges.insert(key.clone(), decay(&mut b, &edge_label("E0", key), e)?);
+    }
+    let mut hyperedges: BTreeMap<String, Vec<Lc>> = BTreeMap::new();
+    let mut nodes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (hid, h) in &prior.hyperedges {
+        hyperedges.insert(hid.clone(), decay(&mut b, &format!("H0[{}]", hid), &h.e)?);
+        nodes.insert(hid.clone(), h.nodes.clone());
+    }
+
+    let zero = || vec![Lc::default(); params.channels()];
+    let mut deltas = Vec::new();
+    for event in events {
+        let lag = (t - event.t()).max(0) as u64;
+        let factors: Vec<Fixed<FRAC>> = params.decay_factors(lag);
+        let mut de = Vec::with_capacity(params.channels());
+        for (c, (x, f)) in event.de().data.iter().zip(&factors).enumerate() {
+            let label = format!("dE[{}][{}]", event.id(), c);
+            let x = b.input(&label, Fixed::<FRAC>::from_f64(*x).raw())?;
+            de.push(if lag == 0 { x } else { b.mul_const(&x, f.raw(), &format!("lag decay {}", label))? });
+        }
+        let target = match event {
+            Event::Pair(e) => edges.entry((e.src.clone(), e.dst.clone())).or_insert_with(zero),
+            Event::Hyper(e) => {
+                nodes.entry(e.hid.clone()).or_insert_with(|| e.nodes.clone());
+                deltas.push((e.hid.clone(), de.clone()));
+                hyperedges.entry(e.hid.clone()).or_insert_with(zero)
+            }
+        };
+        for (acc, x) in target.iter_mut().zip(&de) {
+            acc.add(x);
+        }
+    }
+
+    for (hid, de) in &deltas {
+        let h = Hyperedge::<Fixed<FRAC>>::new(hid, nodes[hid].clone(), EvidenceTensor::zeros(params.channels()))?;
+        for i in &h.nodes {
+            for j in &h.nodes {
+                let alpha = h.alpha(i, j);
+                if alpha <= Fixed::zero() {
+                    continue;
+                }
+                let key = (i.clone(), j.clone());
+                for (c, x) in de.iter().enumerate() {
+                    let scaled = b.mul_const(x, alpha.raw(), &format!("attribute {} to {}[{}]", hid, edge_label("E", &key), c))?;
+                    edges.entry(key.clone()).or_insert_with(zero)[c].add(&scaled);
+                }
+            }
+        }
+    }
+
+    let k = Fixed::<FRAC>::from_f64(params.k).raw();
+    for (key, channels) in &edges {
+        let label = edge_label("E", key);
+        let e: Vec<Lc> = channels
+            .iter()
+            .enumerate()
+            .map(|(c, x)| b.output(&format!("{}[{}]", label, c), x))
+            .collect::<Result<_, String>>()?;
+        let project = |b: &mut Builder<FRAC>, weights: &[f64], name: &str| -> Result<Lc, String> {
+            let mut acc = Lc::default();
+            for (c, (x, &w)) in e.iter().zip(weights).enumerate() {
+                let w = Fixed::<FRAC>::from_f64(w).raw();
+                if w != 0 {
+                    acc.add(&b.mul_const(x, w, &format!("{}: w[{}] {}[{}]", name, c, label, c))?);
+                }
+            }
+            Ok(acc)
+        };
+        let r = project(&mut b, &params.w_pos, "r")?;
+        let s = project(&mut b, &params.w_neg, "s")?;
+        let mut sum = r.clone();
+        sum.add(&s);
+        sum.add(&Lc::constant(k));
+        let pair = &label[1..];
+        for (name, numerator) in [("b", r), ("d", s), ("u", Lc::constant(k))] {
+            let x = b.div(&numerator, &sum, &format!("lift {}{}", name, pair))?;
+            b.output(&format!("{}{}", name, pair), &x)?;
+        }
+    }
+    for (hid, channels) in &hyperedges {
+        for (c, x) in channels.iter().enumerate() {
+            b.output(&format!("H[{}][{}]", hid, c), x)?;
+        }
+    }
+    Ok(b.finish())
+}
+
+fn edge_label(name: &str, (i, j): &EdgeKey) -> String {
+    format!("{}[{},{}]", name, i, j)
+}
+
+/// Linear combination over integers with its value under the witness being built.
+#[derive(Debug, Clone, Default)]
+struct Lc {
+    terms: BTreeMap<usize, BigInt>,
+    value: BigInt,
+}
+
+impl Lc {
+    fn var(v: usize, value: BigInt) -> Self {
+        Self {
+            terms: BTreeMap::from([(v, BigInt::one())]),
+            value,
+        }
+    }
+
+    fn constant(c: i64) -> Self {
+        Self {
+            terms: BTreeMap::from([(0, BigInt::from(c))]),
+            value: BigInt::from(c),
+        }
+    }
+
+    fn add(&mut self, other: &Lc) {
+        self.add_scaled(other, &BigInt::one());
+    }
+
+    fn add_scaled(&mut self, other: &Lc, k: &BigInt) {
+        for (v, coeff) in &other.terms {
+            *self.terms.entry(*v).or_default() += coeff * k;
+        }
+        self.terms.retain(|_, coeff| !coeff.is_zero());
+        self.value += &other.value * k;
+    }
+
+    fn scaled(&self, k: &BigInt) -> Lc {
+        let mut out = Lc::default();
+        out.add_scaled(self, k);
+        out
+    }
+}
+
+#[derive(Debug, Default)]
+struct Builder<const FRAC: u32> {
+    /// (public, label, value) of variables 1..; variable 0 is the constant 1.
+    vars: Vec<(bool, String, BigInt)>,
+    constraints: Vec<(Lc, Lc, Lc, String)>,
+    private_inputs: usize,
+}
+
+impl<const FRAC: u32> Builder<FRAC> {
+    fn alloc(&mut self, public: bool, label: String, value: BigInt) -> Lc {
+        self.vars.push((public, label, value.clone()));
+        Lc::var(self.vars.len(), value)
+    }
+
+    fn enforce(&mut self, a: Lc, b: Lc, c: Lc, label: String) -> Result<(), String> {
+        if &a.value * &b.value != c.value {
+            return Err(format!("Witness generation failed at '{}'", label));
+        }
+        self.constraints.push((a, b, c, label));
+        Ok(())
+    }
+
+    /// Constrains 0 ≤ x < 2^bits by decomposing x into bits.
+    fn range(&mut self, x: &Lc, bits: usize, label: &str) -> Result<(), String> {
+        if x.value.sign() == Sign::Minus || x.value.bits() > bits as u64 {
+            return Err(format!("Value of '{}' is outside [0, 2^{})", label, bits));
+        }
+        let mut sum = Lc::default();
+        for n in 0..bits {
+            let bit = BigInt::from(x.value.bit(n as u64) as u8);
+            let v = self.alloc(false, format!("{} bit {}", label, n), bit);
+            let mut v_minus_one = v.clone();
+            v_minus_one.add(&Lc::constant(-1));
+            self.enforce(v.clone(), v_minus_one, Lc::default(), format!("{} bit {} is boolean", label, n))?;
+            sum.add_scaled(&v, &(BigInt::one() << n));
+        }
+        self.enforce(sum, Lc::constant(1), x.clone(), format!("{} range", label))
+    }
+
+    /// A private input fixed-point value.
+    fn input(&mut self, label: &str, raw: i64) -> Result<Lc, String> {
+        self.private_inputs += 1;
+        let x = self.alloc(false, label.to_string(), BigInt::from(raw));
+        self.range(&x, VALUE_BITS, label)?;
+        Ok(x)
+    }
+
+    /// A public output equal to `x`.
+    fn output(&mut self, label: &str, x: &Lc) -> Result<Lc, String> {
+        let out = self.alloc(true, label.to_string(), x.value.clone());
+        self.range(&out, VALUE_BITS, label)?;
+        self.enforce(x.clone(), Lc::constant(1), out.clone(), format!("output {}", label))?;
+        Ok(out)
+    }
+
+    /// `Fixed` multiplication of x ≥ 0 by the constant c ≥ 0 (raw): q = ⌊(c x + 2^(F-1)) / 2^F⌋,
+    /// i.e. c x + 2^(F-1) = 2^F q + rem with 0 ≤ rem < 2^F.
+    fn mul_const(&mut self, x: &Lc, c: i64, label: &str) -> Result<Lc, String> {
+        let one = BigInt::one() << FRAC;
+        let mut lhs = x.scaled(&BigInt::from(c));
+        lhs.add(&Lc::constant(1).scaled(&(&one >> 1)));
+        let (q, rem) = (&lhs.value / &one, &lhs.value % &one);
+        let q = self.alloc(false, format!("{} quotient", label), q);
+        let rem = self.alloc(false, format!("{} remainder", label), rem);
+        self.range(&rem, FRAC as usize, &format!("{} remainder", label))?;
+        self.range(&q, VALUE_BITS, &format!("{} quotient", label))?;
+        let mut rhs = q.scaled(&one);
+        rhs.add(&rem);
+        self.enforce(lhs, Lc::constant(1), rhs, label.to_string())?;
+        Ok(q)
+    }
+
+    /// `Fixed` division n / d of n ≥ 0 by d > 0: q = ⌊(2^(F+1) n + d) / 2d⌋, i.e.
+    /// q · 2d = 2^(F+1) n + d - rem with 0 ≤ rem < 2d.
+    fn div(&mut self, n: &Lc, d: &Lc, label: &str) -> Result<Lc, String> {
+        let two = BigInt::from(2);
+        let mut numerator = n.scaled(&(BigInt::one() << (FRAC + 1)));
+        numerator.add(d);
+        let denominator = d.scaled(&two);
+        if denominator.value.sign() != Sign::Plus {
+            return Err(format!("Division by a non-positive value at '{}'", label));
+        }
+        let (q, rem) = (&numerator.value / &denominator.value, &numerator.value % &denominator.value);
+        let q = self.alloc(false, format!("{} quotient", label), q);
+        let rem = self.alloc(false, format!("{} remainder", label), rem);
+        let bits = VALUE_BITS + 4;
+        self.range(&rem, bits, &format!("{} remainder", label))?;
+        let mut slack = denominator.clone();
+        slack.add(&Lc::constant(-1));
+        slack.add_scaled(&rem, &BigInt::from(-1));
+        self.range(&slack, bits, &format!("{} remainder bound", label))?;
+        self.range(&q, VALUE_BITS, &format!("{} quotient", label))?;
+        let mut rhs = numerator;
+        rhs.add_scaled(&rem, &BigInt::from(-1));
+        self.enforce(q.clone(), denominator, rhs, label.to_string())?;
+        Ok(q)
+    }
+
+    /// Renumbers the variables (constant, public, private) and reduces into the field.
+    fn finish(self) -> Circuit {
+        let prime: BigUint = BN254_PRIME.parse().expect("field prime");
+        let prime_int = BigInt::from(prime.clone());
+        let reduce = |x: &BigInt| {
+            let x = ((x % &prime_int) + &prime_int) % &prime_int;
+            x.to_biguint().expect("reduced value is non-negative")
+        };
+        let mut index = vec![0; self.vars.len() + 1];
+        let mut labels = vec!["one".to_string()];
+        let mut witness = vec![BigUint::one()];
+        for public in [true, false] {
+            for (v, (p, label, value)) in self.vars.iter().enumerate() {
+                if *p == public {
+                    index[v + 1] = labels.len();
+                    labels.push(label.clone());
+                    witness.push(reduce(value));
+                }
+            }
+        }
+        let lc = |lc: &Lc| lc.terms.iter().map(|(v, coeff)| (index[*v], reduce(coeff))).collect();
+        let constraints = self
+            .constraints
+            .iter()
+            .map(|(a, b, c, label)| Constraint {
+                a: lc(a),
+                b: lc(b),
+                c: lc(c),
+                label: label.clone(),
+            })
+            .collect();
+        Circuit {
+            r1cs: R1cs {
+                prime,
+                n_public: self.vars.iter().filter(|v| v.0).count(),
+                n_private_inputs: self.private_inputs,
+                labels,
+                constraints,
+            },
+            witness,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::canonical::Digest;
+use crate::events::{Event, StepRecord};
+use crate::operators;
+use crate::state::{EqbslState, Params};
+use serde::{Deserialize, Serialize};
+
+/// Append-only log of update steps, from which the full trust state can be rebuilt.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<StepRecord>", into = "Vec<StepRecord>")]
+pub struct EventLog {
+    entries: Vec<StepRecord>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a step; step times must be non-decreasing.
+    pub fn append(&mut self, record: StepRecord) -> Result<(), String> {
+        if let Some(last) = self.entries.last() {
+            if record.t < last.t {
+                return Err(format!(
+                    "Event log is append-only: step t={} precedes last step t={}",
+                    record.t, last.t
+                ));
+            }
+        }
+        self.entries.push(record);
+        Ok(())
+    }
+
+    /// Retroactively files a late event under its true time: merged into the step at
+    /// `event.t` if one exists, otherwise inserted as a new step in time order.
+    /// Returns the index of the affected step; every step from there on must be replayed.
+    pub(crate) fn insert_late(&mut self, event: Event) -> usize {
+        let t = event.t();
+        let p = self.entries.partition_point(|step| step.t < t);
+        match self.entries.get_mut(p) {
+            Some(step) if step.t == t => step.events.push(event),
+            _ => self.entries.insert(
+                p,
+                StepRecord {
+                    t,
+                    events: vec![event],
+                    disputes: Vec::new(),
+                },
+            ),
+        }
+        p
+    }
+
+    pub fn entries(&self) -> &[StepRecord] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl TryFrom<Vec<StepRecord>> for EventLog {
+    type Error = String;
+
+    fn try_from(entries: Vec<StepRecord>) -> Result<Self, String> {
+        let mut log = EventLog::new();
+        for record in entries {
+            log.append(record)?;
+        }
+        Ok(log)
+    }
+}
+
+impl From<EventLog> for Vec<StepRecord> {
+    fn from(log: EventLog) -> Self {
+        log.entries
+    }
+}
+
+/// Outcome of a replay: the rebuilt state and its digest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    pub state: EqbslState,
+    pub digest: Digest,
+    pub steps: usize,
+}
+
+/// Reapplies `steps` on top of `base` (genesis or a snapshot) through F_θ.
+/// The operators are deterministic, so the same base, steps and params always
+/// produce a bit-identical state.
+pub fn replay(params: &Params, base: &EqbslState, steps: &[StepRecord]) -> Result<ReplayReport, String> {
+    params.validate()?;
+    let mut state = base.clone();
+    for (n, step) in steps.iter().enumerate() {
+        operators::apply_record(&mut state, params, step)
+            .map_err(|e| format!("Replay failed at step {} (t={}): {}", n, step.t, e))?;
+    }
+    Ok(ReplayReport {
+        digest: state.digest(),
+        state,
+        steps: steps.len(),
+    })
+}
+
+/// Rebuilds the state from `base` and `steps` and checks that it matches `live` bit for bit.
+pub fn verify(params: &Params, base: &EqbslState, steps: &[StepRecord], live: &EqbslState) -> Result<ReplayReport, String> {
+    let report = replay(params, base, steps)?;
+    let live_digest = live.digest();
+    if report.digest != live_digest {
+        return Err(format!(
+            "Replay digest {} does not match live state digest {}",
+            report.digest, live_digest
+        ));
+    }
+    Ok(report)
+}
@@ -0,0 +1,339 @@
+use crate::eqbsl::EvidenceTensor;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How a channel's mass maps into EBSL evidence (Primer Section 1.2, 1.4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Polarity {
+    /// Mass counts towards positive evidence r.
+    Positive,
+    /// Mass counts towards negative evidence s.
+    Negative,
+    /// Mass is recorded but does not project into (r, s) unless weights say otherwise.
+    Neutral,
+}
+
+/// Description of a single evidence channel k.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelSpec {
+    /// Unique, stable channel name (e.g. "late_delivery").
+    pub name: String,
+    /// Human-readable description of what the channel counts.
+    pub description: String,
+    pub polarity: Polarity,
+    /// Default half-life in time steps; `None` means the channel does not decay.
+    pub half_life: Option<f64>,
+    /// Projection weight w^+_k into positive evidence.
+    pub w_pos: f64,
+    /// Projection weight w^-_k into negative evidence.
+    pub w_neg: f64,
+}
+
+impl ChannelSpec {
+    /// Creates a channel with unit projection weight on the side given by its polarity.
+    pub fn new(name: &str, description: &str, polarity: Polarity) -> Self {
+        let (w_pos, w_neg) = match polarity {
+            Polarity::Positive => (1.0, 0.0),
+            Polarity::Negative => (0.0, 1.0),
+            Polarity::Neutral => (0.0, 0.0),
+        };
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            polarity,
+            half_life: None,
+            w_pos,
+            w_neg,
+        }
+    }
+
+    pub fn with_half_life(mut self, half_life: f64) -> Self {
+        self.half_life = Some(half_life);
+        self
+    }
+
+    pub fn with_weights(mut self, w_pos: f64, w_neg: f64) -> Self {
+        self.w_pos = w_pos;
+        self.w_neg = w_neg;
+        self
+    }
+
+    /// Per-step decay factor β_k = 2^{-1 / half_life_k} (Primer Section 5).
+    pub fn decay_beta(&self) -> f64 {
+        match self.half_life {
+            Some(h) => 2f64.powf(-1.0 / h),
+            None => 1.0,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("Channel name must not be empty".to_string());
+        }
+        if !(self.w_pos >= 0.0 && self.w_neg >= 0.0) {
+            return Err(format!(
+                "Channel '{}': projection weights must be nonnegative, got w_pos={}, w_neg={}",
+                self.name, self.w_pos, self.w_neg
+            ));
+        }
+        match self.polarity {
+            Polarity::Positive if self.w_neg > 0.0 => {
+                return Err(format!("Channel '{}' is positive but has w_neg > 0", self.name));
+            }
+            Polarity::Negative if self.w_pos > 0.0 => {
+                return Err(format!("Channel '{}' is negative but has w_pos > 0", self.name));
+            }
+            _ => {}
+        }
+        if let Some(h) = self.half_life {
+            if !(h > 0.0 && h.is_finite()) {
+                return Err(format!(
+                    "Channel '{}': half-life must be positive and finite, got {}",
+                    self.name, h
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Named channel layout shared by evidence producers and the EQBSL engine.
+/// Index k of every `EvidenceTensor` validated against this schema is `channels[k]`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SchemaRepr", into = "SchemaRepr")]
+pub struct ChannelSchema {
+    /// Schema identifier, carried so producers and consumers can detect drift.
+    pub id: String,
+    pub version: u32,
+    channels: Vec<ChannelSpec>,
+    index: HashMap<String, usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SchemaRepr {
+    id: String,
+    version: u32,
+    channels: Vec<ChannelSpec>,
+}
+
+impl TryFrom<SchemaRepr> for ChannelSchema {
+    type Error = String;
+
+    fn try_from(repr: SchemaRepr) -> Result<Self, String> {
+        ChannelSchema::new(&repr.id, repr.version, repr.channels)
+    }
+}
+
+impl From<ChannelSchema> for SchemaRepr {
+    fn from(schema: ChannelSchema) -> Self {
+        SchemaRepr {
+            id: schema.id,
+            version: schema.version,
+            channels: schema.channels,
+        }
+    }
+}
+
+impl ChannelSchema {
+    /// Creates a schema, rejecting duplicate names and invalid channel specs.
+    pub fn new(id: &str, version: u32, channels: Vec<ChannelSpec>) -> Result<Self, String> {
+        if channels.is_empty() {
+            return Err("Schema must declare at least one channel".to_string());
+        }
+        let mut index = HashMap::new();
+        for (k, channel) in channels.iter().enumerate() {
+            channel.validate()?;
+            if index.insert(channel.name.clone(), k).is_some() {
+                return Err(format!("Duplicate channel name '{}'", channel.name));
+            }
+        }
+        Ok(Self {
+            id: id.to_string(),
+            version,
+            channels,
+            index,
+        })
+    }
+
+    /// Number of channels m.
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    pub fn channels(&self) -> &[ChannelSpec] {
+        &self.channels
+    }
+
+    /// Index k of the named channel.
+    pub fn index_of(&self, name: &str) -> Result<usize, String> {
+        self.index
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("Unknown channel '{}' in schema '{}' v{}", name, self.id, self.version))
+    }
+
+    pub fn channel(&self, name: &str) -> Result<&ChannelSpec, String> {
+        self.index_of(name).map(|k| &self.channels[k])
+    }
+
+    /// Projection weights w^+ (Primer Section 1.4).
+    pub fn w_pos(&self) -> Vec<f64> {
+        self.channels.iter().map(|c| c.w_pos).collect()
+    }
+
+    /// Projection weights w^- (Primer Section 1.4).
+    pub fn w_neg(&self) -> Vec<f64> {
+        self.channels.iter().map(|c| c.w_neg).collect()
+    }
+
+    /// Per-step decay factors β derived from the channel half-lives.
+    pub fn decay_beta(&self) -> Vec<f64> {
+        self.channels.iter().map(|c| c.decay_beta()).collect()
+    }
+
+    /// Checks that a tensor has this schema's shape and nonnegative, finite masses.
+    pub fn validate(&self, tensor: &EvidenceTensor) -> Result<(), String> {
+        if tensor.len() != self.len() {
+            return Err(format!(
+                "Evidence tensor has {} channels, schema '{}' v{} expects {}",
+                tensor.len(),
+                self.id,
+                self.version,
+                self.len()
+            ));
+        }
+        for (channel, slice) in self.channels.iter().zip(tensor.data.outer_iter()) {
+            if let Some(mass) = slice.iter().find(|m| !(**m >= 0.0 && m.is_finite())) {
+                return Err(format!(
+                    "Channel '{}' has invalid mass {} (must be finite and nonnegative)",
+                    channel.name, mass
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds a tensor from `(channel name, mass)` pairs; unnamed channels are zero.
+    pub fn tensor(&self, masses: &[(&str, f64)]) -> Result<EvidenceTensor, String> {
+        let mut tensor = EvidenceTensor::zeros(self.len());
+        for &(name, mass) in masses {
+            let k = self.index_of(name)?;
+            tensor.data[[k]] += mass;
+        }
+        self.validate(&tensor)?;
+        Ok(tensor)
+    }
+
+    /// Pairs every channel mass of a validated tensor with its channel name.
+    /// Higher-rank tensors are reduced to their rank-1 channel form first.
+    pub fn labelled(&self, tensor: &EvidenceTensor) -> Result<Vec<(String, f64)>, String> {
+        self.validate(tensor)?;
+        Ok(self
+            .channels
+            .iter()
+            .zip(tensor.channel_masses())
+            .map(|(c, mass)| (c.name.clone(), mass))
+            .collect())
+    }
+
+    /// Renders the nonzero channels of a tensor, e.g. `{on_time: 3, chargeback: 1}`.
+    pub fn render(&self, tensor: &EvidenceTensor) -> Result<String, String> {
+        let parts: Vec<String> = self
+            .labelled(tensor)?
+            .into_iter()
+            .filter(|(_, mass)| *mass != 0.0)
+            .map(|(name, mass)| format!("{}: {}", name, mass))
+            .collect();
+        Ok(format!("{{{}}}", parts.join(", ")))
+    }
+
+    /// Projects a tensor into EBSL scalars (r, s) = (<w^+, e>, <w^-, e>).
+    pub fn project(&self, tensor: &EvidenceTensor) -> Result<(f64, f64), String> {
+        self.validate(tensor)?;
+        tensor.project(&self.w_pos(), &self.w_neg())
+    }
+
+    /// Lists the differences between two schemas, empty when they describe the same layout.
+    pub fn diff(&self, other: &ChannelSchema) -> Vec<String> {
+        let mut diffs = Vec::new();
+        if self.id != other.id || self.version != other.version {
+            diffs.push(format!(
+                "schema '{}' v{} vs '{}' v{}",
+                self.id, self.version, other.id, other.version
+            ));
+        }
+        for (k, channel) in self.channels.iter().enumerate() {
+            match other.index.get(&channel.name) {
+                None => diffs.push(format!("channel '{}' missing from other schema", channel.name)),
+                Some(&j) if j != k => diffs.push(format!(
+                    "channel '{}' at index {} vs {}",
+                    channel.name, k, j
+                )),
+                Some(&j) if other.channels[j] != *channel => {
+                    diffs.push(format!("channel '{}' definition differs", channel.name))
+                }
+                _ => {}
+            }
+        }
+        for channel in &other.channels {
+            if !self.index.contains_key(&channel.name) {
+                diffs.push(format!("channel '{}' missing from this schema", channel.name));
+            }
+        }
+        diffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> ChannelSchema {
+        ChannelSchema::new(
+            "marketplace",
+            1,
+            vec![
+                ChannelSpec::new("on_time", "Delivered on time", Polarity::Positive),
+                ChannelSpec::new("late_delivery", "Delivered late", Polarity::Negative).with_half_life(30.0),
+                ChannelSpec::new("chargeback", "Payment reversed", Polarity::Negative).with_weights(0.0, 3.0),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_named_tensor_roundtrip() {
+        let schema = schema();
+        let e = schema.tensor(&[("on_time", 4.0), ("chargeback", 1.0)]).unwrap();
+        assert_eq!(e, EvidenceTensor::new(vec![4.0, 0.0, 1.0]));
+        assert_eq!(schema.render(&e).unwrap(), "{on_time: 4, chargeback: 1}");
+        assert_eq!(schema.project(&e).unwrap(), (4.0, 3.0));
+
+        let json = serde_json::to_string(&schema).unwrap();
+        let back: ChannelSchema = serde_json::from_str(&json).unwrap();
+        assert!(schema.diff(&back).is_empty());
+    }
+
+    #[test]
+    fn test_schema_mismatch_is_reported() {
+        let schema = schema();
+        assert!(schema.tensor(&[("refund", 1.0)]).is_err());
+        assert!(schema.validate(&EvidenceTensor::new(vec![1.0, 2.0])).is_err());
+        assert!(schema.validate(&EvidenceTensor::new(vec![1.0, -2.0, 0.0])).is_err());
+
+        let dup = ChannelSchema::new(
+            "dup",
+            1,
+            vec![
+                ChannelSpec::new("a", "", Polarity::Positive),
+                ChannelSpec::new("a", "", Polarity::Negative),
+            ],
+        );
+        assert!(dup.is_err());
+    }
+}
@@ -0,0 +1,386 @@
+use crate::core::Opinion;
+use crate::operators;
+use crate::parallel;
+use crate::state::{EdgeKey, EqbslState, NodeId, Params};
+use crate::witness;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+/// Compressed sparse row matrix. Row i holds `indices[indptr[i]..indptr[i+1]]` and the
+/// matching `values`; column indices are `u32` to halve index memory at million-node scale.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CsrMatrix<T> {
+    n_rows: usize,
+    n_cols: usize,
+    indptr: Vec<usize>,
+    indices: Vec<u32>,
+    values: Vec<T>,
+}
+
+impl<T: Copy> CsrMatrix<T> {
+    /// Builds a matrix from (row, col, value) triplets; duplicates are merged with `combine`.
+    pub fn from_triplets(
+        n_rows: usize,
+        n_cols: usize,
+        mut triplets: Vec<(u32, u32, T)>,
+        combine: impl Fn(T, T) -> T,
+    ) -> Result<Self, String> {
+        if let Some(&(i, j, _)) = triplets
+            .iter()
+            .find(|(i, j, _)| *i as usize >= n_rows || *j as usize >= n_cols)
+        {
+            return Err(format!("Entry ({}, {}) is outside a {}x{} matrix", i, j, n_rows, n_cols));
+        }
+        triplets.sort_by_key(|&(i, j, _)| (i, j));
+        let mut indptr = vec![0; n_rows + 1];
+        let mut indices: Vec<u32> = Vec::with_capacity(triplets.len());
+        let mut values: Vec<T> = Vec::with_capacity(triplets.len());
+        let mut last: Option<(u32, u32)> = None;
+        for (i, j, v) in triplets {
+            if last == Some((i, j)) {
+                let prev = values.last_mut().expect("duplicate follows an entry");
+                *prev = combine(*prev, v);
+                continue;
+            }
+            indptr[i as usize + 1] += 1;
+            indices.push(j);
+            values.push(v);
+            last = Some((i, j));
+        }
+        for i in 0..n_rows {
+            indptr[i + 1] += indptr[i];
+        }
+        Ok(Self {
+            n_rows,
+            n_cols,
+            indptr,
+            indices,
+            values,
+        })
+    }
+
+    /// Builds a matrix row by row, keeping the column order given for each row.
+    pub fn from_rows(n_cols: usize, rows: impl IntoIterator<Item = Vec<(u32, T)>>) -> Self {
+        let mut indptr = vec![0];
+        let mut indices = Vec::new();
+        let mut values = Vec::new();
+        for row in rows {
+            for (j, v) in row {
+                indices.push(j);
+                values.push(v);
+            }
+            indptr.push(indices.len());
+        }
+        indptr.shrink_to_fit();
+        indices.shrink_to_fit();
+        values.shrink_to_fit();
+        Self {
+            n_rows: indptr.len() - 1,
+            n_cols,
+            indptr,
+            indices,
+            values,
+        }
+    }
+
+    pub fn n_rows(&self) -> usize {
+        self.n_rows
+    }
+
+    pub fn n_cols(&self) -> usize {
+        self.n_cols
+    }
+
+    /// Number of stored entries.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Column indices and values of row i.
+    pub fn row(&self, i: usize) -> (&[u32], &[T]) {
+        let range = self.indptr[i]..self.indptr[i + 1];
+        (&self.indices[range.clone()], &self.values[range])
+    }
+
+    /// Transpose, i.e. the CSC layout of this matrix (column-wise access).
+    pub fn transpose(&self) -> CsrMatrix<T> {
+        let mut counts = vec![0usize; self.n_cols + 1];
+        for &j in &self.indices {
+            counts[j as usize + 1] += 1;
+        }
+        for j in 0..self.n_cols {
+            counts[j + 1] += counts[j];
+        }
+        let indptr = counts.clone();
+        let mut next = counts;
+        let mut indices = vec![0u32; self.nnz()];
+        let mut values: Vec<Option<T>> = vec![None; self.nnz()];
+        for i in 0..self.n_rows {
+            let (cols, vals) = self.row(i);
+            for (&j, &v) in cols.iter().zip(vals) {
+                let p = next[j as usize];
+                indices[p] = i as u32;
+                values[p] = Some(v);
+                next[j as usize] += 1;
+            }
+        }
+        CsrMatrix {
+            n_rows: self.n_cols,
+            n_cols: self.n_rows,
+            indptr,
+            indices,
+            values: values.into_iter().map(|v| v.expect("every slot filled")).collect(),
+        }
+    }
+
+    /// Heap bytes held by the index and value arrays.
+    pub fn memory_bytes(&self) -> usize {
+        self.indptr.capacity() * std::mem::size_of::<usize>()
+            + self.indices.capacity() * std::mem::size_of::<u32>()
+            + self.values.capacity() * std::mem::size_of::<T>()
+    }
+}
+
+/// Witness candidate ordered so that the max-heap top is the *worst* kept witness:
+/// lower discount first, then higher node index (matching `witness::select_witnesses`).
+struct Candidate {
+    delta: f64,
+    k: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.delta.total_cmp(&self.delta).then(self.k.cmp(&other.k))
+    }
+}
+
+/// Sparse backend for transitive aggregation: direct (r, s) and witness discounts δ as CSR
+/// matrices over node indices, with propagation expressed as repeated sparse products
+/// RS^{n+1} = RS^direct + λ Δ · RS^n (zero diagonal).
+///
+/// Memory is O(nnz(RS) + n·K) for the inputs and O(n) scratch per product (plus the
+/// contributions of one row); witness
+/// selection keeps at most K candidates per row in a bounded heap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseGraph {
+    nodes: Vec<NodeId>,
+    index: HashMap<NodeId, u32>,
+    rs: CsrMatrix<(f64, f64)>,
+    /// Row i lists N(i) as (k, δ_ik) in witness order (best first).
+    discount: CsrMatrix<f64>,
+}
+
+impl SparseGraph {
+    /// Builds the sparse graph of an EQBSL state; node indices follow `state.nodes()`.
+    pub fn from_state(state: &EqbslState, params: &Params) -> Result<Self, String> {
+        let nodes = state.nodes();
+        let index: HashMap<&NodeId, u32> = nodes.iter().enumerate().map(|(n, id)| (id, n as u32)).collect();
+        let triplets = operators::direct_rs(state, params)?
+            .into_iter()
+            .map(|((i, j), rs)| (index[&i], index[&j], rs))
+            .collect();
+        Self::from_rs(nodes, triplets, params)
+    }
+
+    /// Builds the sparse graph from (i, j, (r_ij, s_ij)) triplets over `nodes`.
+    /// Witness ties are broken by node index, so pass `nodes` sorted to match the dense path.
+    pub fn from_rs(nodes: Vec<NodeId>, triplets: Vec<(u32, u32, (f64, f64))>, params: &Params) -> Result<Self, String> {
+        params.validate()?;
+        if nodes.len() > u32::MAX as usize {
+            return Err(format!("Too many nodes for u32 indices: {}", nodes.len()));
+        }
+        let n = nodes.len();
+        let rs = CsrMatrix::from_triplets(n, n, triplets, |a, b| (a.0 + b.0, a.1 + b.1))?;
+        let index: HashMap<NodeId, u32> = nodes.iter().enumerate().map(|(n, id)| (id.clone(), n as u32)).collect();
+        let discount = CsrMatrix::from_rows(
+            n,
+            (0..n).map(|i| {
+                if params.witness_policy.is_empty() {
+                    Self::top_k_row(&rs, i, params)
+                } else {
+                    Self::policy_row(&rs, i, &nodes, &index, params)
+                }
+            }),
+        );
+        Ok(Self {
+            nodes,
+            index,
+            rs,
+            discount,
+        })
+    }
+
+    /// Top-K witnesses of row i by discount, keeping at most K candidates in a bounded heap.
+    fn top_k_row(rs: &CsrMatrix<(f64, f64)>, i: usize, params: &Params) -> Vec<(u32, f64)> {
+        let (cols, vals) = rs.row(i);
+        let mut heap = BinaryHeap::with_capacity(params.witness_top_k + 1);
+        for (&k, &(r, s)) in cols.iter().zip(vals) {
+            heap.push(Candidate {
+                delta: params.lift(r, s).expected_probability(),
+                k,
+            });
+            if heap.len() > params.witness_top_k {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec().into_iter().map(|c| (c.k, c.delta)).collect()
+    }
+
+    /// Witnesses of row i under `params.witness_policy`; memory is O(out-degree of i).
+    fn policy_row(
+        rs: &CsrMatrix<(f64, f64)>,
+        i: usize,
+        nodes: &[NodeId],
+        index: &HashMap<NodeId, u32>,
+        params: &Params,
+    ) -> Vec<(u32, f64)> {
+        let (cols, vals) = rs.row(i);
+        let opinions: Vec<(&NodeId, Opinion)> = cols
+            .iter()
+            .zip(vals)
+            .map(|(&k, &(r, s))| (&nodes[k as usize], params.lift(r, s)))
+            .collect();
+        witness::select_witnesses(
+            &nodes[i],
+            opinions.iter().map(|(k, op)| (*k, op)),
+            params.witness_policy.as_slice(),
+            params.witness_top_k,
+        )
+        .into_iter()
+        .map(|(delta, k)| (index[&k], delta))
+        .collect()
+    }
+
+    pub fn nodes(&self) -> &[NodeId] {
+        &self.nodes
+    }
+
+    pub fn index_of(&self, node: &str) -> Option<u32> {
+        self.index.get(node).copied()
+    }
+
+    pub fn direct(&self) -> &CsrMatrix<(f64, f64)> {
+        &self.rs
+    }
+
+    pub fn discount(&self) -> &CsrMatrix<f64> {
+        &self.discount
+    }
+
+    /// Heap bytes held by the matrices (excluding node names).
+    pub fn memory_bytes(&self) -> usize {
+        self.rs.memory_bytes() + self.discount.memory_bytes()
+    }
+
+    /// Propagated (r, s) after `params.propagation_depth` rounds; equal to
+    /// `operators::propagate` on the same state.
+    pub fn propagate(&self, params: &Params) -> CsrMatrix<(f64, f64)> {
+        let mut current = self.rs.clone();
+        for _ in 0..params.propagation_depth {
+            current = self.propagation_step(&current, params);
+        }
+        current
+    }
+
+    /// One product RS^direct + λ Δ · current, Gustavson-style with O(n) dense scratch
+    /// (one per chunk of rows with the `parallel` feature). Witness contributions to each entry are
+    /// combined with `params.aggregation`.
+    fn propagation_step(&self, current: &CsrMatrix<(f64, f64)>, params: &Params) -> CsrMatrix<(f64, f64)> {
+        let n = self.nodes.len();
+        let rows = parallel::map_range_with(
+            n,
+            || (vec![Vec::new(); n], Vec::new()),
+            |(acc, touched), i| self.propagate_row(i, current, params, acc, touched),
+        );
+        CsrMatrix::from_rows(n, rows)
+    }
+
+    /// Row i of one propagation product. `acc` (per-column witness contributions, length n)
+    /// and `touched` are scratch space and are left cleared.
+    fn propagate_row(
+        &self,
+        i: usize,
+        current: &CsrMatrix<(f64, f64)>,
+        params: &Params,
+        acc: &mut [Vec<(f64, f64)>],
+        touched: &mut Vec<u32>,
+    ) -> Vec<(u32, (f64, f64))> {
+        let (witnesses, deltas) = self.discount.row(i);
+        for (&k, &delta) in witnesses.iter().zip(deltas) {
+            let w = params.damping_lambda * delta;
+            let (cols, vals) = current.row(k as usize);
+            for (&j, &(rk, sk)) in cols.iter().zip(vals) {
+                if j as usize == i || (rk == 0.0 && sk == 0.0) {
+                    continue;
+                }
+                let entry = &mut acc[j as usize];
+                if entry.is_empty() {
+                    touched.push(j);
+                }
+                entry.push((w * rk, w * sk));
+            }
+        }
+        touched.sort_unstable();
+
+        let (dcols, dvals) = self.rs.row(i);
+        let mut row = Vec::with_capacity(dcols.len() + touched.len());
+        let (mut a, mut b) = (0, 0);
+        while a < dcols.len() || b < touched.len() {
+            let take_direct = b >= touched.len() || (a < dcols.len() && dcols[a] <= touched[b]);
+            let take_indirect = a >= dcols.len() || (b < touched.len() && touched[b] <= dcols[a]);
+            let (j, direct) = if take_direct {
+                (dcols[a], dvals[a])
+            } else {
+                (touched[b], (0.0, 0.0))
+            };
+            let value = if take_indirect {
+                let (rind, sind) = params.aggregation.aggregate(&acc[j as usize], witnesses.len());
+                (direct.0 + rind, direct.1 + sind)
+            } else {
+                direct
+            };
+            row.push((j, value));
+            a += take_direct as usize;
+            b += take_indirect as usize;
+        }
+
+        for &j in touched.iter() {
+            acc[j as usize].clear();
+        }
+        touched.clear();
+        row
+    }
+
+    /// Converts a node-indexed (r, s) matrix back into an edge-keyed map.
+    pub fn to_map(&self, m: &CsrMatrix<(f64, f64)>) -> BTreeMap<EdgeKey, (f64, f64)> {
+        let mut out = BTreeMap::new();
+        for i in 0..m.n_rows() {
+            let (cols, vals) = m.row(i);
+            for (&j, &v) in cols.iter().zip(vals) {
+                out.insert((self.nodes[i].clone(), self.nodes[j as usize].clone()), v);
+            }
+        }
+        out
+    }
+}
+
+/// Sparse-backend equivalent of `operators::propagate`.
+pub fn propagate(state: &EqbslState, params: &Params) -> Result<BTreeMap<EdgeKey, (f64, f64)>, String> {
+    let graph = SparseGraph::from_state(state, params)?;
+    Ok(graph.to_map(&graph.propagate(params)))
+}
@@ -0,0 +1,296 @@
+use crate::aggregation::Aggregation;
+use crate::core::{Evidence, Opinion};
+use crate::eqbsl::EvidenceTensor;
+use crate::numeric::Scalar;
+use crate::provenance::{Provenance, ProvenanceConfig};
+use crate::schema::ChannelSchema;
+use crate::witness::{WitnessPolicy, WitnessPolicyConfig};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+/// Agent identifier i ∈ V.
+pub type NodeId = String;
+
+/// Directed edge (i → j).
+pub type EdgeKey = (NodeId, NodeId);
+
+/// EQBSL parameters θ (Primer Section 2, 11).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Params {
+    /// Prior weight K > 0 of the evidence→opinion mapping.
+    #[serde(rename = "K")]
+    pub k: f64,
+    /// Positive projection weights w^+ ∈ R^m_{≥0}.
+    pub w_pos: Vec<f64>,
+    /// Negative projection weights w^- ∈ R^m_{≥0}.
+    pub w_neg: Vec<f64>,
+    /// Per-step channel decay β ∈ (0,1]^m.
+    pub decay_beta: Vec<f64>,
+    /// Damping λ ∈ (0,1] of transitive aggregation.
+    #[serde(rename = "lambda")]
+    pub damping_lambda: f64,
+    /// Maximum witness set size |N(i)|.
+    pub witness_top_k: usize,
+    /// Number of depth-1 propagation rounds; 0 disables propagation.
+    #[serde(default = "default_propagation_depth")]
+    pub propagation_depth: usize,
+    /// Domain prior a used when lifting opinions.
+    #[serde(default = "default_base_rate")]
+    pub base_rate: f64,
+    /// Witness selection policies applied in order before the top-K cap; empty selects
+    /// the top-K by discount.
+    #[serde(default)]
+    pub witness_policy: Vec<WitnessPolicyConfig>,
+    /// How witness contributions are combined in propagation (default: sum).
+    #[serde(default)]
+    pub aggregation: Aggregation,
+}
+
+fn default_propagation_depth() -> usize {
+    1
+}
+
+fn default_base_rate() -> f64 {
+    0.5
+}
+
+impl Params {
+    /// Creates validated parameters with the Primer defaults λ = 0.5, top-K = 32, depth 1, a = 0.5.
+    pub fn new(k: f64, w_pos: Vec<f64>, w_neg: Vec<f64>, decay_beta: Vec<f64>) -> Result<Self, String> {
+        let params = Self {
+            k,
+            w_pos,
+            w_neg,
+            decay_beta,
+            damping_lambda: 0.5,
+            witness_top_k: 32,
+            propagation_depth: default_propagation_depth(),
+            base_rate: default_base_rate(),
+            witness_policy: Vec::new(),
+            aggregation: Aggregation::Sum,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Takes projection weights and decay factors from a channel schema.
+    pub fn from_schema(schema: &ChannelSchema, k: f64) -> Result<Self, String> {
+        Self::new(k, schema.w_pos(), schema.w_neg(), schema.decay_beta())
+    }
+
+    /// Number of evidence channels m.
+    pub fn channels(&self) -> usize {
+        self.w_pos.len()
+    }
+
+    /// Parameter sanity checks (Primer Section 13.1).
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.k > 0.0 && self.k.is_finite()) {
+            return Err(format!("K must be > 0, got {}", self.k));
+        }
+        let m = self.w_pos.len();
+        if m == 0 || self.w_neg.len() != m || self.decay_beta.len() != m {
+            return Err("w_pos, w_neg, decay_beta must have same nonzero length".to_string());
+        }
+        if self.w_pos.iter().chain(&self.w_neg).any(|w| !(*w >= 0.0 && w.is_finite())) {
+            return Err("w_pos and w_neg must be nonnegative".to_string());
+        }
+        if self.decay_beta.iter().any(|b| !(*b > 0.0 && *b <= 1.0)) {
+            return Err("decay_beta must be in (0,1]".to_string());
+        }
+        if !(self.damping_lambda > 0.0 && self.damping_lambda <= 1.0) {
+            return Err("damping_lambda must be in (0,1]".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.base_rate) {
+            return Err("base_rate must be in [0,1]".to_string());
+        }
+        for policy in &self.witness_policy {
+            policy.validate()?;
+        }
+        self.aggregation.validate()?;
+        Ok(())
+    }
+
+    /// Attaches `policy` to every `WitnessPolicyConfig::Custom` entry named `name`, e.g.
+    /// after deserialising θ.
+    pub fn attach_witness_policy(&mut self, name: &str, policy: Arc<dyn WitnessPolicy + Send + Sync>) -> Result<(), String> {
+        let mut found = false;
+        for entry in &mut self.witness_policy {
+            if let WitnessPolicyConfig::Custom(custom) = entry {
+                if custom.name == name {
+                    custom.attach(policy.clone());
+                    found = true;
+                }
+            }
+        }
+        if !found {
+            return Err(format!("No custom witness policy named '{}'", name));
+        }
+        Ok(())
+    }
+
+    /// Checks that θ survives conversion to the number type `S`: K and every weight and β
+    /// must convert to within half their value, so that none rounds to zero (K divides the
+    /// lift) or saturates. Always holds for f64.
+    pub fn validate_for<S: Scalar>(&self) -> Result<(), String> {
+        let representable = |x: f64| x == 0.0 || (S::from_f64(x).to_f64() - x).abs() <= 0.5 * x;
+        if !representable(self.k) {
+            return Err(format!("K = {} is not representable in {}", self.k, S::label()));
+        }
+        for (name, values) in [("w_pos", &self.w_pos), ("w_neg", &self.w_neg), ("decay_beta", &self.decay_beta)] {
+            if let Some(x) = values.iter().find(|x| !representable(**x)) {
+                return Err(format!("{} entry {} is not representable in {}", name, x, S::label()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Per-channel decay β^dt for `dt` steps, computed in `S` from β converted to `S`.
+    pub fn decay_factors<S: Scalar>(&self, dt: u64) -> Vec<S> {
+        self.decay_beta.iter().map(|&b| S::from_f64(b).pow_steps(dt)).collect()
+    }
+
+    /// Projects an evidence tensor into (r, s) with w^+ and w^-.
+    pub fn rs<S: Scalar>(&self, e: &EvidenceTensor<S>) -> Result<(S, S), String> {
+        e.project(&self.w_pos, &self.w_neg)
+    }
+
+    /// EBSL lift of (r, s) into an opinion (Primer Section 1.5). For a fixed-point `S`, K
+    /// must not round to zero; `validate_for::<S>` checks it.
+    pub fn lift<S: Scalar>(&self, r: S, s: S) -> Opinion<S> {
+        Evidence::new(r, s, S::from_f64(self.k)).to_opinion(S::from_f64(self.base_rate))
+    }
+}
+
+/// β^n by binary exponentiation, so the result only depends on IEEE multiplication
+/// and is bit-identical across platforms (unlike `powf`).
+pub fn pow_steps(base: f64, n: u64) -> f64 {
+    Scalar::pow_steps(base, n)
+}
+
+/// A hyperedge h ⊆ V with optional roles and its evidence tensor e_h(t) (Primer Section 1.1, 1.2).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "S: Scalar")]
+pub struct Hyperedge<S = f64> {
+    pub id: String,
+    pub nodes: Vec<NodeId>,
+    #[serde(default)]
+    pub roles: BTreeMap<NodeId, String>,
+    pub e: EvidenceTensor<S>,
+}
+
+impl<S: Scalar> Hyperedge<S> {
+    pub fn new(id: &str, nodes: Vec<NodeId>, e: EvidenceTensor<S>) -> Result<Self, String> {
+        let unique: BTreeSet<&NodeId> = nodes.iter().collect();
+        if unique.len() < 2 || unique.len() != nodes.len() {
+            return Err(format!("Hyperedge '{}' needs at least 2 distinct nodes", id));
+        }
+        Ok(Self {
+            id: id.to_string(),
+            nodes,
+            roles: BTreeMap::new(),
+            e,
+        })
+    }
+
+    /// Attribution coefficient α_ijh: equal split over the n(n-1) ordered pairs in h,
+    /// so the total allocated mass is exactly 1 (Primer Section 6).
+    pub fn alpha(&self, i: &str, j: &str) -> S {
+        let n = self.nodes.len();
+        if n < 2 || i == j {
+            return S::zero();
+        }
+        S::one() / S::from_f64((n * (n - 1)) as f64)
+    }
+}
+
+/// The EQBSL state at time t: pairwise evidence field E_t and hyperedge field H_t^E (Primer Section 2).
+/// Ordered maps keep every iteration, and therefore every operator, deterministic; with a
+/// fixed-point `S` (`numeric::Fixed`) so is every number, on any machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "S: Scalar")]
+pub struct EqbslState<S = f64> {
+    pub t: i64,
+    #[serde(with = "edge_list")]
+    pub edges: BTreeMap<EdgeKey, EvidenceTensor<S>>,
+    pub hyperedges: BTreeMap<String, Hyperedge<S>>,
+    /// Optional per-edge provenance of the evidence, maintained by the operators (in f64).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl<S: Scalar> EqbslState<S> {
+    /// Empty state at time t.
+    pub fn new(t: i64) -> Self {
+        Self {
+            t,
+            edges: BTreeMap::new(),
+            hyperedges: BTreeMap::new(),
+            provenance: None,
+        }
+    }
+
+    /// Starts tracking which events contribute to each edge. Evidence already present is
+    /// accounted as untracked (compacted) mass.
+    pub fn enable_provenance(&mut self, config: ProvenanceConfig) {
+        self.provenance = Some(Provenance::new(config, &self.edges));
+    }
+
+    /// All agents appearing on an edge or in a hyperedge, sorted.
+    pub fn nodes(&self) -> Vec<NodeId> {
+        let mut nodes: BTreeSet<&NodeId> = BTreeSet::new();
+        for (i, j) in self.edges.keys() {
+            nodes.insert(i);
+            nodes.insert(j);
+        }
+        for h in self.hyperedges.values() {
+            nodes.extend(&h.nodes);
+        }
+        nodes.into_iter().cloned().collect()
+    }
+
+    pub fn contains_node(&self, agent_id: &str) -> bool {
+        self.edges.keys().any(|(i, j)| i == agent_id || j == agent_id)
+            || self.hyperedges.values().any(|h| h.nodes.iter().any(|n| n == agent_id))
+    }
+
+    pub fn edge(&self, src: &str, dst: &str) -> Option<&EvidenceTensor<S>> {
+        self.edges.get(&(src.to_string(), dst.to_string()))
+    }
+}
+
+/// Serialises the edge map as `[{"src", "dst", "e"}]` (Primer Section 11).
+mod edge_list {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(bound = "T: Scalar")]
+    struct EdgeEntry<T> {
+        src: NodeId,
+        dst: NodeId,
+        e: EvidenceTensor<T>,
+    }
+
+    pub fn serialize<T: Scalar, S: Serializer>(
+        edges: &BTreeMap<EdgeKey, EvidenceTensor<T>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let entries: Vec<EdgeEntry<T>> = edges
+            .iter()
+            .map(|((src, dst), e)| EdgeEntry {
+                src: src.clone(),
+                dst: dst.clone(),
+                e: e.clone(),
+            })
+            .collect();
+        entries.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Scalar, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<EdgeKey, EvidenceTensor<T>>, D::Error> {
+        let entries = Vec::<EdgeEntry<T>>::deserialize(deserializer)?;
+        Ok(entries.into_iter().map(|x| ((x.src, x.dst), x.e)).collect())
+    }
+}
@@ -0,0 +1,227 @@
+use crate::operators;
+use crate::state::{EqbslState, NodeId, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Parameters of the Sybil analysis.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SybilConfig {
+    /// Trusted (verified honest) agents the trust walk starts from.
+    pub seeds: Vec<NodeId>,
+    /// Power-iteration steps; `None` uses ⌈log2 n⌉, the SybilRank early termination that
+    /// mixes within the honest region but not across the sparse attack cut.
+    #[serde(default)]
+    pub iterations: Option<usize>,
+    /// Weight edges by belief × certainty b (1 - u) rather than belief alone, so attack
+    /// edges with little evidence carry almost no trust.
+    #[serde(default = "default_certainty_weighted")]
+    pub certainty_weighted: bool,
+    /// Agents scoring at least this are grouped into suspect clusters.
+    #[serde(default = "default_suspect_threshold")]
+    pub suspect_threshold: f64,
+    /// Suspect groups are reported as clusters only when at least this share of their
+    /// member pairs is linked by positive evidence.
+    #[serde(default = "default_min_cluster_density")]
+    pub min_cluster_density: f64,
+}
+
+fn default_certainty_weighted() -> bool {
+    true
+}
+
+fn default_suspect_threshold() -> f64 {
+    0.8
+}
+
+fn default_min_cluster_density() -> f64 {
+    0.5
+}
+
+impl SybilConfig {
+    pub fn new(seeds: Vec<NodeId>) -> Self {
+        Self {
+            seeds,
+            iterations: None,
+            certainty_weighted: default_certainty_weighted(),
+            suspect_threshold: default_suspect_threshold(),
+            min_cluster_density: default_min_cluster_density(),
+        }
+    }
+}
+
+/// A densely connected group of suspect agents and its cut to the rest of the graph.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SybilCluster {
+    pub members: Vec<NodeId>,
+    /// Share of member pairs linked by positive evidence.
+    pub density: f64,
+    /// Total positive weight on edges inside the cluster.
+    pub internal_weight: f64,
+    /// Total positive weight on edges crossing the cut (the attack edges).
+    pub cut_weight: f64,
+    /// Number of node pairs connected across the cut.
+    pub attack_edges: usize,
+}
+
+impl SybilCluster {
+    /// Share of the cluster's weight that crosses the cut; near 0 for a Sybil region.
+    pub fn cut_ratio(&self) -> f64 {
+        let total = self.internal_weight + self.cut_weight;
+        if total > 0.0 {
+            self.cut_weight / total
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Result of the Sybil analysis.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SybilReport {
+    /// Degree-normalised landing probability of the trust walk.
+    pub trust: BTreeMap<NodeId, f64>,
+    /// Sybil score in [0, 1]: 1 - trust / max trust; 1 for agents the walk never reached.
+    pub scores: BTreeMap<NodeId, f64>,
+    /// Suspect clusters, largest first.
+    pub clusters: Vec<SybilCluster>,
+}
+
+impl SybilReport {
+    pub fn score(&self, agent_id: &str) -> Option<f64> {
+        self.scores.get(agent_id).copied()
+    }
+}
+
+/// SybilRank-style analysis of the Sybil resistance through uncertainty claimed in the EQBSL Report.
+///
+/// Trust starts on the seeds and spreads for a few power-iteration steps along undirected
+/// edges weighted by positive, certain evidence. Honest agents, well connected to the seeds,
+/// receive trust in proportion to their degree; a Sybil region, however dense inside, is
+/// reached only through a few low-certainty attack edges and stays low. Agents are scored
+/// by degree-normalised trust, and connected groups of suspect agents with dense internal
+/// positive evidence are reported as clusters.
+///
+/// Seeds not (yet) in the state carry no trust; while none is present, the walk reaches
+/// nobody and every agent scores 1.
+pub fn analyse(state: &EqbslState, params: &Params, config: &SybilConfig) -> Result<SybilReport, String> {
+    for (name, x) in [
+        ("suspect_threshold", config.suspect_threshold),
+        ("min_cluster_density", config.min_cluster_density),
+    ] {
+        if !(0.0..=1.0).contains(&x) {
+            return Err(format!("{} must be in [0,1], got {}", name, x));
+        }
+    }
+    if config.seeds.is_empty() {
+        return Err("Sybil analysis needs at least one trusted seed".to_string());
+    }
+    let nodes = state.nodes();
+    let known: BTreeSet<&NodeId> = nodes.iter().collect();
+
+    // Symmetric positive weights W_uv = w(u→v) + w(v→u)
+    let mut weights: BTreeMap<&NodeId, BTreeMap<&NodeId, f64>> = BTreeMap::new();
+    let direct = operators::direct_rs(state, params)?;
+    for ((i, j), &(r, s)) in &direct {
+        let op = params.lift(r, s);
+        let w = if config.certainty_weighted { op.b * (1.0 - op.u) } else { op.b };
+        if w <= 0.0 {
+            continue;
+        }
+        let (i, j) = (known.get(i).copied().expect("edge endpoint"), known.get(j).copied().expect("edge endpoint"));
+        *weights.entry(i).or_default().entry(j).or_insert(0.0) += w;
+        *weights.entry(j).or_default().entry(i).or_insert(0.0) += w;
+    }
+    let degree: BTreeMap<&NodeId, f64> = weights.iter().map(|(v, row)| (*v, row.values().sum())).collect();
+
+    let iterations = config
+        .iterations
+        .unwrap_or_else(|| (nodes.len().max(2) as f64).log2().ceil() as usize);
+    let seeds: BTreeSet<&NodeId> = config.seeds.iter().filter(|s| known.contains(s)).collect();
+    let mut trust: BTreeMap<&NodeId, f64> = seeds.iter().map(|s| (*s, 1.0 / seeds.len() as f64)).collect();
+    for _ in 0..iterations {
+        let mut next: BTreeMap<&NodeId, f64> = BTreeMap::new();
+        for (&u, &t) in &trust {
+            match weights.get(u) {
+                Some(row) => {
+                    let d = degree[u];
+                    for (&v, &w) in row {
+                        *next.entry(v).or_insert(0.0) += t * w / d;
+                    }
+                }
+                // Isolated agents keep their trust
+                None => *next.entry(u).or_insert(0.0) += t,
+            }
+        }
+        trust = next;
+    }
+
+    let normalised: BTreeMap<NodeId, f64> = nodes
+        .iter()
+        .map(|v| {
+            let t = trust.get(v).copied().unwrap_or(0.0);
+            let d = degree.get(v).copied().unwrap_or(0.0);
+            (v.clone(), if d > 0.0 { t / d } else { 0.0 })
+        })
+        .collect();
+    let max = normalised.values().copied().fold(0.0, f64::max);
+    let scores: BTreeMap<NodeId, f64> = normalised
+        .iter()
+        .map(|(v, &t)| (v.clone(), if max > 0.0 { 1.0 - t / max } else { 1.0 }))
+        .collect();
+
+    let suspects: BTreeSet<&NodeId> = scores
+        .iter()
+        .filter(|(_, &s)| s >= config.suspect_threshold)
+        .map(|(v, _)| v)
+        .collect();
+    let mut clusters = Vec::new();
+    let mut visited: BTreeSet<&NodeId> = BTreeSet::new();
+    for &start in &suspects {
+        if !visited.insert(start) {
+            continue;
+        }
+        let mut members = vec![start];
+        let mut stack = vec![start];
+        while let Some(u) = stack.pop() {
+            for &v in weights.get(u).into_iter().flat_map(|row| row.keys()) {
+                if suspects.contains(v) && visited.insert(v) {
+                    members.push(v);
+                    stack.push(v);
+                }
+            }
+        }
+        let set: BTreeSet<&NodeId> = members.iter().copied().collect();
+        let (mut internal, mut cut, mut links, mut attack_edges) = (0.0, 0.0, 0, 0);
+        for &u in &set {
+            for (&v, &w) in weights.get(u).into_iter().flatten() {
+                if set.contains(v) {
+                    internal += w / 2.0;
+                    links += 1;
+                } else {
+                    cut += w;
+                    attack_edges += 1;
+                }
+            }
+        }
+        // Each internal link was counted from both ends
+        let pairs = set.len() * (set.len() - 1);
+        let density = if pairs > 0 { links as f64 / pairs as f64 } else { 0.0 };
+        if set.len() < 2 || density < config.min_cluster_density {
+            continue;
+        }
+        clusters.push(SybilCluster {
+            members: set.into_iter().cloned().collect(),
+            density,
+            internal_weight: internal,
+            cut_weight: cut,
+            attack_edges,
+        });
+    }
+    clusters.sort_by(|a, b| b.members.len().cmp(&a.members.len()).then_with(|| a.members.cmp(&b.members)));
+
+    Ok(SybilReport {
+        trust: normalised,
+        scores,
+        clusters,
+    })
+}
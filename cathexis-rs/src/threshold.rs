@@ -0,0 +1,272 @@
+use crate::attestation::{HandleSigner, KeyRing};
+use crate::canonical::{Canonical, Digest};
+use crate::core::Opinion;
+use crate::numeric::{Scalar, Q32};
+use crate::state::NodeId;
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar as GroupScalar;
+use ed25519_dalek::Signature;
+use merlin::Transcript;
+use num_traits::{One, Zero};
+use serde::{Deserialize, Serialize};
+
+/// Bits of each range proof: a proven difference lies in [0, 2^64).
+const RANGE_BITS: usize = 64;
+
+/// Values proven per attestation: E - τ_E, 1 - E, u and τ_u - u.
+const PROVEN_VALUES: usize = 4;
+
+/// Pedersen commitments C = v·B + r·B̃ to the Q32 expected probability E = b + a·u and the
+/// uncertainty u of an opinion. Hiding and binding; published in place of the opinion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpinionCommitment {
+    pub expectation: CompressedRistretto,
+    pub uncertainty: CompressedRistretto,
+}
+
+impl Canonical for OpinionCommitment {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.expectation.as_bytes());
+        out.extend_from_slice(self.uncertainty.as_bytes());
+    }
+}
+
+/// The values and blinding factors behind an `OpinionCommitment`. Handed privately to the
+/// subject by the issuer, never published.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpinionOpening {
+    pub expectation: Q32,
+    pub uncertainty: Q32,
+    pub expectation_blinding: GroupScalar,
+    pub uncertainty_blinding: GroupScalar,
+}
+
+impl OpinionOpening {
+    /// Commits to `opinion` with fresh random blindings.
+    pub fn new(opinion: &Opinion<Q32>) -> Self {
+        let mut rng = rand::thread_rng();
+        Self {
+            expectation: opinion.expected_probability(),
+            uncertainty: opinion.u,
+            expectation_blinding: GroupScalar::random(&mut rng),
+            uncertainty_blinding: GroupScalar::random(&mut rng),
+        }
+    }
+
+    pub fn commitment(&self) -> OpinionCommitment {
+        let pc = PedersenGens::default();
+        OpinionCommitment {
+            expectation: pc.commit(raw(self.expectation), self.expectation_blinding).compress(),
+            uncertainty: pc.commit(raw(self.uncertainty), self.uncertainty_blinding).compress(),
+        }
+    }
+}
+
+/// An `OpinionCommitment` issued by the engine: the commitment to the opinion of
+/// `observer` about `subject` in the state with root `state_root`, signed by the issuer's
+/// key `key_id` (see `EqbslEngine::issue_commitment`). The signature is what ties the
+/// committed values to the trust state; a subject cannot commit to an opinion of its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssuedCommitment {
+    pub observer: NodeId,
+    pub subject: NodeId,
+    pub state_root: Digest,
+    pub issued_at: i64,
+    pub commitment: OpinionCommitment,
+    pub key_id: String,
+    pub signature: Signature,
+}
+
+impl IssuedCommitment {
+    /// Signs the commitment of `opening` as the opinion of `observer` about `subject` in
+    /// the state with root `state_root`.
+    pub fn issue(
+        signer: &HandleSigner,
+        observer: &str,
+        subject: &str,
+        state_root: Digest,
+        issued_at: i64,
+        opening: &OpinionOpening,
+    ) -> Self {
+        let mut issued = Self {
+            observer: observer.to_string(),
+            subject: subject.to_string(),
+            state_root,
+            issued_at,
+            commitment: opening.commitment(),
+            key_id: signer.key_id().to_string(),
+            // Replaced by the signature over the fields above
+            signature: Signature::from_bytes(&[0; 64]),
+        };
+        issued.signature = signer.sign_bytes(&issued.message());
+        issued
+    }
+
+    /// Checks the issuer's signature against `keys`.
+    pub fn verify(&self, keys: &KeyRing) -> Result<(), String> {
+        keys.verify_signature(&self.key_id, self.issued_at, &self.message(), &self.signature)
+            .map_err(|e| format!("{} on the commitment to {} -> {}", e, self.observer, self.subject))
+    }
+
+    /// Digest of everything but the signature, bound into the proof transcript.
+    pub fn digest(&self) -> Digest {
+        Digest::of("eqbsl/threshold/commitment/v1", &self.content())
+    }
+
+    fn content(&self) -> impl Canonical + '_ {
+        (
+            (&self.observer, &self.subject),
+            (self.state_root, self.issued_at),
+            (&self.commitment, &self.key_id),
+        )
+    }
+
+    fn message(&self) -> Vec<u8> {
+        ("eqbsl/threshold/commitment/v1", self.content()).to_canonical_bytes()
+    }
+}
+
+/// The public claim of a threshold attestation: the committed opinion has
+/// E ≥ `min_expectation` and u ≤ `max_uncertainty` (1 unless set). Both thresholds lie in
+/// [0, 1].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdClaim {
+    pub min_expectation: Q32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_uncertainty: Option<Q32>,
+}
+
+impl ThresholdClaim {
+    /// Thresholds are rounded to Q32 like the values they are compared with.
+    pub fn new(min_expectation: f64) -> Self {
+        Self {
+            min_expectation: Q32::from_f64(min_expectation),
+            max_uncertainty: None,
+        }
+    }
+
+    pub fn with_max_uncertainty(mut self, max_uncertainty: f64) -> Self {
+        self.max_uncertainty = Some(Q32::from_f64(max_uncertainty));
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let unit = Q32::zero()..=Q32::one();
+        if !unit.contains(&self.min_expectation) || !self.max_uncertainty.is_none_or(|u| unit.contains(&u)) {
+            return Err("Threshold claims need thresholds in [0, 1]".to_string());
+        }
+        Ok(())
+    }
+
+    /// Upper bound on u that is proven: `max_uncertainty`, or 1.
+    fn uncertainty_bound(&self) -> Q32 {
+        self.max_uncertainty.unwrap_or_else(Q32::one)
+    }
+
+    pub fn digest(&self) -> Digest {
+        Digest::of(
+            "eqbsl/threshold/claim/v2",
+            &(self.min_expectation, self.max_uncertainty),
+        )
+    }
+}
+
+/// Zero-knowledge proof that an engine-issued committed opinion satisfies a claim ("my
+/// trust > 0.8"): an aggregated Bulletproofs range proof, on commitments derived from the
+/// `OpinionCommitment`, that E - τ_E, 1 - E, u and τ_u - u lie in [0, 2^64), i.e. that
+/// τ_E ≤ E ≤ 1 and 0 ≤ u ≤ τ_u in Q32 (Primer Section 10). Reveals nothing about the
+/// evidence beyond the claim.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdAttestation {
+    pub claim: ThresholdClaim,
+    pub issued: IssuedCommitment,
+    /// `RangeProof::to_bytes`.
+    pub proof: Vec<u8>,
+}
+
+impl ThresholdAttestation {
+    pub const SYSTEM: &'static str = "eqbsl/threshold/bulletproofs/v2";
+
+    /// Proves `claim` about the opinion behind `opening`, which must open `issued`; fails
+    /// if the claim does not hold.
+    pub fn prove(opening: &OpinionOpening, issued: IssuedCommitment, claim: ThresholdClaim) -> Result<Self, String> {
+        claim.validate()?;
+        if opening.commitment() != issued.commitment {
+            return Err("Opening does not match the issued commitment".to_string());
+        }
+        let values = vec![
+            difference(opening.expectation, claim.min_expectation, "expectation")?,
+            difference(Q32::one(), opening.expectation, "expectation")?,
+            difference(opening.uncertainty, Q32::zero(), "uncertainty")?,
+            difference(claim.uncertainty_bound(), opening.uncertainty, "uncertainty")?,
+        ];
+        let blindings = vec![
+            opening.expectation_blinding,
+            -opening.expectation_blinding,
+            opening.uncertainty_blinding,
+            -opening.uncertainty_blinding,
+        ];
+        let (gens, pc) = generators();
+        let mut transcript = transcript(&claim, &issued);
+        let (proof, _) = RangeProof::prove_multiple(&gens, &pc, &mut transcript, &values, &blindings, RANGE_BITS)
+            .map_err(|e| format!("Cannot prove threshold claim: {}", e))?;
+        Ok(Self {
+            claim,
+            issued,
+            proof: proof.to_bytes(),
+        })
+    }
+
+    /// Checks the issuer's signature on the commitment against `keys`, then the proof
+    /// against the claim and commitment, offline.
+    pub fn verify(&self, keys: &KeyRing) -> Result<(), String> {
+        self.claim.validate()?;
+        self.issued.verify(keys)?;
+        let point = |c: &CompressedRistretto, name: &str| {
+            c.decompress().ok_or_else(|| format!("Commitment to the {} is not a valid point", name))
+        };
+        let (gens, pc) = generators();
+        let expectation = point(&self.issued.commitment.expectation, "expectation")?;
+        let uncertainty = point(&self.issued.commitment.uncertainty, "uncertainty")?;
+        let shifted = [
+            expectation - pc.B * raw(self.claim.min_expectation),
+            pc.B * raw(Q32::one()) - expectation,
+            uncertainty,
+            pc.B * raw(self.claim.uncertainty_bound()) - uncertainty,
+        ]
+        .map(|p| p.compress());
+        let proof = RangeProof::from_bytes(&self.proof).map_err(|e| format!("Malformed range proof: {}", e))?;
+        let mut transcript = transcript(&self.claim, &self.issued);
+        proof
+            .verify_multiple(&gens, &pc, &mut transcript, &shifted, RANGE_BITS)
+            .map_err(|e| format!("Threshold attestation does not verify: {}", e))
+    }
+}
+
+fn generators() -> (BulletproofGens, PedersenGens) {
+    (BulletproofGens::new(RANGE_BITS, PROVEN_VALUES), PedersenGens::default())
+}
+
+/// Fiat-Shamir transcript bound to the claim and the issued commitment.
+fn transcript(claim: &ThresholdClaim, issued: &IssuedCommitment) -> Transcript {
+    let mut transcript = Transcript::new(ThresholdAttestation::SYSTEM.as_bytes());
+    transcript.append_message(b"claim", &claim.digest().0);
+    transcript.append_message(b"issued", &issued.digest().0);
+    transcript
+}
+
+/// The raw Q32 value as a group scalar (negative values map to their negation mod ℓ).
+fn raw(x: Q32) -> GroupScalar {
+    let magnitude = GroupScalar::from(x.raw().unsigned_abs());
+    if x.raw() < 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// `x - y` as the value of a range proof, if the claim it encodes holds.
+fn difference(x: Q32, y: Q32, name: &str) -> Result<u64, String> {
+    u64::try_from(x.raw() as i128 - y.raw() as i128).map_err(|_| format!("Claim on the {} does not hold: {} vs {}", name, x, y))
+}
@@ -0,0 +1,300 @@
+use crate::engine::EqbslEngine;
+use crate::events::Event;
+use crate::operators;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What to do with an event that arrives after the watermark has passed its time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatePolicy {
+    /// Discard the event.
+    Drop,
+    /// File the event at its true time and replay the history from there (needs engine history).
+    Replay,
+    /// Ingest the event in a step at the engine's current time. The current state is exact
+    /// (the delta is decayed by its lateness) but snapshots before now do not include it.
+    ApplyAtNow,
+}
+
+/// Event-time buffering parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatermarkConfig {
+    /// How far behind the newest event time an event may arrive and still be ordered correctly.
+    pub allowed_lateness: i64,
+    pub late_policy: LatePolicy,
+}
+
+/// How a late event was handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LateAction {
+    Dropped,
+    Replayed,
+    AppliedAtNow,
+}
+
+/// An event that arrived beyond the lateness bound.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LateEvent {
+    pub event_id: String,
+    pub t: i64,
+    /// Watermark at the time the event arrived.
+    pub watermark: i64,
+    pub action: LateAction,
+}
+
+/// Result of feeding a batch of events through the buffer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IngestReport {
+    /// Times of the engine steps taken, ascending.
+    pub steps: Vec<i64>,
+    /// Number of events applied in order.
+    pub applied: usize,
+    /// Events still buffered, waiting for the watermark.
+    pub pending: usize,
+    pub late: Vec<LateEvent>,
+}
+
+/// Reorders events from multiple sources by event time before they reach the engine.
+///
+/// The watermark is `max event time seen - allowed_lateness`. Events at or before the
+/// watermark are released to the engine in time order, one step per distinct time, so
+/// each is decayed from its true timestamp. Events arriving with a time the engine has
+/// already passed are handled by the `LatePolicy`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventBuffer {
+    config: WatermarkConfig,
+    pending: BTreeMap<i64, Vec<Event>>,
+    max_seen: Option<i64>,
+}
+
+impl EventBuffer {
+    pub fn new(config: WatermarkConfig) -> Result<Self, String> {
+        if config.allowed_lateness < 0 {
+            return Err(format!(
+                "allowed_lateness must be nonnegative, got {}",
+                config.allowed_lateness
+            ));
+        }
+        Ok(Self {
+            config,
+            pending: BTreeMap::new(),
+            max_seen: None,
+        })
+    }
+
+    pub fn config(&self) -> &WatermarkConfig {
+        &self.config
+    }
+
+    /// Current watermark, or `None` before any event has been seen.
+    pub fn watermark(&self) -> Option<i64> {
+        self.max_seen.map(|t| t - self.config.allowed_lateness)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.values().map(Vec::len).sum()
+    }
+
+    /// Buffers `events`, then releases every step the watermark allows into `engine`.
+    /// Late events are validated together before any is handled, so an invalid one (or
+    /// `LatePolicy::Replay` on an engine without history) fails the call with nothing
+    /// applied or buffered. A released batch leaves the buffer only once its step succeeds,
+    /// so on error it and every later batch stay pending (earlier steps remain applied).
+    pub fn process(&mut self, engine: &mut EqbslEngine, events: Vec<Event>) -> Result<IngestReport, String> {
+        let mut report = IngestReport::default();
+        let (late, on_time): (Vec<Event>, Vec<Event>) = events.into_iter().partition(|e| e.t() < engine.state().t);
+        if !late.is_empty() {
+            operators::validate_events(engine.state(), engine.params(), engine.state().t, &late)?;
+            if self.config.late_policy == LatePolicy::Replay && engine.history().is_none() {
+                return Err("LatePolicy::Replay needs history on the engine".to_string());
+            }
+        }
+        for event in late {
+            let watermark = self.watermark().unwrap_or(engine.state().t);
+            report.late.push(self.handle_late(engine, event, watermark)?);
+        }
+        for event in on_time {
+            self.max_seen = Some(self.max_seen.map_or(event.t(), |m| m.max(event.t())));
+            self.pending.entry(event.t()).or_default().push(event);
+        }
+        if let Some(watermark) = self.watermark() {
+            self.release(engine, Some(watermark), &mut report)?;
+        }
+        report.pending = self.pending();
+        Ok(report)
+    }
+
+    /// Releases everything still buffered, regardless of the watermark (e.g. at shutdown).
+    pub fn flush(&mut self, engine: &mut EqbslEngine) -> Result<IngestReport, String> {
+        let mut report = IngestReport::default();
+        self.release(engine, None, &mut report)?;
+        Ok(report)
+    }
+
+    fn release(&mut self, engine: &mut EqbslEngine, upto: Option<i64>, report: &mut IngestReport) -> Result<(), String> {
+        while let Some(entry) = self.pending.first_entry() {
+            let t = *entry.key();
+            if upto.is_some_and(|w| t > w) {
+                break;
+            }
+            engine.step(t, entry.get().clone())?;
+            report.applied += entry.remove().len();
+            report.steps.push(t);
+        }
+        Ok(())
+    }
+
+    fn handle_late(&self, engine: &mut EqbslEngine, event: Event, watermark: i64) -> Result<LateEvent, String> {
+        let event_id = event.id().to_string();
+        let t = event.t();
+        let action = match self.config.late_policy {
+            LatePolicy::Drop => LateAction::Dropped,
+            LatePolicy::Replay => {
+                engine.amend(event)?;
+                LateAction::Replayed
+            }
+            LatePolicy::ApplyAtNow => {
+                engine.step(engine.state().t, vec![event])?;
+                LateAction::AppliedAtNow
+            }
+        };
+        Ok(LateEvent {
+            event_id,
+            t,
+            watermark,
+            action,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eqbsl::EvidenceTensor;
+    use crate::events::PairEvent;
+    use crate::state::{EqbslState, Params};
+
+    fn pair(id: &str, t: i64, src: &str, dst: &str, mass: f64) -> Event {
+        Event::Pair(PairEvent {
+            id: id.to_string(),
+            t,
+            src: src.to_string(),
+            dst: dst.to_string(),
+            de: EvidenceTensor::new(vec![mass, 0.0]),
+        })
+    }
+
+    fn engine() -> EqbslEngine {
+        let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.8, 0.9]).unwrap();
+        EqbslEngine::new(params, EqbslState::new(0)).unwrap().with_history(2).unwrap()
+    }
+
+    fn buffer(policy: LatePolicy) -> EventBuffer {
+        EventBuffer::new(WatermarkConfig {
+            allowed_lateness: 3,
+            late_policy: policy,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_out_of_order_within_bound() {
+        let mut in_order = engine();
+        let mut buf = buffer(LatePolicy::Drop);
+        buf.process(&mut in_order, vec![pair("a", 1, "A", "B", 1.0), pair("b", 2, "B", "C", 1.0)])
+            .unwrap();
+        buf.process(&mut in_order, vec![pair("c", 6, "C", "A", 1.0)]).unwrap();
+        buf.flush(&mut in_order).unwrap();
+
+        let mut shuffled = engine();
+        let mut buf = buffer(LatePolicy::Drop);
+        let r1 = buf
+            .process(&mut shuffled, vec![pair("b", 2, "B", "C", 1.0), pair("c", 6, "C", "A", 1.0)])
+            .unwrap();
+        assert_eq!(r1.steps, vec![2]);
+        let r2 = buf.process(&mut shuffled, vec![pair("a", 1, "A", "B", 1.0)]).unwrap();
+        assert_eq!(r2.late.len(), 1);
+        assert_eq!(r2.late[0].action, LateAction::Dropped);
+
+        // Within the bound: "a" (t=1) arrives before the watermark (t=3) passes it
+        let mut bounded = engine();
+        let mut buf = buffer(LatePolicy::Drop);
+        buf.process(&mut bounded, vec![pair("b", 2, "B", "C", 1.0)]).unwrap();
+        buf.process(&mut bounded, vec![pair("a", 1, "A", "B", 1.0), pair("c", 6, "C", "A", 1.0)])
+            .unwrap();
+        buf.flush(&mut bounded).unwrap();
+        assert_eq!(bounded.state(), in_order.state());
+        assert_eq!(bounded.state_at(2).unwrap().state(), in_order.state_at(2).unwrap().state());
+    }
+
+    #[test]
+    fn test_late_policies() {
+        let events = vec![pair("b", 2, "B", "C", 1.0), pair("c", 9, "C", "A", 1.0)];
+        let late = pair("a", 1, "A", "B", 4.0);
+
+        let mut replayed = engine();
+        let mut buf = buffer(LatePolicy::Replay);
+        buf.process(&mut replayed, events.clone()).unwrap();
+        let report = buf.process(&mut replayed, vec![late.clone()]).unwrap();
+        assert_eq!(report.late[0].action, LateAction::Replayed);
+        assert_eq!(report.late[0].watermark, 6);
+        assert!(replayed.state_at(1).unwrap().state().edge("A", "B").is_some());
+        replayed.verify_replay().unwrap();
+
+        let mut at_now = engine();
+        let mut buf = buffer(LatePolicy::ApplyAtNow);
+        buf.process(&mut at_now, events).unwrap();
+        let report = buf.process(&mut at_now, vec![late]).unwrap();
+        assert_eq!(report.late[0].action, LateAction::AppliedAtNow);
+        assert!(at_now.state_at(1).unwrap().state().edge("A", "B").is_none());
+
+        let ab_replayed = replayed.state().edge("A", "B").unwrap().data[[0]];
+        let ab_now = at_now.state().edge("A", "B").unwrap().data[[0]];
+        approx::assert_relative_eq!(ab_replayed, ab_now);
+    }
+
+    #[test]
+    fn test_failed_steps_keep_events_buffered() {
+        let mut engine = engine();
+        let mut buf = buffer(LatePolicy::ApplyAtNow);
+        let mut bad = pair("bad", 3, "C", "A", 1.0);
+        if let Event::Pair(e) = &mut bad {
+            e.de = EvidenceTensor::new(vec![1.0, 0.0, 0.0]);
+        }
+        buf.process(&mut engine, vec![pair("a", 1, "A", "B", 1.0), bad]).unwrap();
+        let err = buf.process(&mut engine, vec![pair("b", 7, "B", "C", 1.0)]);
+        assert!(err.is_err());
+        // t=1 was applied; the failing t=3 batch and the rest wait in the buffer
+        assert_eq!(engine.state().t, 1);
+        assert_eq!((buf.pending(), buf.watermark()), (2, Some(4)));
+
+        // A failing late event leaves the buffer and watermark as they were
+        let mut late = pair("late", 0, "A", "C", 1.0);
+        if let Event::Pair(e) = &mut late {
+            e.de = EvidenceTensor::new(vec![-1.0, 0.0]);
+        }
+        assert!(buf.process(&mut engine, vec![late, pair("c", 20, "C", "B", 1.0)]).is_err());
+        assert_eq!((buf.pending(), buf.watermark()), (2, Some(4)));
+    }
+
+    #[test]
+    fn test_invalid_late_events_apply_nothing() {
+        let mut engine = engine();
+        let mut buf = buffer(LatePolicy::ApplyAtNow);
+        buf.process(&mut engine, vec![pair("a", 5, "A", "B", 1.0)]).unwrap();
+        buf.flush(&mut engine).unwrap();
+        let state = engine.state().clone();
+
+        // The first late event is valid, the second is a self-edge
+        let late = vec![pair("b", 2, "B", "C", 1.0), pair("c", 3, "C", "C", 1.0)];
+        assert!(buf.process(&mut engine, late).unwrap_err().contains("self-edge"));
+        assert_eq!(engine.state(), &state);
+        assert_eq!(engine.history().unwrap().log().entries().len(), 1);
+
+        let mut replay = buffer(LatePolicy::Replay);
+        let mut plain = EqbslEngine::new(engine.params().clone(), state).unwrap();
+        assert!(replay.process(&mut plain, vec![pair("b", 2, "B", "C", 1.0)]).unwrap_err().contains("history"));
+    }
+}
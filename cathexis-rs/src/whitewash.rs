@@ -0,0 +1,311 @@
+use crate::eqbsl::TrustEmbedding;
+use crate::events::Event;
+use crate::operators;
+use crate::state::{EqbslState, NodeId, Params};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Parameters of the whitewashing detector.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitewashConfig {
+    /// An agent is penalised when the opinion lifted from its summed inbound evidence has
+    /// expectation at most this...
+    pub penalised_max_expectation: f64,
+    /// ...and uncertainty at most this (the penalty rests on real evidence).
+    pub penalised_max_uncertainty: f64,
+    /// An agent is new when its inbound opinion is still this uncertain (near vacuous)...
+    pub newcomer_min_uncertainty: f64,
+    /// ...and, with activity data, it first appeared after the penalised agent was last
+    /// seen, or within this many time units of the state time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub newcomer_window: Option<i64>,
+    /// Candidates must share at least this many counterparties.
+    pub min_shared_counterparties: usize,
+    /// Links below this confidence are not reported.
+    pub min_confidence: f64,
+    /// Weights of the counterparty, timing and embedding similarities in the confidence.
+    pub weights: [f64; 3],
+    /// Activity times are folded modulo `period` into `bins` slots for the timing pattern.
+    pub period: i64,
+    pub bins: usize,
+}
+
+impl Default for WhitewashConfig {
+    fn default() -> Self {
+        Self {
+            penalised_max_expectation: 0.35,
+            penalised_max_uncertainty: 0.5,
+            newcomer_min_uncertainty: 0.5,
+            newcomer_window: None,
+            min_shared_counterparties: 1,
+            min_confidence: 0.5,
+            weights: [0.5, 0.2, 0.3],
+            period: 24,
+            bins: 24,
+        }
+    }
+}
+
+impl WhitewashConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, x) in [
+            ("penalised_max_expectation", self.penalised_max_expectation),
+            ("penalised_max_uncertainty", self.penalised_max_uncertainty),
+            ("newcomer_min_uncertainty", self.newcomer_min_uncertainty),
+            ("min_confidence", self.min_confidence),
+        ] {
+            if !(0.0..=1.0).contains(&x) {
+                return Err(format!("{} must be in [0,1], got {}", name, x));
+            }
+        }
+        if self.weights.iter().any(|w| !(*w >= 0.0 && w.is_finite())) || self.weights.iter().sum::<f64>() <= 0.0 {
+            return Err(format!("Similarity weights must be >= 0 and not all zero, got {:?}", self.weights));
+        }
+        if self.newcomer_window.is_some_and(|w| w < 0) {
+            return Err(format!("newcomer_window must be nonnegative, got {:?}", self.newcomer_window));
+        }
+        if self.period <= 0 || self.bins == 0 {
+            return Err(format!("Timing needs period > 0 and bins > 0, got {} and {}", self.period, self.bins));
+        }
+        Ok(())
+    }
+}
+
+/// When an agent was active: first and last event time and a histogram of event times
+/// folded over the configured period.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivityProfile {
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub events: usize,
+    pub slots: Vec<f64>,
+}
+
+/// Activity profiles of every agent taking part in observed events (either endpoint of a
+/// pair event, every participant of a hyperedge event).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Activity {
+    pub agents: BTreeMap<NodeId, ActivityProfile>,
+}
+
+impl Activity {
+    /// Records `events`. Hyperedge events that omit their nodes (later events on an
+    /// existing hyperedge) count for the members of that hyperedge in `state`.
+    pub fn observe(&mut self, config: &WhitewashConfig, state: &EqbslState, events: &[Event]) {
+        for event in events {
+            let t = event.t();
+            let slot = (t.rem_euclid(config.period) as u128 * config.bins as u128 / config.period as u128) as usize;
+            let agents: Vec<&NodeId> = match event {
+                Event::Pair(e) => vec![&e.src, &e.dst],
+                Event::Hyper(e) => {
+                    let nodes = if e.nodes.is_empty() {
+                        state.hyperedges.get(&e.hid).map(|h| &h.nodes[..]).unwrap_or(&[])
+                    } else {
+                        &e.nodes[..]
+                    };
+                    nodes.iter().collect()
+                }
+            };
+            for agent in agents {
+                let profile = self.agents.entry(agent.clone()).or_insert_with(|| ActivityProfile {
+                    first_seen: t,
+                    last_seen: t,
+                    events: 0,
+                    slots: vec![0.0; config.bins],
+                });
+                profile.first_seen = profile.first_seen.min(t);
+                profile.last_seen = profile.last_seen.max(t);
+                profile.events += 1;
+                profile.slots[slot] += 1.0;
+            }
+        }
+    }
+}
+
+/// A new agent suspected to be a fresh identity of a penalised one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CandidateLink {
+    pub new_agent: NodeId,
+    pub penalised_agent: NodeId,
+    /// Weighted mean of the available similarities, in [0, 1].
+    pub confidence: f64,
+    /// Jaccard similarity of the two counterparty sets.
+    pub counterparty: f64,
+    /// Similarity of the activity patterns; `None` without activity data.
+    pub timing: Option<f64>,
+    /// Similarity of the outbound half of the trust embeddings.
+    pub embedding: f64,
+    pub shared_counterparties: Vec<NodeId>,
+}
+
+/// Result of the whitewashing detector.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WhitewashReport {
+    /// Candidate links, highest confidence first: the review queue.
+    pub links: Vec<CandidateLink>,
+}
+
+impl WhitewashReport {
+    /// Highest confidence of any link from `agent_id` to a penalised agent, 0 if none.
+    pub fn score(&self, agent_id: &str) -> f64 {
+        self.links
+            .iter()
+            .filter(|l| l.new_agent == agent_id)
+            .map(|l| l.confidence)
+            .fold(0.0, f64::max)
+    }
+
+    /// `score` of every agent with a link; agents not listed score 0.
+    pub fn scores(&self) -> BTreeMap<NodeId, f64> {
+        let mut scores = BTreeMap::new();
+        for l in &self.links {
+            let score = scores.entry(l.new_agent.clone()).or_insert(0.0);
+            *score = f64::max(*score, l.confidence);
+        }
+        scores
+    }
+
+    /// Links of `agent_id`, in either role, highest confidence first.
+    pub fn links_for(&self, agent_id: &str) -> Vec<&CandidateLink> {
+        self.links
+            .iter()
+            .filter(|l| l.new_agent == agent_id || l.penalised_agent == agent_id)
+            .collect()
+    }
+
+    /// Review queue: links with confidence at least `min_confidence`, highest first.
+    pub fn review_queue(&self, min_confidence: f64) -> impl Iterator<Item = &CandidateLink> {
+        self.links.iter().take_while(move |l| l.confidence >= min_confidence)
+    }
+}
+
+/// Links new agents (near-vacuous inbound opinion, and with `activity` first seen after the
+/// penalised agent was last seen or within `newcomer_window`) to penalised ones (confident,
+/// low expectation) that share counterparties with them, the signature of an identity reset:
+/// the old identity's reputation is abandoned but its trading partners, activity rhythm
+/// and the way it rates others carry over.
+///
+/// Confidence is the weighted mean of counterparty overlap, timing similarity (cosine of
+/// the activity histograms, halved when the two identities were active at the same time)
+/// and embedding similarity. Without `activity` the timing term is left out.
+pub fn detect(
+    state: &EqbslState,
+    params: &Params,
+    activity: Option<&Activity>,
+    config: &WhitewashConfig,
+) -> Result<WhitewashReport, String> {
+    config.validate()?;
+    let mut inbound: BTreeMap<&NodeId, (f64, f64)> = BTreeMap::new();
+    for ((_, j), e) in &state.edges {
+        let (r, s) = params.rs(e)?;
+        let acc = inbound.entry(j).or_insert((0.0, 0.0));
+        *acc = (acc.0 + r, acc.1 + s);
+    }
+    let nodes = state.nodes();
+    let (mut penalised, mut newcomers) = (Vec::new(), BTreeSet::new());
+    for v in &nodes {
+        let (r, s) = inbound.get(v).copied().unwrap_or((0.0, 0.0));
+        let op = params.lift(r, s);
+        if op.u <= config.penalised_max_uncertainty && op.expected_probability() <= config.penalised_max_expectation {
+            penalised.push(v);
+        } else if op.u >= config.newcomer_min_uncertainty {
+            newcomers.insert(v);
+        }
+    }
+    if penalised.is_empty() || newcomers.is_empty() {
+        return Ok(WhitewashReport::default());
+    }
+
+    let adj = operators::neighbours(state);
+    let embeddings = operators::embed_nodes(state, params)?;
+    let empty = BTreeSet::new();
+    let mut links = Vec::new();
+    for old in penalised {
+        let old_peers = adj.get(old).unwrap_or(&empty);
+        // Candidates: newcomers reachable through a shared counterparty
+        let candidates: BTreeSet<&NodeId> = old_peers
+            .iter()
+            .flat_map(|p| adj.get(p).into_iter().flatten())
+            .filter(|v| newcomers.contains(*v) && **v != old)
+            .copied()
+            .collect();
+        for new in candidates {
+            if let Some(activity) = activity {
+                if !is_newer(activity, old, new, state.t, config) {
+                    continue;
+                }
+            }
+            let new_peers = adj.get(new).unwrap_or(&empty);
+            let shared: Vec<NodeId> = old_peers
+                .intersection(new_peers)
+                .filter(|p| **p != old && **p != new)
+                .map(|p| (*p).clone())
+                .collect();
+            if shared.len() < config.min_shared_counterparties || shared.is_empty() {
+                continue;
+            }
+            let union = old_peers.union(new_peers).filter(|p| **p != old && **p != new).count();
+            let counterparty = shared.len() as f64 / union as f64;
+            let timing = activity.and_then(|a| Some(timing_similarity(a.agents.get(old)?, a.agents.get(new)?)));
+            let embedding = embedding_similarity(&embeddings[old], &embeddings[new]);
+
+            let [wc, wt, we] = config.weights;
+            let (mut total, mut weight) = (wc * counterparty + we * embedding, wc + we);
+            if let Some(timing) = timing {
+                total += wt * timing;
+                weight += wt;
+            }
+            let confidence = if weight > 0.0 { total / weight } else { 0.0 };
+            if confidence >= config.min_confidence {
+                links.push(CandidateLink {
+                    new_agent: new.clone(),
+                    penalised_agent: old.clone(),
+                    confidence,
+                    counterparty,
+                    timing,
+                    embedding,
+                    shared_counterparties: shared,
+                });
+            }
+        }
+    }
+    links.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| a.new_agent.cmp(&b.new_agent))
+            .then_with(|| a.penalised_agent.cmp(&b.penalised_agent))
+    });
+    Ok(WhitewashReport { links })
+}
+
+/// Whether `new` first appeared after `old` was last seen, or within the newcomer window.
+/// Agents without a profile were present before tracking started and are never new.
+fn is_newer(activity: &Activity, old: &str, new: &str, now: i64, config: &WhitewashConfig) -> bool {
+    let Some(first_seen) = activity.agents.get(new).map(|p| p.first_seen) else {
+        return false;
+    };
+    let after_old = activity.agents.get(old).is_some_and(|p| first_seen > p.last_seen);
+    after_old || config.newcomer_window.is_some_and(|w| first_seen >= now - w)
+}
+
+fn timing_similarity(old: &ActivityProfile, new: &ActivityProfile) -> f64 {
+    let dot: f64 = old.slots.iter().zip(&new.slots).map(|(a, b)| a * b).sum();
+    let norm = |x: &[f64]| x.iter().map(|v| v * v).sum::<f64>().sqrt();
+    let (na, nb) = (norm(&old.slots), norm(&new.slots));
+    let cosine = if na > 0.0 && nb > 0.0 { dot / (na * nb) } else { 0.0 };
+    // A reset identity takes over where the old one stopped
+    if new.first_seen >= old.last_seen {
+        cosine
+    } else {
+        cosine / 2.0
+    }
+}
+
+/// 1 / (1 + distance) on [out_expect_mean, out_u_mean, ln(1 + out_count)] of the baseline
+/// embedding: how an agent rates others survives a reset, how it is rated does not.
+fn embedding_similarity(a: &TrustEmbedding, b: &TrustEmbedding) -> f64 {
+    let outbound = |e: &TrustEmbedding| [e.vector[2], e.vector[3], e.vector[5].ln_1p()];
+    let (a, b) = (outbound(a), outbound(b));
+    let distance = a.iter().zip(&b).map(|(x, y)| (x - y) * (x - y)).sum::<f64>().sqrt();
+    1.0 / (1.0 + distance)
+}
@@ -0,0 +1,287 @@
+use crate::canonical::Digest;
+use crate::core::Opinion;
+use crate::state::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A candidate witness k ∈ out(i) with its lifted opinion ω_ik and discount δ_ik = E(ω_ik).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitnessCandidate {
+    pub witness: NodeId,
+    pub delta: f64,
+    pub opinion: Opinion,
+}
+
+/// Selection of the witness set N(i) used by transitive aggregation (Primer Section 7).
+///
+/// `select` receives i's outgoing candidates ranked by δ descending, ties by node id, and
+/// returns the chosen subset in the same order. A policy may only look at the source, the
+/// candidates and its own configuration, so N(i) depends on i's outgoing edges alone.
+pub trait WitnessPolicy {
+    fn select(&self, source: &str, candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate>;
+}
+
+/// Keeps the K candidates with the highest discount.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopK {
+    pub k: usize,
+}
+
+impl WitnessPolicy for TopK {
+    fn select(&self, _source: &str, mut candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        candidates.truncate(self.k);
+        candidates
+    }
+}
+
+/// Keeps candidates whose opinion certainty 1 - u reaches `min_certainty`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinCertainty {
+    pub min_certainty: f64,
+}
+
+impl WitnessPolicy for MinCertainty {
+    fn select(&self, _source: &str, mut candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        candidates.retain(|c| 1.0 - c.opinion.u >= self.min_certainty);
+        candidates
+    }
+}
+
+/// Keeps candidates in the same context (e.g. marketplace, community) as the source.
+/// Nodes without a context match nothing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SameContext {
+    pub contexts: BTreeMap<NodeId, String>,
+}
+
+impl WitnessPolicy for SameContext {
+    fn select(&self, source: &str, mut candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        match self.contexts.get(source) {
+            Some(context) => candidates.retain(|c| self.contexts.get(&c.witness) == Some(context)),
+            None => candidates.clear(),
+        }
+        candidates
+    }
+}
+
+/// Keeps a pseudo-random subset of `size` candidates. The draw for (seed, i, k) is a hash,
+/// so the same seed always selects the same witnesses on every platform.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeededSample {
+    pub seed: u64,
+    pub size: usize,
+}
+
+impl WitnessPolicy for SeededSample {
+    fn select(&self, source: &str, candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        if candidates.len() <= self.size {
+            return candidates;
+        }
+        let mut draws: Vec<(Digest, usize)> = candidates
+            .iter()
+            .enumerate()
+            .map(|(n, c)| (Digest::of("eqbsl/witness-sample/v1", &(self.seed, source, c.witness.as_str())), n))
+            .collect();
+        draws.sort();
+        let mut keep = vec![false; candidates.len()];
+        for (_, n) in draws.into_iter().take(self.size) {
+            keep[n] = true;
+        }
+        candidates.into_iter().zip(keep).filter_map(|(c, k)| k.then_some(c)).collect()
+    }
+}
+
+/// Drops candidates in the same suspected collusion cluster as the source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExcludeClusters {
+    pub clusters: BTreeMap<NodeId, String>,
+}
+
+impl WitnessPolicy for ExcludeClusters {
+    fn select(&self, source: &str, mut candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        if let Some(cluster) = self.clusters.get(source) {
+            candidates.retain(|c| self.clusters.get(&c.witness) != Some(cluster));
+        }
+        candidates
+    }
+}
+
+/// A user-defined policy, plugged into `Params::witness_policy` under a name. Only the name
+/// is serialised and committed to by `Params::digest`, so the name must identify the
+/// policy's behaviour; after deserialising, re-attach the implementation with
+/// `Params::attach_witness_policy`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CustomPolicy {
+    pub name: String,
+    #[serde(skip)]
+    policy: Option<Arc<dyn WitnessPolicy + Send + Sync>>,
+}
+
+impl CustomPolicy {
+    pub fn new(name: &str, policy: Arc<dyn WitnessPolicy + Send + Sync>) -> Self {
+        Self {
+            name: name.to_string(),
+            policy: Some(policy),
+        }
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.policy.is_some()
+    }
+
+    pub(crate) fn attach(&mut self, policy: Arc<dyn WitnessPolicy + Send + Sync>) {
+        self.policy = Some(policy);
+    }
+}
+
+/// Policies compare by name, as they are committed to.
+impl PartialEq for CustomPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for CustomPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomPolicy")
+            .field("name", &self.name)
+            .field("attached", &self.is_attached())
+            .finish()
+    }
+}
+
+/// A detached policy (see `is_attached`) selects nothing; `Params::validate` rejects it.
+impl WitnessPolicy for CustomPolicy {
+    fn select(&self, source: &str, candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        match &self.policy {
+            Some(policy) => policy.select(source, candidates),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Serialisable choice of policy, stored in `Params::witness_policy`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum WitnessPolicyConfig {
+    TopK(TopK),
+    MinCertainty(MinCertainty),
+    SameContext(SameContext),
+    SeededSample(SeededSample),
+    ExcludeClusters(ExcludeClusters),
+    Custom(CustomPolicy),
+}
+
+impl WitnessPolicyConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            WitnessPolicyConfig::MinCertainty(p) if !(0.0..=1.0).contains(&p.min_certainty) => {
+                Err(format!("min_certainty must be in [0,1], got {}", p.min_certainty))
+            }
+            WitnessPolicyConfig::Custom(p) if !p.is_attached() => {
+                Err(format!("Custom witness policy '{}' is not attached", p.name))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl WitnessPolicy for WitnessPolicyConfig {
+    fn select(&self, source: &str, candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        match self {
+            WitnessPolicyConfig::TopK(p) => p.select(source, candidates),
+            WitnessPolicyConfig::MinCertainty(p) => p.select(source, candidates),
+            WitnessPolicyConfig::SameContext(p) => p.select(source, candidates),
+            WitnessPolicyConfig::SeededSample(p) => p.select(source, candidates),
+            WitnessPolicyConfig::ExcludeClusters(p) => p.select(source, candidates),
+            WitnessPolicyConfig::Custom(p) => p.select(source, candidates),
+        }
+    }
+}
+
+/// Policies applied one after another, each to the survivors of the previous one.
+impl<P: WitnessPolicy> WitnessPolicy for [P] {
+    fn select(&self, source: &str, candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        self.iter().fold(candidates, |acc, p| p.select(source, acc))
+    }
+}
+
+/// Ranks i's outgoing opinions by δ descending (ties by node id), applies `policy`, and caps
+/// the result at `top_k`. Returns N(i) as (δ_ik, k), best first.
+pub fn select_witnesses<'a, P: WitnessPolicy + ?Sized>(
+    source: &str,
+    outgoing: impl IntoIterator<Item = (&'a NodeId, &'a Opinion)>,
+    policy: &P,
+    top_k: usize,
+) -> Vec<(f64, NodeId)> {
+    let mut candidates: Vec<WitnessCandidate> = outgoing
+        .into_iter()
+        .map(|(k, op)| WitnessCandidate {
+            witness: k.clone(),
+            delta: op.expected_probability(),
+            opinion: *op,
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.delta.total_cmp(&a.delta).then_with(|| a.witness.cmp(&b.witness)));
+    let mut selected = policy.select(source, candidates);
+    selected.truncate(top_k);
+    selected.into_iter().map(|c| (c.delta, c.witness)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<WitnessCandidate> {
+        [("a", 0.9, 0.1), ("b", 0.8, 0.3), ("c", 0.7, 0.2), ("d", 0.6, 0.3)]
+            .iter()
+            .map(|&(k, delta, u)| WitnessCandidate {
+                witness: k.to_string(),
+                delta,
+                opinion: Opinion::new(delta - u * 0.5, 1.0 - (delta - u * 0.5) - u, u, 0.5).unwrap(),
+            })
+            .collect()
+    }
+
+    fn ids(selected: &[WitnessCandidate]) -> Vec<&str> {
+        selected.iter().map(|c| c.witness.as_str()).collect()
+    }
+
+    #[test]
+    fn test_builtin_policies() {
+        assert_eq!(ids(&TopK { k: 2 }.select("i", candidates())), ["a", "b"]);
+        assert_eq!(ids(&MinCertainty { min_certainty: 0.75 }.select("i", candidates())), ["a", "c"]);
+
+        let contexts: BTreeMap<NodeId, String> = [("i", "x"), ("a", "y"), ("b", "x"), ("d", "x")]
+            .iter()
+            .map(|(n, c)| (n.to_string(), c.to_string()))
+            .collect();
+        let same = SameContext { contexts: contexts.clone() };
+        assert_eq!(ids(&same.select("i", candidates())), ["b", "d"]);
+        assert!(same.select("z", candidates()).is_empty());
+        let exclude = ExcludeClusters { clusters: contexts };
+        assert_eq!(ids(&exclude.select("i", candidates())), ["a", "c"]);
+
+        let sample = SeededSample { seed: 42, size: 2 };
+        let first = sample.select("i", candidates());
+        assert_eq!(first.len(), 2);
+        assert_eq!(first, sample.select("i", candidates()));
+        assert!(first[0].delta > first[1].delta);
+    }
+
+    #[test]
+    fn test_policy_chain_and_serde() {
+        let chain = vec![
+            WitnessPolicyConfig::MinCertainty(MinCertainty { min_certainty: 0.75 }),
+            WitnessPolicyConfig::TopK(TopK { k: 2 }),
+        ];
+        assert_eq!(ids(&chain.as_slice().select("i", candidates())), ["a", "c"]);
+
+        let json = serde_json::to_string(&chain).unwrap();
+        assert!(json.contains("\"policy\":\"min_certainty\""));
+        let back: Vec<WitnessPolicyConfig> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, chain);
+    }
+}
@@ -0,0 +1,98 @@
+use cathexis::aggregation::Aggregation;
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, PairEvent};
+use cathexis::exact::Rational;
+use cathexis::numeric::Q32;
+use cathexis::operators;
+use cathexis::sparse;
+use cathexis::{EqbslEngine, EqbslState, Params};
+use num_traits::Zero;
+
+fn pair(id: String, src: &str, dst: &str, r: f64, s: f64) -> Event {
+    Event::Pair(PairEvent {
+        id,
+        t: 1,
+        src: src.to_string(),
+        dst: dst.to_string(),
+        de: EvidenceTensor::new(vec![r, s]),
+    })
+}
+
+/// i trusts six honest witnesses and two colluders equally; honest witnesses hold modest
+/// evidence about j, the colluders inflate j.
+fn colluded_state() -> EqbslState {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    let mut events = Vec::new();
+    for k in 0..6 {
+        let w = format!("h{}", k);
+        events.push(pair(format!("i-{}", w), "i", &w, 10.0, 0.0));
+        events.push(pair(format!("{}-j", w), &w, "j", 2.0 + 0.1 * k as f64, 1.0));
+    }
+    for c in ["c1", "c2"] {
+        events.push(pair(format!("i-{}", c), "i", c, 10.0, 0.0));
+        events.push(pair(format!("{}-j", c), c, "j", 500.0, 0.0));
+    }
+    engine.step(1, events).unwrap();
+    engine.state().clone()
+}
+
+#[test]
+fn test_robust_modes_bound_collusion() {
+    let state = colluded_state();
+    let mut params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]).unwrap();
+    let r_ij = |params: &Params| {
+        let dense = operators::propagate(&state, params).unwrap();
+        assert_eq!(dense, sparse::propagate(&state, params).unwrap(), "{:?}", params.aggregation);
+        dense[&("i".to_string(), "j".to_string())].0
+    };
+
+    let summed = r_ij(&params);
+    assert!(summed > 400.0);
+
+    // Every witness report is λ δ (r_kj): the honest range scaled by the panel of 8
+    let delta = params.lift(10.0, 0.0).expected_probability();
+    let w = params.damping_lambda * delta;
+    let honest = (8.0 * w * 2.0, 8.0 * w * 2.5);
+
+    for mode in [Aggregation::TrimmedMean { trim: 0.25 }, Aggregation::MedianOfMeans { groups: 5 }] {
+        params.aggregation = mode;
+        let r = r_ij(&params);
+        assert!(r >= honest.0 - 1e-9 && r <= honest.1 + 1e-9, "{:?}: {}", params.aggregation, r);
+    }
+
+    params.aggregation = Aggregation::InfluenceCap { cap: 3.0 };
+    let honest_sum: f64 = (0..6).map(|k| w * (2.0 + 0.1 * k as f64)).sum();
+    assert!(r_ij(&params) <= honest_sum + 2.0 * 3.0 + 1e-9);
+
+    // Selectable through the serialised parameters
+    let json = serde_json::to_string(&params).unwrap();
+    assert!(json.contains("\"mode\":\"influence_cap\""));
+    let restored: Params = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.aggregation, params.aggregation);
+}
+
+#[test]
+fn test_small_and_empty_panels() {
+    let modes = [
+        Aggregation::Sum,
+        Aggregation::InfluenceCap { cap: 3.0 },
+        Aggregation::TrimmedMean { trim: 0.25 },
+        Aggregation::MedianOfMeans { groups: 5 },
+    ];
+    for mode in &modes {
+        assert_eq!(mode.aggregate::<f64>(&[], 0), (0.0, 0.0), "{:?}", mode);
+        assert_eq!(mode.aggregate::<Q32>(&[], 0), (Q32::zero(), Q32::zero()), "{:?}", mode);
+        assert_eq!(mode.aggregate::<Rational>(&[], 0), (Rational::zero(), Rational::zero()), "{:?}", mode);
+    }
+
+    // Fewer witnesses than groups: one group each, i.e. n × the median report
+    let reports = [(1.0, 0.0), (2.0, 1.0), (9.0, 0.0)];
+    assert_eq!(Aggregation::MedianOfMeans { groups: 5 }.aggregate(&reports, 3), (6.0, 0.0));
+
+    // Fewer reports than witnesses: the silent (0, 0) reports are dealt after the nonzero
+    // ones, so each group pairs one report with a silent witness; the group means are
+    // (2, 0), (1, 0) and (4, 1)
+    let reports = [(4.0, 0.0), (2.0, 0.0), (8.0, 2.0)];
+    assert_eq!(Aggregation::MedianOfMeans { groups: 3 }.aggregate(&reports, 6), (12.0, 0.0));
+}
@@ -0,0 +1,164 @@
+use cathexis::anomaly::{AnomalyConfig, AnomalyDetector, Signal};
+use cathexis::invariants::AuditMode;
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, PairEvent};
+use cathexis::{EqbslEngine, EqbslState, Params};
+
+const AGENTS: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+fn pair(id: String, t: i64, src: &str, dst: &str, r: f64, s: f64) -> Event {
+    Event::Pair(PairEvent {
+        id,
+        t,
+        src: src.to_string(),
+        dst: dst.to_string(),
+        de: EvidenceTensor::new(vec![r, s]),
+    })
+}
+
+/// Every agent rates the next one with one unit of positive evidence per step.
+fn routine(t: i64) -> Vec<Event> {
+    (0..AGENTS.len())
+        .map(|n| {
+            let (src, dst) = (AGENTS[n], AGENTS[(n + 1) % AGENTS.len()]);
+            pair(format!("{}-{}{}", t, src, dst), t, src, dst, 1.0, 0.0)
+        })
+        .collect()
+}
+
+/// Ten events of positive evidence from one farmer to "b", and a slander campaign on "d"
+/// spread over many sources.
+fn attack(t: i64) -> Vec<Event> {
+    let mut events = routine(t);
+    for n in 0..10 {
+        events.push(pair(format!("farm{}", n), t, "farmer", "b", 3.0, 0.0));
+    }
+    for n in 0..12 {
+        events.push(pair(format!("slander{}", n), t, &format!("troll{}", n), "d", 0.0, 2.0));
+    }
+    events
+}
+
+fn setup(config: AnomalyConfig) -> (EqbslEngine, AnomalyDetector) {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.9]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    let mut detector = AnomalyDetector::new(config).unwrap();
+    for t in 1..=6 {
+        let report = detector.process(&mut engine, t, routine(t)).unwrap();
+        assert!(report.alerts.is_empty(), "t={}: {:?}", t, report.alerts);
+    }
+    (engine, detector)
+}
+
+#[test]
+fn test_bursts_raise_alerts() {
+    let (mut engine, mut detector) = setup(AnomalyConfig::default());
+    let report = detector.process(&mut engine, 7, attack(7)).unwrap();
+    assert_eq!(report.alerts.len(), 2);
+    assert_eq!(report.applied, 27);
+
+    let farming = report.alerts.iter().find(|a| a.agent == "b").unwrap();
+    assert_eq!(farming.channel, 0);
+    assert_eq!(farming.signals, vec![Signal::ZScore, Signal::RateChange, Signal::Concentration]);
+    assert_eq!(farming.top_counterparty, "farmer");
+    assert!(farming.rs.0 > 30.0 && farming.rs.1 == 0.0);
+    assert_eq!(farming.events.len(), 11);
+
+    let slander = report.alerts.iter().find(|a| a.agent == "d").unwrap();
+    assert_eq!(slander.channel, 1);
+    assert_eq!(slander.signals, vec![Signal::ZScore, Signal::RateChange]);
+    assert!(slander.concentration < 0.1);
+    assert_eq!(slander.rs, (0.0, 24.0));
+
+    // Without quarantine the flood reaches the engine and enters the baseline
+    assert!(engine.state().edge("farmer", "b").is_some());
+    assert!(detector.baseline("b", 0).unwrap().mean > 5.0);
+}
+
+#[test]
+fn test_warmup_suppresses_alerts() {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.9]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    let mut detector = AnomalyDetector::new(AnomalyConfig::default()).unwrap();
+    assert!(detector.process(&mut engine, 1, attack(1)).unwrap().alerts.is_empty());
+    assert!(AnomalyDetector::new(AnomalyConfig {
+        alpha: 0.0,
+        ..Default::default()
+    })
+    .is_err());
+}
+
+#[test]
+fn test_quarantine_holds_evidence_until_review() {
+    let config = AnomalyConfig {
+        quarantine: true,
+        ..Default::default()
+    };
+    let (mut engine, mut detector) = setup(config);
+    let before = engine.state().edge("a", "b").cloned();
+    let report = detector.process(&mut engine, 7, attack(7)).unwrap();
+
+    // Everything on an alerted channel is held, including the routine a→b; the routine c→d
+    // is on the positive channel while d's alert is on the negative one
+    assert_eq!(report.quarantined.len(), 23);
+    assert_eq!(report.applied, 4);
+    assert!(engine.state().edge("farmer", "b").is_none());
+    assert_eq!(engine.state().edge("a", "b").cloned(), before.map(|e| {
+        let mut e = e;
+        e.data *= 0.9;
+        e
+    }));
+    // The baseline did not learn the flood
+    assert!(detector.baseline("b", 0).unwrap().mean < 1.5);
+
+    detector.release(&mut engine, "7-ab").unwrap();
+    assert!(engine.state().edge("a", "b").is_some());
+    let rejected = detector.reject("farm0").unwrap();
+    assert_eq!(rejected.id(), "farm0");
+    assert!(detector.reject("farm0").is_err());
+    assert!(detector.release(&mut engine, "nope").is_err());
+    assert_eq!(detector.quarantined().len(), 21);
+
+    let json = serde_json::to_string(&detector).unwrap();
+    let restored: AnomalyDetector = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.quarantined(), detector.quarantined());
+}
+
+#[test]
+fn test_rejected_steps_leave_the_detector_unchanged() {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.9]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0))
+        .unwrap()
+        .with_derived_cache(false)
+        .unwrap()
+        .with_auditor(AuditMode::Strict);
+    let config = AnomalyConfig {
+        quarantine: true,
+        ..Default::default()
+    };
+    let mut detector = AnomalyDetector::new(config).unwrap();
+    detector.process(&mut engine, 1, routine(1)).unwrap();
+    let snapshot = serde_json::to_string(&detector).unwrap();
+
+    // Still in warmup, so nothing is held; the step is valid, but propagating it overflows
+    // and the engine rejects it
+    let mut flood = routine(2);
+    flood.push(pair("big1".to_string(), 2, "e", "b", 1.7e308, 0.0));
+    flood.push(pair("big2".to_string(), 2, "a", "b", 1.7e308, 0.0));
+    assert!(detector.process(&mut engine, 2, flood).unwrap_err().contains("propagated"));
+    assert_eq!(serde_json::to_string(&detector).unwrap(), snapshot);
+
+    let mut twice = routine(2);
+    twice.push(twice[0].clone());
+    assert!(detector.process(&mut engine, 2, twice).unwrap_err().contains("Duplicate"));
+    assert_eq!(serde_json::to_string(&detector).unwrap(), snapshot);
+
+    for t in 2..=6 {
+        detector.process(&mut engine, t, routine(t)).unwrap();
+    }
+
+    let report = detector.process(&mut engine, 7, attack(7)).unwrap();
+    assert!(!report.quarantined.is_empty());
+    let again = vec![pair("farm0".to_string(), 8, "farmer", "b", 1.0, 0.0)];
+    assert!(detector.process(&mut engine, 8, again).unwrap_err().contains("already quarantined"));
+}
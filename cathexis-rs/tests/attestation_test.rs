@@ -0,0 +1,141 @@
+use cathexis::attestation::{HandleClaim, HandleSigner, KeyRing, SignedHandle};
+use cathexis::canonical::Digest;
+use cathexis::categoriser::MLPCategoriser;
+use cathexis::eqbsl::{EvidenceTensor, TrustGraph};
+use cathexis::events::{Event, PairEvent};
+use cathexis::labeling::DummyLabeler;
+use cathexis::pipeline::{AgentHandleResponse, CathexisPipeline};
+use cathexis::{EqbslEngine, EqbslState, Params};
+use ed25519_dalek::SigningKey;
+use ndarray::{Array1, Array2};
+
+const DAY: i64 = 86_400;
+
+fn response() -> AgentHandleResponse {
+    AgentHandleResponse {
+        category_id: 2,
+        probabilities: vec![0.1, 0.2, 0.7],
+        label: "Reliable trader".to_string(),
+        description: "Consistent positive evidence".to_string(),
+        guidance: None,
+        explanation: None,
+    }
+}
+
+fn claim(issued_at: i64) -> HandleClaim {
+    HandleClaim::new("agent_1", &response(), Digest::of("root", &1u64), issued_at, issued_at + DAY).unwrap()
+}
+
+fn signer(key_id: &str, seed: u8) -> HandleSigner {
+    HandleSigner::new(key_id, SigningKey::from_bytes(&[seed; 32]))
+}
+
+#[test]
+fn test_signed_handles_verify_offline() {
+    let signer = signer("2026-01", 7);
+    let mut keys = KeyRing::new();
+    keys.add("2026-01", signer.verifying_key(), 0).unwrap();
+
+    let handle = signer.sign(claim(1_000));
+    handle.verify(&keys, 1_000 + 60).unwrap();
+    assert_eq!(handle.claim.label, "Reliable trader");
+
+    // Third parties receive the handle and the key ring as JSON
+    let handle: SignedHandle = serde_json::from_str(&serde_json::to_string(&handle).unwrap()).unwrap();
+    let keys: KeyRing = serde_json::from_str(&serde_json::to_string(&keys).unwrap()).unwrap();
+    handle.verify(&keys, 1_000 + 60).unwrap();
+
+    assert!(handle.verify(&keys, 999).unwrap_err().contains("not at 999"));
+    assert!(handle.verify(&keys, 1_000 + DAY).is_err());
+    assert!(HandleClaim::new("agent_1", &response(), Digest::of("root", &1u64), 5, 5).is_err());
+    assert!(format!("{:?}", signer).contains("2026-01"));
+}
+
+#[test]
+fn test_tampered_or_misattributed_handles_are_rejected() {
+    let issuer = signer("2026-01", 7);
+    let mut keys = KeyRing::new();
+    keys.add("2026-01", issuer.verifying_key(), 0).unwrap();
+    let handle = issuer.sign(claim(1_000));
+
+    let mut label = handle.clone();
+    label.claim.label = "Trusted partner".to_string();
+    assert!(label.verify(&keys, 1_500).unwrap_err().contains("Invalid signature"));
+    let mut probabilities = handle.clone();
+    probabilities.claim.probabilities[2] = 0.9;
+    assert!(probabilities.verify(&keys, 1_500).is_err());
+    let mut expiry = handle.clone();
+    expiry.claim.expires_at += DAY;
+    assert!(expiry.verify(&keys, 1_500).is_err());
+
+    // A signature by another key, or claimed under another key ID
+    let other = signer("2026-02", 8);
+    keys.add("2026-02", other.verifying_key(), 0).unwrap();
+    let mut forged = other.sign(claim(1_000));
+    forged.key_id = "2026-01".to_string();
+    assert!(forged.verify(&keys, 1_500).is_err());
+    let mut relabelled = handle.clone();
+    relabelled.key_id = "2026-02".to_string();
+    assert!(relabelled.verify(&keys, 1_500).is_err());
+    assert!(keys.add("2026-02", other.verifying_key(), 5).is_err());
+}
+
+#[test]
+fn test_key_rotation_and_revocation() {
+    let old = signer("2026-01", 7);
+    let new = signer("2026-02", 8);
+    let mut keys = KeyRing::new();
+    keys.add("2026-01", old.verifying_key(), 0).unwrap();
+    let before = old.sign(claim(1_000));
+
+    keys.rotate("2026-01", "2026-02", new.verifying_key(), 2_000).unwrap();
+    assert_eq!(keys.key_ids().collect::<Vec<_>>(), vec!["2026-01", "2026-02"]);
+    // Handles signed before the rotation stay valid until they expire
+    before.verify(&keys, 2_500).unwrap();
+    // The retired key can no longer issue handles
+    assert!(old.sign(claim(2_000)).verify(&keys, 2_500).unwrap_err().contains("not active"));
+    new.sign(claim(2_000)).verify(&keys, 2_500).unwrap();
+    assert!(new.sign(claim(1_999)).verify(&keys, 2_500).is_err());
+    assert!(keys.rotate("2026-02", "2026-03", old.verifying_key(), 1_000).is_err());
+    assert!(keys.get("2026-03").is_none());
+
+    // Revoking a compromised key invalidates everything it signed
+    keys.revoke("2026-01").unwrap();
+    assert!(before.verify(&keys, 2_500).unwrap_err().contains("revoked"));
+    assert!(keys.revoke("2026-01").is_err());
+}
+
+#[test]
+fn test_pipeline_signs_handles_against_its_state_root() {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.8]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    let pair = |id: &str, t: i64, src: &str, dst: &str| {
+        Event::Pair(PairEvent {
+            id: id.to_string(),
+            t,
+            src: src.to_string(),
+            dst: dst.to_string(),
+            de: EvidenceTensor::new(vec![3.0, 1.0]),
+        })
+    };
+    engine.step(1, vec![pair("e1", 1, "A", "B"), pair("e2", 1, "B", "C")]).unwrap();
+    let input_dim = engine.compute_features("A").unwrap().to_vector().len();
+    let w1 = Array2::from_shape_fn((4, input_dim), |(i, j)| ((i * 7 + j * 3) % 5) as f64 - 2.0);
+    let w2 = Array2::from_shape_fn((3, 4), |(i, j)| ((i + 2 * j) % 3) as f64 - 1.0);
+    let categoriser = MLPCategoriser::new(w1, Array1::zeros(4), w2, Array1::zeros(3));
+    let mut pipeline = CathexisPipeline::new(engine, categoriser, DummyLabeler);
+    pipeline.batch_process().unwrap();
+
+    let signer = signer("k1", 7);
+    let mut keys = KeyRing::new();
+    keys.add("k1", signer.verifying_key(), 0).unwrap();
+    let handle = pipeline.query_signed_handle("B", &signer, 100, 200).unwrap();
+    handle.verify(&keys, 150).unwrap();
+    assert_eq!(handle.claim.state_root, pipeline.graph.commitment().root);
+    assert_eq!(handle.claim.label, pipeline.query_agent_handle("B").unwrap().label);
+
+    // The claim follows the engine's state
+    pipeline.graph.step(2, vec![pair("e3", 2, "C", "A")]).unwrap();
+    let later = pipeline.query_signed_handle("B", &signer, 300, 400).unwrap();
+    assert_ne!(later.claim.state_root, handle.claim.state_root);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use cathexis::audit::{self, AuditLog};
+use cathexis::canonical::Digest;
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, HyperEvent};
+use cathexis::merkle::{SparseMerkleTree, StateCommitment, EMPTY};
+use cathexis::operators::Operator;
+use cathexis::proof::{self, TransparentProver};
+use cathexis::provenance::ProvenanceConfig;
+use cathexis::{EqbslEngine, EqbslState};
+use common::{pair, params};
+use std::collections::BTreeMap;
+
+/// Steps (one proof-carrying), a dispute and an amendment, all logged.
+fn engine() -> EqbslEngine {
+    let mut engine = EqbslEngine::new(params(), EqbslState::new(0))
+        .unwrap()
+        .with_provenance(ProvenanceConfig::default())
+        .unwrap()
+        .with_history(2)
+        .unwrap()
+        .with_audit_log();
+    engine.step(1, vec![pair("e1", 1, "A", "B", vec![3.0, 0.0]), pair("e2", 1, "C", "B", vec![0.0, 4.0])]).unwrap();
+    let deal = Event::Hyper(HyperEvent {
+        id: "h1".to_string(),
+        t: 2,
+        hid: "deal".to_string(),
+        nodes: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+        roles: BTreeMap::new(),
+        de: EvidenceTensor::new(vec![1.0, 0.5]),
+    });
+    engine.step_with_proof(2, vec![deal], &TransparentProver, &[]).unwrap();
+    engine.step(2, Vec::new()).unwrap();
+    engine.dispute("e2", 0.5).unwrap();
+    engine.amend(pair("late", 1, "B", "A", vec![1.0, 0.0])).unwrap();
+    engine
+}
+
+#[test]
+fn test_every_transition_is_logged_and_chained() {
+    let engine = engine();
+    let log = engine.audit_log().unwrap();
+    let operators: Vec<&[Operator]> = log.entries().iter().map(|e| e.operators.as_slice()).collect();
+    assert_eq!(
+        operators,
+        vec![
+            &[Operator::Decay, Operator::Ingest][..],
+            &[Operator::Decay, Operator::Ingest, Operator::Attribute],
+            &[Operator::Ingest],
+            &[Operator::Dispute],
+            &[Operator::Replay],
+        ]
+    );
+    let head = engine.verify_audit_log().unwrap();
+    assert_eq!(head, log.head());
+    assert_eq!(log.entries()[0].prev, EMPTY);
+    assert_eq!(log.entries()[0].pre, log.genesis());
+    assert_eq!(log.latest_root(), engine.commitment().root);
+    assert!(log.entries().iter().all(|e| e.params == params().digest()));
+    // An empty step keeps the state root but is still an entry
+    assert_eq!(log.entries()[2].pre, log.entries()[2].post);
+    assert_eq!(log.entries()[2].events, proof::events_commitment(&[]));
+
+    // The exported log verifies on its own
+    let exported: AuditLog = serde_json::from_str(&serde_json::to_string(log).unwrap()).unwrap();
+    assert_eq!(exported.verify().unwrap(), head);
+
+    let plain = EqbslEngine::new(params(), EqbslState::new(0)).unwrap();
+    assert!(plain.audit_log().is_none() && plain.verify_audit_log().is_err());
+}
+
+#[test]
+fn test_verifier_detects_tampering_and_gaps() {
+    let engine = engine();
+    let log = engine.audit_log().unwrap();
+    let genesis = log.genesis();
+    let entries = log.entries().to_vec();
+
+    let mut altered = entries.clone();
+    altered[1].events = Digest::of("other", &1u64);
+    assert!(audit::verify_chain(&genesis, &altered).unwrap_err().contains("altered"));
+
+    // Rehashing an altered entry breaks the link from its successor
+    altered[1].hash = altered[1].compute_hash();
+    assert!(audit::verify_chain(&genesis, &altered).unwrap_err().contains("entry 2"));
+
+    let mut dropped = entries.clone();
+    dropped.remove(2);
+    assert!(audit::verify_chain(&genesis, &dropped).unwrap_err().contains("Gap"));
+
+    let mut swapped = entries.clone();
+    swapped.swap(3, 4);
+    assert!(audit::verify_chain(&genesis, &swapped).is_err());
+
+    assert!(audit::verify_chain(&Digest::of("other", &2u64), &entries).unwrap_err().contains("outside"));
+
+    // Truncation keeps a valid chain, but not the published head
+    let truncated = audit::verify_chain(&genesis, &entries[..3]).unwrap();
+    assert_ne!(truncated, log.head());
+}
+
+#[test]
+fn test_entries_track_the_full_commitment_and_only_successes() {
+    let mut engine = engine();
+    let full = |engine: &EqbslEngine| StateCommitment::new(engine.state(), engine.params());
+    assert_eq!(engine.commitment(), full(&engine));
+    let proof = engine.prove_edge("A", "B").unwrap();
+    assert!(full(&engine).verify_edge(&proof, &engine.state().edges[&("A".to_string(), "B".to_string())]));
+
+    // Rejected transitions leave no entry
+    let len = engine.audit_log().unwrap().len();
+    assert!(engine.step(1, Vec::new()).is_err());
+    assert!(engine.dispute("unknown", 0.5).is_err());
+    assert_eq!(engine.audit_log().unwrap().len(), len);
+
+    // The tree is updated in place, including across decay and new edges
+    engine.step(5, vec![pair("e3", 5, "D", "A", vec![0.0, 2.0])]).unwrap();
+    let mut tree = SparseMerkleTree::from_edges(&EqbslState::<f64>::new(0).edges);
+    tree.update(&engine.state().edges, engine.state().edges.keys());
+    assert_eq!(tree.root(), full(&engine).edges);
+    tree.update(&EqbslState::<f64>::new(0).edges, engine.state().edges.keys());
+    assert_eq!((tree.root(), tree.len()), (EMPTY, 0));
+    assert_eq!(engine.audit_log().unwrap().latest_root(), full(&engine).root);
+    engine.verify_audit_log().unwrap();
+}
@@ -0,0 +1,21 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, PairEvent};
+use cathexis::Params;
+
+pub fn pair(id: &str, t: i64, src: &str, dst: &str, de: Vec<f64>) -> Event {
+    Event::Pair(PairEvent {
+        id: id.to_string(),
+        t,
+        src: src.to_string(),
+        dst: dst.to_string(),
+        de: EvidenceTensor::new(de),
+    })
+}
+
+/// Two channels (positive, negative) with β = (0.9, 0.8).
+pub fn params() -> Params {
+    Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.8]).unwrap()
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use approx::assert_relative_eq;
+use cathexis::dispute::{self, DisputeAction, DisputeStatus};
+use cathexis::eqbsl::TrustGraph;
+use cathexis::operators;
+use cathexis::provenance::ProvenanceConfig;
+use cathexis::replay::{self, EventLog};
+use cathexis::{EqbslEngine, EqbslState};
+use common::{pair, params};
+
+/// D trusts C, C leaves a revenge review on B; A vouches for B. With `revenge` false the
+/// review never happened.
+fn engine(revenge: bool) -> EqbslEngine {
+    let mut engine = EqbslEngine::new(params(), EqbslState::new(0))
+        .unwrap()
+        .with_provenance(ProvenanceConfig::default())
+        .unwrap()
+        .with_history(2)
+        .unwrap();
+    let mut events = vec![
+        pair("vouch", 1, "A", "B", vec![5.0, 0.0]),
+        pair("trust", 1, "D", "C", vec![8.0, 0.0]),
+        pair("deal", 1, "C", "B", vec![1.0, 0.0]),
+    ];
+    if revenge {
+        events.push(pair("revenge", 1, "C", "B", vec![0.0, 6.0]));
+    }
+    engine.step(1, events).unwrap();
+    engine
+}
+
+fn s(engine: &EqbslEngine, src: &str, dst: &str) -> f64 {
+    engine.params().rs(engine.state().edge(src, dst).unwrap()).unwrap().1
+}
+
+#[test]
+fn test_pending_dispute_holds_evidence_at_reduced_weight() {
+    let mut engine = engine(true);
+    engine.dispute("revenge", 0.25).unwrap();
+    assert_relative_eq!(s(&engine, "C", "B"), 1.5, epsilon = 1e-12);
+
+    let features = engine.compute_features("B").unwrap();
+    let disputes = features.trust.disputes.clone().unwrap();
+    assert_eq!((disputes.pending, disputes.confirmed, disputes.reversed), (1.0, 0.0, 0.0));
+    assert_relative_eq!(disputes.withheld_mass, 4.5, epsilon = 1e-12);
+    assert_eq!(engine.compute_features("C").unwrap().trust.disputes.unwrap().pending, 0.0);
+
+    // Both the held and the withheld mass decay
+    engine.step(3, vec![]).unwrap();
+    let revenge = &engine.provenance().unwrap().event("revenge")[0].1;
+    assert_relative_eq!(revenge.mass[1], 1.5 * 0.64, epsilon = 1e-12);
+    assert_relative_eq!(revenge.withheld.as_ref().unwrap()[1], 4.5 * 0.64, epsilon = 1e-12);
+    assert_eq!(engine.provenance().unwrap().disputes["revenge"].status, DisputeStatus::Pending);
+
+    // Confirming restores exactly the evidence of an undisputed review
+    engine.confirm_dispute("revenge").unwrap();
+    let mut undisputed = self::engine(true);
+    undisputed.step(3, vec![]).unwrap();
+    assert_relative_eq!(s(&engine, "C", "B"), s(&undisputed, "C", "B"), epsilon = 1e-12);
+    let dispute = &engine.provenance().unwrap().disputes["revenge"];
+    assert_eq!((dispute.status, dispute.opened_at, dispute.resolved_at), (DisputeStatus::Confirmed, 1, Some(3)));
+    assert!(engine.reverse_dispute("revenge").is_err());
+}
+
+#[test]
+fn test_reversal_flows_through_decay_and_propagation() {
+    let mut engine = engine(true).with_derived_cache(true).unwrap();
+    engine.dispute("revenge", 0.5).unwrap();
+    engine.step(2, vec![pair("later", 2, "C", "B", vec![0.0, 1.0])]).unwrap();
+    engine.reverse_dispute("revenge").unwrap();
+    engine.step(4, vec![]).unwrap();
+
+    let mut clean = self::engine(false);
+    clean.step(2, vec![pair("later", 2, "C", "B", vec![0.0, 1.0])]).unwrap();
+    clean.step(4, vec![]).unwrap();
+    assert_relative_eq!(s(&engine, "C", "B"), s(&clean, "C", "B"), epsilon = 1e-12);
+
+    let key = ("D".to_string(), "B".to_string());
+    let (propagated, reference) = (
+        operators::propagate(engine.state(), engine.params()).unwrap(),
+        operators::propagate(clean.state(), clean.params()).unwrap(),
+    );
+    assert_relative_eq!(propagated[&key].1, reference[&key].1, epsilon = 1e-12);
+    assert_relative_eq!(propagated[&key].0, reference[&key].0, epsilon = 1e-12);
+
+    let disputes = engine.compute_features("B").unwrap().trust.disputes.unwrap();
+    assert_eq!((disputes.pending, disputes.reversed, disputes.withheld_mass), (0.0, 1.0, 0.0));
+
+    // Disputes are logged, so replays and late amendments reproduce them
+    engine.verify_replay().unwrap();
+    engine.amend(pair("late", 1, "A", "C", vec![2.0, 0.0])).unwrap();
+    engine.verify_replay().unwrap();
+    assert_relative_eq!(s(&engine, "C", "B"), s(&clean, "C", "B"), epsilon = 1e-12);
+    let json = serde_json::to_string(engine.history().unwrap().log()).unwrap();
+    assert!(json.contains("\"action\":\"reverse\""));
+}
+
+#[test]
+fn test_dispute_errors() {
+    let mut engine = engine(true);
+    assert!(engine.dispute("vouch", 0.5).is_err());
+    assert!(engine.dispute("nope", 0.5).is_err());
+    assert!(engine.dispute("revenge", 1.0).is_err());
+    assert!(engine.confirm_dispute("revenge").is_err());
+    engine.dispute("revenge", 0.0).unwrap();
+    assert!(engine.dispute("revenge", 0.5).is_err());
+    assert_eq!(s(&engine, "C", "B"), 0.0);
+
+    let mut untracked = EqbslEngine::new(params(), EqbslState::new(0)).unwrap();
+    untracked.step(1, vec![pair("revenge", 1, "C", "B", vec![0.0, 6.0])]).unwrap();
+    assert!(untracked.dispute("revenge", 0.5).is_err());
+    assert_eq!(untracked.compute_features("B").unwrap().trust.disputes, None);
+}
+
+#[test]
+fn test_event_ids_address_a_single_contribution() {
+    let mut engine = engine(true);
+    let again = pair("revenge", 2, "C", "B", vec![0.0, 1.0]);
+    let err = engine.step(2, vec![again]).unwrap_err();
+    assert!(err.contains("already been applied"), "{}", err);
+    let err = engine
+        .step(2, vec![pair("late", 2, "A", "C", vec![1.0, 0.0]), pair("late", 2, "D", "B", vec![1.0, 0.0])])
+        .unwrap_err();
+    assert!(err.contains("Duplicate event id 'late'"), "{}", err);
+    assert_eq!(engine.state().t, 1);
+
+    // Provenance pointing at an edge the state lacks is an error, not a panic
+    let mut state = engine.state().clone();
+    state.edges.remove(&("C".to_string(), "B".to_string()));
+    let open = DisputeAction::Open {
+        event_id: "revenge".to_string(),
+        weight: 0.5,
+    };
+    let before = state.clone();
+    assert!(dispute::apply(&mut state, engine.params(), &open).is_err());
+    assert_eq!(state, before);
+}
+
+#[test]
+fn test_rebuild_from_log_replays_disputes() {
+    let mut live = engine(true);
+    live.dispute("revenge", 0.25).unwrap();
+    live.step(3, vec![pair("later", 3, "A", "C", vec![2.0, 0.0])]).unwrap();
+    live.reverse_dispute("revenge").unwrap();
+
+    let history = live.history().unwrap();
+    let log: EventLog = serde_json::from_str(&serde_json::to_string(history.log()).unwrap()).unwrap();
+    let rebuilt = EqbslEngine::from_log(params(), history.genesis().clone(), &log, 2).unwrap();
+    assert_eq!(rebuilt.state().digest(), live.state().digest());
+    assert_eq!(rebuilt.provenance(), live.provenance());
+    assert_eq!(rebuilt.history().unwrap().log(), &log);
+    rebuilt.verify_replay().unwrap();
+
+    // The digest covers the dispute records, so a live state whose record differs from
+    // the replayed one fails verification even though the evidence is the same
+    let mut altered = live.state().clone();
+    let record = altered.provenance.as_mut().unwrap().disputes.get_mut("revenge").unwrap();
+    record.status = DisputeStatus::Confirmed;
+    assert_ne!(altered.digest(), live.state().digest());
+    let genesis = history.genesis();
+    assert!(replay::verify(&params(), genesis, log.entries(), &altered).is_err());
+    replay::verify(&params(), genesis, log.entries(), live.state()).unwrap();
+
+    // Without provenance the disputes cannot be replayed
+    let err = EqbslEngine::from_log(params(), EqbslState::new(0), &log, 2).unwrap_err();
+    assert!(err.contains("provenance"), "{}", err);
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use cathexis::aggregation::Aggregation;
+use cathexis::canonical::Canonical;
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, HyperEvent, StepRecord};
+use cathexis::exact::{self, Rational};
+use cathexis::numeric::{Scalar, Q32};
+use cathexis::operators;
+use cathexis::{EqbslState, Evidence, Params};
+use common::pair;
+use num_bigint::BigInt;
+use std::collections::BTreeMap;
+
+fn ratio(n: i64, d: i64) -> Rational {
+    Rational::new(BigInt::from(n), BigInt::from(d))
+}
+
+fn params() -> Params {
+    Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.7]).unwrap()
+}
+
+fn steps() -> Vec<StepRecord> {
+    let hyper = Event::Hyper(HyperEvent {
+        id: "h1".to_string(),
+        t: 1,
+        hid: "deal".to_string(),
+        nodes: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+        roles: BTreeMap::new(),
+        de: EvidenceTensor::new(vec![0.3, 0.1]),
+    });
+    let record = |t, events| StepRecord {
+        t,
+        events,
+        disputes: Vec::new(),
+    };
+    vec![
+        record(1, vec![pair("e1", 1, "A", "B", vec![0.1, 0.2]), hyper]),
+        record(3, vec![pair("e2", 2, "A", "B", vec![1.7, 0.0])]),
+        record(40, vec![pair("e3", 40, "B", "C", vec![0.3, 3.3])]),
+    ]
+}
+
+#[test]
+fn test_rational_conversion_and_exact_operators() {
+    assert_eq!(Rational::from_f64(0.9), ratio(9, 10));
+    assert_eq!(Rational::from_f64(-1.5e-3), ratio(-3, 2000));
+    assert_eq!(Rational::from_f64(2.5e3), ratio(2500, 1));
+    assert_eq!(Rational::from_f64(f64::NAN), ratio(0, 1));
+    assert_ne!(exact::exact(0.9), ratio(9, 10));
+    assert_eq!(exact::exact(0.75), ratio(3, 4));
+    assert_ne!(ratio(1, 2).to_canonical_bytes(), ratio(2, 1).to_canonical_bytes());
+
+    let params = params();
+    let mut state = EqbslState::<Rational>::new(0);
+    let steps = steps();
+    operators::apply_step(&mut state, &params, steps[0].t, &steps[0].events).unwrap();
+    operators::apply_step(&mut state, &params, steps[1].t, &steps[1].events).unwrap();
+    // (0.1 + 0.3 / 6) 0.9^2 + 1.7 * 0.9 on the positive channel, exactly
+    let ab = state.edge("A", "B").unwrap();
+    assert_eq!(ab.data[[0]], (ratio(1, 10) + ratio(1, 20)) * ratio(81, 100) + ratio(17, 10) * ratio(9, 10));
+    assert_eq!(state.edge("C", "A").unwrap().data[[1]], ratio(1, 60) * ratio(49, 100));
+
+    let (r, s) = params.rs(ab).unwrap();
+    let opinion = params.lift(r.clone(), s.clone());
+    assert_eq!(opinion.b.clone() + opinion.d.clone() + opinion.u.clone(), ratio(1, 1));
+    assert_eq!(opinion.u, ratio(2, 1) / (r.clone() + s.clone() + ratio(2, 1)));
+
+    let fused = Aggregation::Sum
+        .fuse(&[Evidence::new(r.clone(), s.clone(), ratio(2, 1)), Evidence::new(ratio(1, 3), ratio(0, 1), ratio(2, 1))])
+        .unwrap()
+        .unwrap();
+    assert_eq!(fused.r, r + ratio(1, 3));
+}
+
+#[test]
+fn test_deviation_of_f64_and_fixed_runs() {
+    let params = params();
+    let reference = exact::replay_exact(&EqbslState::new(0), &params, &steps()).unwrap();
+    let mut float: EqbslState = EqbslState::new(0);
+    let mut fixed = EqbslState::<Q32>::new(0);
+    for record in steps() {
+        operators::apply_step(&mut float, &params, record.t, &record.events).unwrap();
+        operators::apply_step(&mut fixed, &params, record.t, &record.events).unwrap();
+    }
+
+    let float_report = exact::compare(&float, &reference, &params).unwrap();
+    assert_eq!(float_report.entries, 2 * 6 + 2);
+    assert!(float_report.evidence.value > 0.0 && float_report.evidence.value < 1e-15);
+    assert!(float_report.relative_evidence.value < 1e-14);
+    assert!(float_report.opinion.value < 1e-15 && float_report.expectation.value < 1e-15);
+
+    // Q31.32 resolution is 2^-32, and 37 steps of decay compound its rounding
+    let fixed_report = exact::compare(&fixed, &reference, &params).unwrap();
+    assert!(fixed_report.evidence.value > float_report.evidence.value);
+    assert!(fixed_report.evidence.value < 1e-8);
+    assert!(fixed_report.opinion.value < 1e-8);
+    assert!(fixed_report.evidence.edge.is_some() || fixed_report.evidence.hyperedge.is_some());
+
+    let mut short: EqbslState = EqbslState::new(0);
+    operators::apply_step(&mut short, &params, 40, &[]).unwrap();
+    assert!(exact::compare(&short, &reference, &params).is_err());
+    let mut disputed = steps();
+    disputed[1].disputes.push(cathexis::dispute::DisputeAction::Confirm { event_id: "e1".to_string() });
+    assert!(exact::replay_exact(&EqbslState::new(0), &params, &disputed).is_err());
+}
+
+#[test]
+fn test_reference_reads_state_and_events_as_decimals() {
+    let params = params();
+    let mut initial: EqbslState = EqbslState::new(0);
+    operators::apply_step(&mut initial, &params, 0, &[pair("e0", 0, "A", "B", vec![0.1, 0.7])]).unwrap();
+    assert_eq!(exact::exact_state(&initial).edge("A", "B").unwrap().data[[0]], ratio(1, 10));
+
+    let step = StepRecord {
+        t: 1,
+        events: vec![pair("e1", 1, "A", "B", vec![0.1, 0.3])],
+        disputes: Vec::new(),
+    };
+    let reference = exact::replay_exact(&initial, &params, std::slice::from_ref(&step)).unwrap();
+    // 0.1 * 0.9 + 0.1 and 0.7 * 0.7 + 0.3, with no binary residue from the initial state
+    let ab = reference.edge("A", "B").unwrap();
+    assert_eq!(ab.data[[0]], ratio(19, 100));
+    assert_eq!(ab.data[[1]], ratio(79, 100));
+
+    let mut float = initial.clone();
+    operators::apply_step(&mut float, &params, step.t, &step.events).unwrap();
+    let report = exact::compare(&float, &reference, &params).unwrap();
+    assert!(report.evidence.value > 0.0 && report.evidence.value < 1e-15);
+}
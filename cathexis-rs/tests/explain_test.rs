@@ -0,0 +1,139 @@
+use approx::assert_relative_eq;
+use cathexis::categoriser::MLPCategoriser;
+use cathexis::eqbsl::{EvidenceTensor, TrustGraph};
+use cathexis::events::{Event, PairEvent};
+use cathexis::labeling::DummyLabeler;
+use cathexis::pipeline::CathexisPipeline;
+use cathexis::{EqbslEngine, EqbslState, Params};
+use ndarray::{Array1, Array2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn pair(id: &str, src: &str, dst: &str, r: f64, s: f64) -> Event {
+    Event::Pair(PairEvent {
+        id: id.to_string(),
+        t: 1,
+        src: src.to_string(),
+        dst: dst.to_string(),
+        de: EvidenceTensor::new(vec![r, s]),
+    })
+}
+
+fn engine() -> EqbslEngine {
+    let mut params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]).unwrap();
+    params.propagation_depth = 2;
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    engine
+        .step(
+            1,
+            vec![
+                pair("e1", "A", "D", 1.0, 1.0),
+                pair("e2", "A", "B", 8.0, 0.0),
+                pair("e3", "B", "D", 6.0, 0.0),
+                pair("e4", "A", "C", 2.0, 2.0),
+                pair("e5", "C", "D", 0.0, 5.0),
+                pair("e6", "B", "C", 4.0, 1.0),
+                pair("e7", "D", "A", 3.0, 0.0),
+            ],
+        )
+        .unwrap();
+    engine
+}
+
+#[test]
+fn test_paths_sum_to_propagated_evidence() {
+    let engine = engine();
+    let explanation = engine.explain("A", "D", usize::MAX).unwrap();
+    assert_eq!(explanation.direct, Some((1.0, 1.0)));
+
+    // Direct, A→B→D, A→C→D, A→B→C→D
+    assert_eq!(explanation.total_paths, 4);
+    let r: f64 = explanation.paths.iter().map(|p| p.rs.0).sum();
+    let s: f64 = explanation.paths.iter().map(|p| p.rs.1).sum();
+    assert_relative_eq!(r, explanation.propagated.0, epsilon = 1e-12);
+    assert_relative_eq!(s, explanation.propagated.1, epsilon = 1e-12);
+    assert!(explanation
+        .paths
+        .windows(2)
+        .all(|w| w[0].contribution() >= w[1].contribution()));
+
+    let strongest = &explanation.paths[0];
+    assert_eq!(strongest.nodes, vec!["A", "B", "D"]);
+    assert_eq!(strongest.discounts.len(), 1);
+    assert_relative_eq!(strongest.weight, 0.5 * strongest.discounts[0]);
+    assert!(explanation.paths.iter().any(|p| p.is_direct() && p.rs == (1.0, 1.0)));
+    // D is never a witness for evidence about itself
+    assert!(explanation.paths.iter().all(|p| !p.nodes[1..p.nodes.len() - 1].contains(&"D".to_string())));
+
+    let top = engine.explain("A", "D", 2).unwrap();
+    assert_eq!(top.paths, explanation.paths[..2]);
+    assert_eq!(top.total_paths, 4);
+    assert!(engine.explain("A", "Z", 3).is_err());
+}
+
+/// Complete graph on `n` agents with random evidence on every edge.
+fn complete_engine(n: usize, depth: usize) -> EqbslEngine {
+    let mut params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]).unwrap();
+    params.propagation_depth = depth;
+    params.witness_top_k = n;
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut events = Vec::new();
+    for i in 0..n {
+        for j in (0..n).filter(|&j| j != i) {
+            let id = format!("e{}", events.len());
+            events.push(pair(&id, &format!("a{}", i), &format!("a{}", j), rng.gen_range(0.5..9.0), rng.gen_range(0.0..3.0)));
+        }
+    }
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    engine.step(1, events).unwrap();
+    engine
+}
+
+#[test]
+fn test_top_paths_found_without_enumerating_all() {
+    // 38 candidate witnesses per hop and 6 hops: ~3·10^9 paths, of which only the top are built
+    let engine = complete_engine(40, 6);
+    let explanation = engine.explain("a0", "a1", 5).unwrap();
+    let total: usize = (0..=6).map(|m| 38usize.pow(m)).sum();
+    assert_eq!(explanation.total_paths, total);
+    assert_eq!(explanation.paths.len(), 5);
+    assert!(explanation
+        .paths
+        .windows(2)
+        .all(|w| w[0].contribution() >= w[1].contribution()));
+
+    // On a small graph the top paths match a full ranking
+    let engine = complete_engine(6, 3);
+    let all = engine.explain("a0", "a1", usize::MAX).unwrap();
+    assert_eq!(all.paths.len(), all.total_paths);
+    assert_eq!(all.total_paths, 1 + 4 + 16 + 64);
+    let r: f64 = all.paths.iter().map(|p| p.rs.0).sum();
+    assert_relative_eq!(r, all.propagated.0, epsilon = 1e-9);
+    let mut ranked = all.paths.clone();
+    ranked.sort_by(|a, b| b.contribution().total_cmp(&a.contribution()));
+    assert_eq!(engine.explain("a0", "a1", 10).unwrap().paths, ranked[..10]);
+}
+
+#[test]
+fn test_explanation_alongside_handle_query() {
+    let engine = engine();
+    let first = engine.get_nodes()[0].clone();
+    let input_dim = engine.compute_features(&first).unwrap().to_vector().len();
+    let categoriser = MLPCategoriser::new(
+        Array2::from_elem((3, input_dim), 0.1),
+        Array1::zeros(3),
+        Array2::from_elem((2, 3), 0.2),
+        Array1::zeros(2),
+    );
+    let mut pipeline = CathexisPipeline::new(engine, categoriser, DummyLabeler);
+    pipeline.batch_process().unwrap();
+
+    let plain = pipeline.query_agent_handle("D").unwrap();
+    assert!(plain.explanation.is_none());
+    let response = pipeline.query_agent_handle_for("A", "D", 3).unwrap();
+    assert_eq!(response.category_id, plain.category_id);
+    let explanation = response.explanation.unwrap();
+    assert_eq!((explanation.src.as_str(), explanation.dst.as_str()), ("A", "D"));
+    assert_eq!(explanation.paths.len(), 3);
+    assert_eq!(explanation, pipeline.explain("A", "D", 3).unwrap());
+}
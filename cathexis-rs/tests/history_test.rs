@@ -0,0 +1,114 @@
+mod common;
+
+use cathexis::categoriser::MLPCategoriser;
+use cathexis::eqbsl::TrustGraph;
+use cathexis::invariants::AuditMode;
+use cathexis::labeling::DummyLabeler;
+use cathexis::pipeline::CathexisPipeline;
+use cathexis::replay::{self, EventLog};
+use cathexis::{EqbslEngine, EqbslState, Params};
+use common::pair;
+use ndarray::{Array1, Array2};
+
+fn engine(interval: usize) -> EqbslEngine {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.95]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0))
+        .unwrap()
+        .with_history(interval)
+        .unwrap();
+    engine.step(1, vec![pair("e1", 1, "A", "B", vec![5.0, 0.0])]).unwrap();
+    engine.step(2, vec![pair("e2", 2, "B", "C", vec![3.0, 1.0])]).unwrap();
+    engine.step(4, vec![pair("e3", 4, "A", "B", vec![0.0, 4.0])]).unwrap();
+    engine.step(7, vec![pair("e4", 6, "C", "A", vec![2.0, 0.0])]).unwrap();
+    engine
+}
+
+#[test]
+fn test_state_at_matches_live_state() {
+    let dense = engine(1);
+    let sparse = engine(3);
+
+    // Between steps: state after t=2, decayed to t=3
+    let at3 = sparse.state_at(3).unwrap();
+    assert_eq!(at3.t(), 3);
+    assert_eq!(at3.state(), dense.state_at(3).unwrap().state());
+    assert!(at3.state().edge("C", "A").is_none());
+
+    // The latest time reproduces the live state exactly
+    assert_eq!(sparse.state_at(7).unwrap().state(), sparse.state());
+    assert!(sparse.state_at(-1).is_err());
+
+    let diffs = sparse.state_at(2).unwrap().diff(&sparse.state_at(4).unwrap()).unwrap();
+    let ab = diffs.iter().find(|d| d.src == "A" && d.dst == "B").unwrap();
+    assert!(ab.delta_rs().1 > 3.9);
+    assert!(ab.after_opinion.unwrap().d > ab.before_opinion.unwrap().d);
+}
+
+#[test]
+fn test_snapshot_feeds_pipeline() {
+    let snapshot = engine(2).state_at(5).unwrap();
+    let input_dim = snapshot.compute_features("A").unwrap().to_vector().len();
+    let categoriser = MLPCategoriser::new(
+        Array2::zeros((4, input_dim)),
+        Array1::zeros(4),
+        Array2::zeros((2, 4)),
+        Array1::zeros(2),
+    );
+    let mut pipeline = CathexisPipeline::new(snapshot, categoriser, DummyLabeler);
+    pipeline.batch_process().unwrap();
+    let handle = pipeline.query_agent_handle("B").unwrap();
+    assert_eq!(handle.probabilities.len(), 2);
+}
+
+#[test]
+fn test_replay_from_log_is_bit_identical() {
+    let live = engine(2);
+    let report = live.verify_replay().unwrap();
+    assert_eq!(report.digest, live.state().digest());
+
+    // Round-trip the log through JSON and rebuild from genesis
+    let history = live.history().unwrap();
+    let json = serde_json::to_string(history.log()).unwrap();
+    let log: EventLog = serde_json::from_str(&json).unwrap();
+    let rebuilt = EqbslEngine::from_log(live.params().clone(), history.genesis().clone(), &log, 2).unwrap();
+    assert_eq!(rebuilt.state().digest(), live.state().digest());
+
+    // Replay from the latest checkpoint instead of genesis
+    let (n, checkpoint) = history.latest_checkpoint();
+    let from_snapshot = replay::verify(live.params(), checkpoint, &log.entries()[n..], live.state()).unwrap();
+    assert_eq!(from_snapshot.steps, log.len() - n);
+
+    // Dropping an event changes the digest
+    let mut entries = log.entries().to_vec();
+    entries[1].events.clear();
+    assert!(replay::verify(live.params(), history.genesis(), &entries, live.state()).is_err());
+}
+
+#[test]
+fn test_failed_step_leaves_engine_unchanged() {
+    let mut engine = engine(2)
+        .with_derived_cache(false)
+        .unwrap()
+        .with_auditor(AuditMode::Strict);
+    engine.take_dirty();
+    let (state, log, derived) = (
+        engine.state().clone(),
+        engine.history().unwrap().log().clone(),
+        engine.derived().cloned(),
+    );
+
+    // Both steps succeed on the candidate state; propagating C→B through witness A then
+    // overflows, which the strict auditor rejects after the step
+    let huge = vec![
+        pair("big1", 7, "C", "B", vec![1.7e308, 0.0]),
+        pair("big2", 7, "A", "B", vec![1.7e308, 0.0]),
+    ];
+    assert!(engine.step(7, huge).unwrap_err().contains("propagated"));
+    assert_eq!(engine.state(), &state);
+    assert_eq!(engine.history().unwrap().log(), &log);
+    assert_eq!(engine.derived().cloned(), derived);
+    assert!(engine.dirty().is_empty());
+
+    engine.step(8, vec![pair("e5", 8, "B", "A", vec![1.0, 0.0])]).unwrap();
+    engine.verify_replay().unwrap();
+}
@@ -0,0 +1,95 @@
+use cathexis::categoriser::MLPCategoriser;
+use cathexis::eqbsl::{EvidenceTensor, TrustGraph};
+use cathexis::events::{Event, HyperEvent, PairEvent};
+use cathexis::incremental::DerivedCache;
+use cathexis::labeling::DummyLabeler;
+use cathexis::pipeline::CathexisPipeline;
+use cathexis::{EqbslEngine, EqbslState, Params};
+use ndarray::{Array1, Array2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn random_events(rng: &mut StdRng, t: i64, n: usize, nodes: usize) -> Vec<Event> {
+    (0..n)
+        .filter_map(|x| {
+            let src = rng.gen_range(0..nodes);
+            let dst = rng.gen_range(0..nodes);
+            (src != dst).then(|| {
+                Event::Pair(PairEvent {
+                    id: format!("e{}-{}", t, x),
+                    t,
+                    src: format!("n{}", src),
+                    dst: format!("n{}", dst),
+                    de: EvidenceTensor::new(vec![rng.gen_range(0.0..3.0), rng.gen_range(0.0..1.0), 0.0]),
+                })
+            })
+        })
+        .collect()
+}
+
+fn params() -> Params {
+    let mut params = Params::new(2.0, vec![1.0, 0.0, 0.5], vec![0.0, 1.0, 0.5], vec![0.95, 1.0, 1.0]).unwrap();
+    params.propagation_depth = 2;
+    params.witness_top_k = 3;
+    params
+}
+
+#[test]
+fn test_incremental_cache_matches_full_recomputation() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut engine = EqbslEngine::new(params(), EqbslState::new(0))
+        .unwrap()
+        .with_derived_cache(true)
+        .unwrap();
+    for t in 1..15 {
+        // Every third step is pure decay-free ingestion at the same time
+        let step_t = if t % 3 == 0 { t - 1 } else { t };
+        engine.step(step_t, random_events(&mut rng, step_t, 3, 12)).unwrap();
+    }
+    engine
+        .step(
+            15,
+            vec![Event::Hyper(HyperEvent {
+                id: "h".to_string(),
+                t: 15,
+                hid: "h1".to_string(),
+                nodes: vec!["n1".to_string(), "n2".to_string(), "x".to_string()],
+                roles: Default::default(),
+                de: EvidenceTensor::new(vec![0.0, 0.0, 6.0]),
+            })],
+        )
+        .unwrap();
+    let full = DerivedCache::build(engine.state(), engine.params()).unwrap();
+    assert_eq!(engine.derived().unwrap().propagated, full.propagated);
+}
+
+#[test]
+fn test_incremental_pipeline_refresh() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut params = params();
+    params.decay_beta = vec![1.0, 1.0, 1.0];
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    engine.step(0, random_events(&mut rng, 0, 40, 40)).unwrap();
+    engine.take_dirty();
+
+    let first = engine.get_nodes()[0].clone();
+    let input_dim = engine.compute_features(&first).unwrap().to_vector().len();
+    let w1 = Array2::from_shape_fn((4, input_dim), |(i, j)| ((i * 7 + j * 3) % 5) as f64 - 2.0);
+    let w2 = Array2::from_shape_fn((3, 4), |(i, j)| ((i + 2 * j) % 3) as f64 - 1.0);
+    let categoriser = MLPCategoriser::new(w1, Array1::zeros(4), w2, Array1::zeros(3));
+    let mut pipeline = CathexisPipeline::new(engine, categoriser, DummyLabeler);
+    pipeline.verify_incremental = true;
+    pipeline.batch_process().unwrap();
+
+    for t in 1..6 {
+        let events = random_events(&mut rng, t, 2, 40);
+        pipeline.graph.step(t, events).unwrap();
+        let dirty = pipeline.graph.take_dirty();
+        let agents = dirty.feature_agents(pipeline.graph.state());
+        if dirty.new_nodes.is_empty() {
+            assert!(agents.len() < pipeline.graph.get_nodes().len());
+        }
+        pipeline.refresh(&agents).unwrap();
+    }
+    assert!(pipeline.query_agent_handle(&first).is_ok());
+}
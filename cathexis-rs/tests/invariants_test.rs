@@ -0,0 +1,145 @@
+mod common;
+
+use cathexis::core::Opinion;
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, HyperEvent};
+use cathexis::invariants::{AuditMode, Auditor, Invariant};
+use cathexis::operators::{self, Operator};
+use cathexis::provenance::ProvenanceConfig;
+use cathexis::{EqbslEngine, EqbslState};
+use common::{pair, params};
+use std::collections::BTreeMap;
+
+/// A state whose B→C edge carries negative evidence, as a buggy operator would leave it.
+fn corrupt_state() -> EqbslState {
+    let mut state = EqbslState::new(0);
+    state.edges.insert(("A".to_string(), "B".to_string()), EvidenceTensor::new(vec![3.0, 1.0]));
+    state.edges.insert(("B".to_string(), "C".to_string()), EvidenceTensor::new(vec![2.0, -1.0]));
+    state
+}
+
+#[test]
+fn test_clean_pipeline_passes_strict_audit() {
+    let mut engine = EqbslEngine::new(params(), EqbslState::new(0))
+        .unwrap()
+        .with_provenance(ProvenanceConfig::default())
+        .unwrap()
+        .with_history(2)
+        .unwrap()
+        .with_derived_cache(false)
+        .unwrap()
+        .with_auditor(AuditMode::Strict);
+    for t in 1..=4 {
+        engine
+            .step(
+                t,
+                vec![
+                    pair(&format!("p{}", t), t, "A", "B", vec![2.0, 0.5]),
+                    pair(&format!("q{}", t), t, "B", "C", vec![1.0, 3.0]),
+                    Event::Hyper(HyperEvent {
+                        id: format!("h{}", t),
+                        t,
+                        hid: "h".to_string(),
+                        nodes: vec!["A".to_string(), "C".to_string(), "D".to_string()],
+                        roles: Default::default(),
+                        de: EvidenceTensor::new(vec![3.0, 0.0]),
+                    }),
+                ],
+            )
+            .unwrap();
+    }
+    engine.dispute("q2", 0.5).unwrap();
+    engine.amend(pair("late", 2, "C", "A", vec![0.0, 1.0])).unwrap();
+    let auditor = engine.auditor().unwrap();
+    // Per step: decay, state after decay, ingest, attribute and propagation; then the
+    // dispute and the amendment, each followed by propagation
+    assert_eq!(auditor.checks(), 4 * 5 + 2 + 2);
+    assert!(auditor.violations().is_empty());
+}
+
+#[test]
+fn test_strict_and_monitor_modes() {
+    let mut strict = EqbslEngine::new(params(), corrupt_state())
+        .unwrap()
+        .with_auditor(AuditMode::Strict);
+    let err = strict.step(1, vec![pair("e", 1, "A", "B", vec![1.0, 0.0])]).unwrap_err();
+    assert!(err.contains("NonNegativeEvidence") && err.contains("(B, C)") && err.contains("Decay"), "{}", err);
+    assert_eq!(strict.state().t, 0);
+
+    let mut monitor = EqbslEngine::new(params(), corrupt_state())
+        .unwrap()
+        .with_auditor(AuditMode::Monitor);
+    monitor.step(1, vec![pair("e", 1, "A", "B", vec![1.0, 0.0])]).unwrap();
+    let violations = monitor.take_violations();
+    let operators: Vec<Operator> = violations.iter().map(|v| v.operator).collect();
+    assert_eq!(operators, vec![Operator::Decay, Operator::Ingest, Operator::Attribute]);
+    for v in &violations {
+        assert_eq!(v.invariant, Invariant::NonNegativeEvidence);
+        assert_eq!(v.edge, Some(("B".to_string(), "C".to_string())));
+        assert_eq!(v.t, 1);
+    }
+    assert!(monitor.take_violations().is_empty());
+}
+
+#[test]
+fn test_decay_and_propagation_bounds() {
+    let mut state = EqbslState::new(0);
+    state.edges.insert(("A".to_string(), "B".to_string()), EvidenceTensor::new(vec![3.0, 1.0]));
+    let mut growing = params();
+    growing.decay_beta = vec![1.5, 1.0];
+    let mut auditor = Auditor::new(AuditMode::Monitor);
+    operators::apply_step_audited(&mut state, &growing, 2, &[], &mut auditor).unwrap();
+    let decay: Vec<_> = auditor
+        .violations()
+        .iter()
+        .filter(|v| v.invariant == Invariant::DecayNonIncreasing)
+        .collect();
+    assert_eq!(decay.len(), 2);
+    assert_eq!(decay[1].edge, Some(("A".to_string(), "B".to_string())));
+
+    let params = params();
+    let mut auditor = Auditor::new(AuditMode::Strict);
+    let honest = operators::propagate(&state, &params).unwrap();
+    auditor.check_propagation(&state, &params, &honest).unwrap();
+    let mut inflated = BTreeMap::new();
+    inflated.insert(("A".to_string(), "B".to_string()), (1e6, 0.0));
+    let err = auditor.check_propagation(&state, &params, &inflated).unwrap_err();
+    assert!(err.contains("BoundedPropagation") && err.contains("agent 'A'"), "{}", err);
+
+    assert!(Opinion::new(1.2, -0.2, 0.0, 0.5).is_err());
+    assert!(Opinion::new(0.5, 0.25, 0.25, 0.5).is_ok());
+}
+
+#[test]
+fn test_propagation_is_checked_before_commit() {
+    let engine = |mode| {
+        let mut engine = EqbslEngine::new(params(), EqbslState::new(0))
+            .unwrap()
+            .with_derived_cache(false)
+            .unwrap()
+            .with_auditor(mode);
+        engine.step(1, vec![pair("e1", 1, "C", "A", vec![2.0, 0.0])]).unwrap();
+        engine
+    };
+    // Finite direct evidence, but C→B propagated through witness A overflows
+    let huge = || {
+        vec![
+            pair("big1", 1, "C", "B", vec![1.7e308, 0.0]),
+            pair("big2", 1, "A", "B", vec![1.7e308, 0.0]),
+        ]
+    };
+
+    let mut strict = engine(AuditMode::Strict);
+    let before = strict.state().clone();
+    let err = strict.step(1, huge()).unwrap_err();
+    assert!(err.contains("Propagate") && err.contains("agent 'C'"), "{}", err);
+    assert_eq!(strict.state(), &before);
+    assert!(strict.derived().unwrap().propagated_rs().values().all(|(r, _)| r.is_finite()));
+
+    let mut monitor = engine(AuditMode::Monitor);
+    monitor.step(1, huge()).unwrap();
+    let violations = monitor.take_violations();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].operator, Operator::Propagate);
+    assert_eq!(violations[0].edge, Some(("C".to_string(), "B".to_string())));
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use cathexis::canonical::{Canonical, Digest};
+use cathexis::events::{Event, StepRecord};
+use cathexis::merkle::{SparseMerkleTree, StateCommitment, EMPTY};
+use cathexis::numeric::Q32;
+use cathexis::operators;
+use cathexis::{EqbslEngine, EqbslState};
+use common::{pair, params};
+use std::collections::BTreeMap;
+
+fn events() -> Vec<Event> {
+    (0..40)
+        .map(|n| {
+            let (src, dst) = (format!("a{}", n % 7), format!("a{}", (n * 3 + 1) % 11));
+            pair(&format!("e{}", n), 1, &src, &dst, vec![n as f64, 1.0])
+        })
+        .filter(|e| match e {
+            Event::Pair(p) => p.src != p.dst,
+            Event::Hyper(_) => true,
+        })
+        .collect()
+}
+
+fn engine() -> EqbslEngine {
+    let mut engine = EqbslEngine::new(params(), EqbslState::new(0)).unwrap();
+    engine.step(1, events()).unwrap();
+    engine
+}
+
+#[test]
+fn test_every_edge_proves_against_the_published_root() {
+    let engine = engine();
+    let commitment = engine.commitment();
+    assert!(commitment.is_consistent());
+    assert!(engine.state().edges.len() > 20);
+    for ((src, dst), e) in &engine.state().edges {
+        let proof = engine.prove_edge(src, dst).unwrap();
+        assert!(commitment.verify_edge(&proof, e));
+        // Proofs survive publication as JSON
+        let proof = serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert!(commitment.verify_edge(&proof, e));
+    }
+
+    let ((src, dst), e) = engine.state().edges.iter().next().unwrap();
+    let proof = engine.prove_edge(src, dst).unwrap();
+    let mut tampered = e.clone();
+    tampered.data[[1]] -= 0.5;
+    assert!(!commitment.verify_edge(&proof, &tampered));
+    let mut moved = proof.clone();
+    moved.key = (dst.clone(), src.clone());
+    assert!(!commitment.verify_edge(&moved, e));
+    let mut forged = commitment;
+    forged.edges = Digest::of("other", &1u64);
+    assert!(!forged.is_consistent() && !forged.verify_edge(&proof, e));
+    assert!(engine.prove_edge("a0", "nobody").is_none());
+}
+
+#[test]
+fn test_roots_depend_only_on_content() {
+    let engine = engine();
+    let mut reversed = EqbslEngine::new(params(), EqbslState::new(0)).unwrap();
+    reversed.step(1, events().into_iter().rev().collect()).unwrap();
+    assert_eq!(engine.commitment(), reversed.commitment());
+
+    // A change to one edge, to the parameters or to the time changes the commitment
+    let mut changed = EqbslEngine::new(params(), EqbslState::new(0)).unwrap();
+    changed.step(1, events()).unwrap();
+    changed.step(1, vec![pair("x", 1, "a1", "a2", vec![0.0, 0.1])]).unwrap();
+    assert_ne!(changed.commitment().edges, engine.commitment().edges);
+    assert_eq!(changed.commitment().hyperedges, engine.commitment().hyperedges);
+    let mut lambda = params();
+    lambda.damping_lambda = 0.25;
+    let other = StateCommitment::new(engine.state(), &lambda);
+    assert_eq!(other.edges, engine.commitment().edges);
+    assert_ne!(other.root, engine.commitment().root);
+
+    assert_eq!(SparseMerkleTree::from_edges::<f64>(&BTreeMap::new()).root(), EMPTY);
+    let single: BTreeMap<_, _> = engine.state().edges.iter().take(1).map(|(k, e)| (k.clone(), e.clone())).collect();
+    let tree = SparseMerkleTree::from_edges(&single);
+    let (src, dst) = single.keys().next().unwrap();
+    assert!(tree.prove(src, dst).unwrap().siblings.is_empty());
+
+    // The number type is committed to as well
+    let mut fixed = EqbslState::<Q32>::new(0);
+    operators::apply_step(&mut fixed, &params(), 1, &events()).unwrap();
+    assert_ne!(StateCommitment::new(&fixed, &params()).edges, engine.commitment().edges);
+}
+
+#[test]
+fn test_canonical_events_and_params() {
+    let record = StepRecord {
+        t: 1,
+        events: events(),
+        disputes: Vec::new(),
+    };
+    let mut other = record.clone();
+    if let Event::Pair(e) = &mut other.events[3] {
+        e.de.data[[0]] += 1e-9;
+    }
+    assert_ne!(record.digest(), other.digest());
+    assert_eq!(record.digest(), record.clone().digest());
+    assert_ne!(record.events[0].to_canonical_bytes(), record.events[1].to_canonical_bytes());
+
+    let mut policy = params();
+    policy.aggregation = cathexis::aggregation::Aggregation::TrimmedMean { trim: 0.1 };
+    assert_ne!(policy.digest(), params().digest());
+    assert_eq!(params().digest(), params().digest());
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, HyperEvent};
+use cathexis::numeric::{Scalar, Q32};
+use cathexis::operators;
+use cathexis::{EqbslState, Opinion, Params};
+use common::pair;
+use std::collections::BTreeMap;
+
+fn params() -> Params {
+    Params::new(2.0, vec![1.0, 0.0, 0.5], vec![0.0, 1.0, 0.5], vec![0.97, 0.9, 0.99]).unwrap()
+}
+
+/// Three steps of pair events (one of them late) and a three-party hyperedge.
+fn steps() -> Vec<(i64, Vec<Event>)> {
+    let hyper = Event::Hyper(HyperEvent {
+        id: "h1".to_string(),
+        t: 2,
+        hid: "deal".to_string(),
+        nodes: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+        roles: BTreeMap::new(),
+        de: EvidenceTensor::new(vec![1.0, 0.3, 0.1]),
+    });
+    vec![
+        (1, vec![pair("e1", 1, "A", "B", vec![3.3, 0.1, 0.7]), pair("e2", 1, "B", "C", vec![0.2, 1.9, 0.0])]),
+        (2, vec![pair("e3", 2, "A", "B", vec![0.1, 0.1, 0.1]), hyper, pair("e4", 1, "C", "A", vec![7.0, 0.0, 0.3])]),
+        (9, vec![pair("e5", 5, "A", "C", vec![0.0, 2.5, 1.1])]),
+    ]
+}
+
+fn run<S: Scalar>(reverse: bool) -> EqbslState<S> {
+    let params = params();
+    let mut state = EqbslState::<S>::new(0);
+    for (t, mut events) in steps() {
+        if reverse {
+            events.reverse();
+        }
+        operators::apply_step(&mut state, &params, t, &events).unwrap();
+    }
+    state
+}
+
+#[test]
+fn test_fixed_point_is_order_independent_and_pinned() {
+    let state = run::<Q32>(false);
+    // Fixed-point addition is exact, so the order of a step's events cannot matter
+    assert_eq!(state, run::<Q32>(true));
+    assert_eq!(state.digest(), run::<Q32>(true).digest());
+
+    // Integer arithmetic only: these raw values are the same on every machine
+    let raw: Vec<i64> = state.edge("A", "B").unwrap().data.iter().map(|x| x.raw()).collect();
+    assert_eq!(raw, vec![12033704563, 493024691, 3241249655]);
+    let (r, s) = params().rs(state.edge("A", "C").unwrap()).unwrap();
+    let opinion: Opinion<Q32> = params().lift(r, s);
+    assert_eq!((opinion.b.raw(), opinion.d.raw(), opinion.u.raw()), (591434236, 1940054998, 1763478062));
+    assert_eq!(opinion.b + opinion.d + opinion.u, Q32::from_raw(1 << 32));
+
+    let json = serde_json::to_string(&state).unwrap();
+    let back: EqbslState<Q32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, state);
+}
+
+#[test]
+fn test_fixed_point_tracks_f64() {
+    let (fixed, float) = (run::<Q32>(false), run::<f64>(false));
+    assert_eq!(fixed.edges.keys().collect::<Vec<_>>(), float.edges.keys().collect::<Vec<_>>());
+    for (key, e) in &float.edges {
+        for (x, y) in fixed.edges[key].data.iter().zip(e.data.iter()) {
+            assert!((x.to_f64() - y).abs() < 1e-8, "{:?}: {} vs {}", key, x, y);
+        }
+        let (r, s) = params().rs(e).unwrap();
+        let (rq, sq) = params().rs(&fixed.edges[key]).unwrap();
+        let (op, opq) = (params().lift(r, s), params().lift(rq, sq));
+        assert!((op.expected_probability() - opq.expected_probability().to_f64()).abs() < 1e-8);
+        opq.check(1e-8).unwrap();
+    }
+    assert_ne!(fixed.digest(), float.digest());
+
+    // θ that does not survive conversion to Q32 is rejected before any operator runs
+    let tiny = Params::new(1e-12, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.8]).unwrap();
+    let err = operators::apply_step(&mut EqbslState::<Q32>::new(0), &tiny, 1, &[]).unwrap_err();
+    assert!(err.contains("K = ") && err.contains("Q31.32"), "{}", err);
+    tiny.validate_for::<f64>().unwrap();
+    let mut heavy = params();
+    heavy.w_neg[2] = 1e12;
+    assert!(heavy.validate_for::<Q32>().unwrap_err().contains("w_neg"));
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "parallel")]
+
+use cathexis::categoriser::MLPCategoriser;
+use cathexis::eqbsl::{EvidenceTensor, TrustGraph};
+use cathexis::events::{Event, PairEvent};
+use cathexis::labeling::DummyLabeler;
+use cathexis::operators;
+use cathexis::pipeline::CathexisPipeline;
+use cathexis::sparse;
+use cathexis::{EqbslEngine, EqbslState, Params};
+use ndarray::{Array1, Array2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::ThreadPoolBuilder;
+
+fn engine() -> EqbslEngine {
+    let mut params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 1.0]).unwrap();
+    params.propagation_depth = 2;
+    params.witness_top_k = 4;
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    let mut rng = StdRng::seed_from_u64(5);
+    for t in 1..=3 {
+        let events = (0..150)
+            .filter_map(|x| {
+                let src = rng.gen_range(0..60);
+                let dst = rng.gen_range(0..60);
+                (src != dst).then(|| {
+                    Event::Pair(PairEvent {
+                        id: format!("e{}-{}", t, x),
+                        t,
+                        src: format!("n{}", src),
+                        dst: format!("n{}", dst),
+                        de: EvidenceTensor::new(vec![rng.gen_range(0.0..3.0), rng.gen_range(0.0..1.0)]),
+                    })
+                })
+            })
+            .collect();
+        engine.step(t, events).unwrap();
+    }
+    engine
+}
+
+#[test]
+fn test_results_independent_of_thread_count() {
+    let run = |threads: usize| {
+        let pool = ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        pool.install(|| {
+            let engine = engine();
+            let dense = operators::propagate(engine.state(), engine.params()).unwrap();
+            let sparse = sparse::propagate(engine.state(), engine.params()).unwrap();
+            assert_eq!(dense, sparse);
+
+            let first = engine.get_nodes()[0].clone();
+            let input_dim = engine.compute_features(&first).unwrap().to_vector().len();
+            let w1 = Array2::from_shape_fn((4, input_dim), |(i, j)| ((i * 7 + j * 3) % 5) as f64 - 2.0);
+            let w2 = Array2::from_shape_fn((3, 4), |(i, j)| ((i + 2 * j) % 3) as f64 - 1.0);
+            let categoriser = MLPCategoriser::new(w1, Array1::zeros(4), w2, Array1::zeros(3));
+            let mut pipeline = CathexisPipeline::new(engine, categoriser, DummyLabeler);
+            pipeline.batch_process().unwrap();
+            (dense, pipeline.assignments, pipeline.features)
+        })
+    };
+
+    let serial = run(1);
+    for threads in [2, 4, 8] {
+        assert_eq!(run(threads), serial, "{} threads", threads);
+    }
+}
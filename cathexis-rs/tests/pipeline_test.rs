@@ -23,6 +23,9 @@ impl TrustGraph for MockGraph {
                 embedding: TrustEmbedding::new(vec![0.1, 0.2, 0.3]),
                 reputation_score: 0.8,
                 uncertainty: 0.1,
+                sybil_score: None,
+                whitewash_score: None,
+                disputes: None,
             },
             graph: GraphFeatures {
                 degree: 10.0,
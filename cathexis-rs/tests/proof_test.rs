@@ -0,0 +1,128 @@
+mod common;
+
+use cathexis::proof::{self, ProofCarryingUpdate, Prover, TransparentProver, UpdateWitness, Verifier};
+use cathexis::provenance::ProvenanceConfig;
+use cathexis::{EqbslEngine, EqbslState};
+use common::{pair, params};
+
+fn key(src: &str, dst: &str) -> (String, String) {
+    (src.to_string(), dst.to_string())
+}
+
+fn updates() -> (EqbslEngine, Vec<ProofCarryingUpdate>) {
+    let mut engine = EqbslEngine::new(params(), EqbslState::new(0))
+        .unwrap()
+        .with_provenance(ProvenanceConfig::default())
+        .unwrap();
+    let steps = vec![
+        (1, vec![pair("e1", 1, "A", "B", vec![3.1, 0.2]), pair("e2", 1, "B", "C", vec![0.7, 0.0])]),
+        (2, vec![pair("e3", 2, "A", "B", vec![0.0, 1.3])]),
+        (5, vec![pair("e4", 4, "C", "A", vec![2.0, 0.5])]),
+    ];
+    let updates = steps
+        .into_iter()
+        .map(|(t, events)| engine.step_with_proof(t, events, &TransparentProver, &[key("A", "B")]).unwrap())
+        .collect();
+    (engine, updates)
+}
+
+#[test]
+fn test_proof_carrying_steps_chain_and_verify() {
+    let (engine, updates) = updates();
+    for update in &updates {
+        update.verify(&TransparentProver).unwrap();
+    }
+    for pair in updates.windows(2) {
+        assert_eq!(pair[1].statement.prior, pair[0].statement.outputs.state);
+    }
+    let last = &updates[2].statement;
+    assert_eq!(last.outputs.state, engine.commitment());
+    let (r, s) = engine.params().rs(engine.state().edge("A", "B").unwrap()).unwrap();
+    assert_eq!(last.outputs.opinions, vec![(key("A", "B"), engine.params().lift(r, s))]);
+
+    // Published as JSON, the update still verifies
+    let json = serde_json::to_string(&updates[1]).unwrap();
+    let published: ProofCarryingUpdate = serde_json::from_str(&json).unwrap();
+    published.verify(&TransparentProver).unwrap();
+}
+
+#[test]
+fn test_verifier_rejects_false_statements() {
+    let (_, updates) = updates();
+    let update = &updates[1];
+
+    let mut opinion = update.clone();
+    opinion.statement.outputs.opinions[0].1.b += 1e-12;
+    assert!(opinion.verify(&TransparentProver).unwrap_err().contains("Declared opinion"));
+
+    let mut state = update.clone();
+    state.statement.outputs.state = updates[2].statement.outputs.state;
+    assert!(state.verify(&TransparentProver).is_err());
+
+    let mut params = update.clone();
+    params.statement.params.damping_lambda = 0.25;
+    assert!(params.verify(&TransparentProver).unwrap_err().contains("prior commitment"));
+
+    let mut time = update.clone();
+    time.statement.t = 3;
+    assert!(time.verify(&TransparentProver).is_err());
+
+    // A proof whose witness carries other events
+    let mut witness: UpdateWitness = serde_json::from_slice(&update.proof.data).unwrap();
+    witness.events.push(pair("extra", 2, "C", "B", vec![0.0, 9.0]));
+    let mut events = update.clone();
+    events.proof.data = serde_json::to_vec(&witness).unwrap();
+    assert!(events.verify(&TransparentProver).unwrap_err().contains("events commitment"));
+
+    let mut system = update.clone();
+    system.proof.system = "groth16".to_string();
+    assert!(TransparentProver.verify(&system.statement, &system.proof).is_err());
+
+    // The prover refuses to prove a statement its witness does not satisfy
+    witness.events.pop();
+    assert!(TransparentProver.prove(&opinion.statement, &witness).is_err());
+    assert!(TransparentProver.prove(&update.statement, &witness).is_ok());
+}
+
+#[test]
+fn test_failed_proof_leaves_engine_unchanged() {
+    let (mut engine, _) = updates();
+    let before = engine.commitment();
+    let events = vec![pair("e5", 6, "A", "C", vec![1.0, 0.0])];
+    assert!(engine.step_with_proof(6, events.clone(), &TransparentProver, &[key("X", "Y")]).is_err());
+    assert_eq!(engine.commitment(), before);
+
+    let (next, statement) = proof::state_update(engine.state(), engine.params(), 6, &events, &[]).unwrap();
+    let update = engine.step_with_proof(6, events, &TransparentProver, &[]).unwrap();
+    assert_eq!(update.statement, statement);
+    assert_eq!(next.edges, engine.state().edges);
+}
+
+#[test]
+fn test_verifier_rejects_malformed_witnesses() {
+    let (_, updates) = updates();
+    let update = &updates[1];
+    let witness: UpdateWitness = serde_json::from_slice(&update.proof.data).unwrap();
+    let json = serde_json::to_value(&witness).unwrap();
+    let crafted = |edge: serde_json::Value| {
+        let mut json = json.clone();
+        json["prior"]["edges"][0]["e"] = edge;
+        let mut proof = update.proof.clone();
+        proof.data = serde_json::to_vec(&json).unwrap();
+        TransparentProver.verify(&update.statement, &proof)
+    };
+
+    // A rank-0 tensor has no channel axis
+    let scalar = crafted(serde_json::json!({"data": {"v": 1, "dim": [], "data": [1.0]}}));
+    assert!(scalar.unwrap_err().contains("Malformed"));
+    let channels = crafted(serde_json::json!({"data": {"v": 1, "dim": [3], "data": [1.0, 0.0, 2.0]}}));
+    assert!(channels.unwrap_err().contains("params expect 2 channels"));
+    let negative = crafted(serde_json::json!({"data": {"v": 1, "dim": [2], "data": [1.0, -2.0]}}));
+    assert!(negative.unwrap_err().contains("negative"));
+
+    let mut provenance = witness.clone();
+    provenance.prior.enable_provenance(ProvenanceConfig::default());
+    let mut proof = update.proof.clone();
+    proof.data = serde_json::to_vec(&provenance).unwrap();
+    assert!(TransparentProver.verify(&update.statement, &proof).unwrap_err().contains("provenance"));
+}
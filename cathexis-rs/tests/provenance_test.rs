@@ -0,0 +1,119 @@
+mod common;
+
+use approx::assert_relative_eq;
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, HyperEvent};
+use cathexis::provenance::ProvenanceConfig;
+use cathexis::{EqbslEngine, EqbslState, Params};
+use common::pair;
+
+fn engine(config: ProvenanceConfig) -> EqbslEngine {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.8]).unwrap();
+    EqbslEngine::new(params, EqbslState::new(0))
+        .unwrap()
+        .with_provenance(config)
+        .unwrap()
+        .with_history(2)
+        .unwrap()
+}
+
+/// Tracked plus compacted mass equals the evidence on every edge.
+fn assert_consistent(engine: &EqbslEngine) {
+    let provenance = engine.provenance().unwrap();
+    for (key, e) in &engine.state().edges {
+        let masses = provenance.edges[key].channel_masses();
+        for (a, b) in masses.iter().zip(e.channel_masses().iter()) {
+            assert_relative_eq!(*a, *b, epsilon = 1e-12);
+        }
+    }
+}
+
+#[test]
+fn test_provenance_survives_decay_and_attribution() {
+    let mut engine = engine(ProvenanceConfig::default());
+    engine
+        .step(1, vec![pair("good", 1, "A", "B", vec![4.0, 0.0]), pair("bad", 1, "A", "B", vec![0.0, 3.0])])
+        .unwrap();
+    engine
+        .step(
+            3,
+            vec![Event::Hyper(HyperEvent {
+                id: "deal".to_string(),
+                t: 2,
+                hid: "h1".to_string(),
+                nodes: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+                roles: Default::default(),
+                de: EvidenceTensor::new(vec![6.0, 0.0]),
+            })],
+        )
+        .unwrap();
+    assert_consistent(&engine);
+
+    let provenance = engine.provenance().unwrap();
+    let ab = provenance.edge("A", "B").unwrap();
+    assert_eq!(ab.contributions.len(), 3);
+    // "good" decayed twice on the positive channel
+    assert_relative_eq!(ab.contributions[0].mass[0], 4.0 * 0.81, epsilon = 1e-12);
+    // Hyperedge mass split over 6 ordered pairs, decayed by its one-step lag
+    let deal = &ab.contributions[2];
+    assert_eq!(deal.via.as_deref(), Some("h1"));
+    assert_relative_eq!(deal.mass[0], 6.0 * 0.9 / 6.0, epsilon = 1e-12);
+    assert_eq!(provenance.event("deal").len(), 6);
+
+    // Which events made A→B negative?
+    let negative = ab.top_negative(engine.params(), 5);
+    assert_eq!(negative.len(), 1);
+    assert_eq!(negative[0].event_id, "bad");
+
+    let for_c = provenance.for_agent("C");
+    assert_eq!(for_c.len(), 4);
+    assert!(for_c.values().all(|p| p.contributions.iter().all(|c| c.event_id == "deal")));
+
+    // Provenance is part of the serialised state
+    let json = serde_json::to_string(engine.state()).unwrap();
+    let restored: EqbslState = serde_json::from_str(&json).unwrap();
+    assert_eq!(&restored, engine.state());
+}
+
+#[test]
+fn test_provenance_compaction_and_replay() {
+    let config = ProvenanceConfig {
+        max_events_per_edge: 2,
+        min_mass: 0.5,
+    };
+    let mut engine = engine(config);
+    for t in 1..=4 {
+        engine
+            .step(t, vec![pair(&format!("e{}", t), t, "A", "B", vec![2.0, 0.0])])
+            .unwrap();
+    }
+    engine.step(30, vec![pair("late-big", 30, "A", "B", vec![9.0, 0.0])]).unwrap();
+    assert_consistent(&engine);
+    let ab = engine.provenance().unwrap().edge("A", "B").unwrap();
+    // Everything older has decayed below min_mass
+    assert_eq!(ab.contributions.len(), 1);
+    assert_eq!(ab.contributions[0].event_id, "late-big");
+    assert_eq!(ab.compacted_events, 4);
+
+    engine.step(31, vec![pair("x", 31, "A", "B", vec![3.0, 0.0]), pair("y", 31, "A", "B", vec![1.0, 0.0])]).unwrap();
+    let ab = engine.provenance().unwrap().edge("A", "B").unwrap();
+    assert_eq!(ab.contributions.len(), 2);
+    assert_eq!(ab.contributions[0].event_id, "x");
+    assert_consistent(&engine);
+
+    // A late event is replayed from genesis and keeps its provenance
+    engine.amend(pair("fix", 2, "B", "C", vec![0.0, 5.0])).unwrap();
+    engine.verify_replay().unwrap();
+    let bc = engine.provenance().unwrap().edge("B", "C").unwrap();
+    assert_eq!(bc.contributions.len() + bc.compacted_events, 1);
+    assert_consistent(&engine);
+
+    // Enabling after history would leave genesis untracked
+    let params = engine.params().clone();
+    assert!(EqbslEngine::new(params, EqbslState::new(0))
+        .unwrap()
+        .with_history(2)
+        .unwrap()
+        .with_provenance(ProvenanceConfig::default())
+        .is_err());
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, HyperEvent};
+use cathexis::numeric::Q32;
+use cathexis::operators;
+use cathexis::r1cs::{self, BN254_PRIME};
+use cathexis::{EqbslState, Params};
+use common::pair;
+use num_bigint::BigUint;
+use std::collections::BTreeMap;
+
+fn hyper(id: &str, t: i64, hid: &str, nodes: &[&str], de: Vec<f64>) -> Event {
+    Event::Hyper(HyperEvent {
+        id: id.to_string(),
+        t,
+        hid: hid.to_string(),
+        nodes: nodes.iter().map(|n| n.to_string()).collect(),
+        roles: BTreeMap::new(),
+        de: EvidenceTensor::new(de),
+    })
+}
+
+fn params() -> Params {
+    Params::new(2.0, vec![1.0, 0.5], vec![0.0, 1.0], vec![0.9, 0.8]).unwrap()
+}
+
+fn prior() -> EqbslState<Q32> {
+    let mut state = EqbslState::<Q32>::new(0);
+    let events = vec![
+        pair("e1", 1, "A", "B", vec![3.1, 0.2]),
+        hyper("h1", 1, "deal", &["A", "B", "C"], vec![1.5, 0.3]),
+    ];
+    operators::apply_step(&mut state, &params(), 1, &events).unwrap();
+    state
+}
+
+fn events() -> Vec<Event> {
+    vec![
+        pair("e2", 3, "B", "C", vec![0.7, 0.0]),
+        pair("e3", 2, "A", "B", vec![0.0, 1.3]),
+        hyper("h2", 3, "deal", &[], vec![0.4, 2.0]),
+    ]
+}
+
+#[test]
+fn test_public_outputs_match_the_fixed_point_step() {
+    let prior = prior();
+    let circuit = r1cs::compile_step(&prior, &params(), 3, &events()).unwrap();
+    circuit.check().unwrap();
+
+    let mut next = prior.clone();
+    operators::apply_step(&mut next, &params(), 3, &events()).unwrap();
+    let outputs = circuit.public_outputs();
+    let raw = |x: Q32| BigUint::from(x.raw() as u64);
+    assert_eq!(outputs.len(), next.edges.len() * 5 + next.hyperedges.len() * 2);
+    for ((i, j), e) in &next.edges {
+        for (c, x) in e.data.iter().enumerate() {
+            assert_eq!(outputs[format!("E[{},{}][{}]", i, j, c).as_str()], &raw(*x));
+        }
+        let (r, s) = params().rs(e).unwrap();
+        let opinion = params().lift(r, s);
+        assert_eq!(outputs[format!("b[{},{}]", i, j).as_str()], &raw(opinion.b));
+        assert_eq!(outputs[format!("d[{},{}]", i, j).as_str()], &raw(opinion.d));
+        assert_eq!(outputs[format!("u[{},{}]", i, j).as_str()], &raw(opinion.u));
+    }
+    for (c, x) in next.hyperedges["deal"].e.data.iter().enumerate() {
+        assert_eq!(outputs[format!("H[deal][{}]", c).as_str()], &raw(*x));
+    }
+}
+
+#[test]
+fn test_checker_rejects_tampered_witnesses() {
+    let circuit = r1cs::compile_step(&prior(), &params(), 3, &events()).unwrap();
+    let prime: BigUint = BN254_PRIME.parse().unwrap();
+
+    // Claiming a different opinion
+    let b = circuit.r1cs.labels.iter().position(|l| l == "b[A,B]").unwrap();
+    let mut witness = circuit.witness.clone();
+    witness[b] += 1u32;
+    assert!(circuit.r1cs.check(&witness).unwrap_err().contains("b[A,B]"));
+
+    // Rounding the other way: shifting a quotient and its remainder by one unit
+    let q = circuit.r1cs.labels.iter().position(|l| l.starts_with("decay") && l.ends_with("quotient")).unwrap();
+    let mut witness = circuit.witness.clone();
+    witness[q] += 1u32;
+    witness[q + 1] = (&witness[q + 1] + &prime - (BigUint::from(1u32) << 32)) % &prime;
+    assert!(circuit.r1cs.check(&witness).is_err());
+
+    let mut witness = circuit.witness.clone();
+    witness.pop();
+    assert!(circuit.r1cs.check(&witness).is_err());
+    witness.push(prime);
+    assert!(circuit.r1cs.check(&witness).unwrap_err().contains("not a field element"));
+
+    // Saturating or out-of-range steps cannot be compiled
+    assert!(r1cs::compile_step(&prior(), &params(), 0, &events()).is_err());
+    let huge = vec![pair("big", 3, "A", "B", vec![3e9, 0.0])];
+    assert!(r1cs::compile_step(&prior(), &params(), 3, &huge).is_err());
+}
+
+#[test]
+fn test_exports() {
+    let circuit = r1cs::compile_step(&prior(), &params(), 1, &[pair("e2", 1, "A", "C", vec![1.0, 0.0])]).unwrap();
+    let json = circuit.r1cs.to_json();
+    assert_eq!(json["prime"], BN254_PRIME);
+    assert_eq!(json["nVars"], circuit.witness.len());
+    assert_eq!(json["nOutputs"], circuit.r1cs.n_public);
+    assert_eq!(json["nPrvInputs"], circuit.r1cs.n_private_inputs);
+    assert_eq!(json["nConstraints"], circuit.r1cs.constraints.len());
+    assert_eq!(json["constraints"][0].as_array().unwrap().len(), 3);
+    assert_eq!(circuit.witness_json()[0], "1");
+
+    let text = circuit.r1cs.to_text();
+    assert_eq!(text.lines().count(), circuit.r1cs.constraints.len() + 2);
+    assert!(text.contains("# output b[A,C]"));
+    // Same inputs, same system
+    assert_eq!(circuit, r1cs::compile_step(&prior(), &params(), 1, &[pair("e2", 1, "A", "C", vec![1.0, 0.0])]).unwrap());
+}
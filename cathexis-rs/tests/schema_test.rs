@@ -0,0 +1,52 @@
+use cathexis::events::{Event, PairEvent};
+use cathexis::{ChannelSchema, ChannelSpec, EqbslEngine, EqbslState, Params, Polarity};
+
+fn schema() -> ChannelSchema {
+    ChannelSchema::new(
+        "marketplace",
+        1,
+        vec![
+            ChannelSpec::new("on_time", "Delivered on time", Polarity::Positive).with_half_life(20.0),
+            ChannelSpec::new("chargeback", "Payment reversed", Polarity::Negative).with_weights(0.0, 3.0),
+        ],
+    )
+    .unwrap()
+}
+
+fn pair(id: &str, t: i64, schema: &ChannelSchema, masses: &[(&str, f64)]) -> Event {
+    Event::Pair(PairEvent {
+        id: id.to_string(),
+        t,
+        src: "A".to_string(),
+        dst: "B".to_string(),
+        de: schema.tensor(masses).unwrap(),
+    })
+}
+
+#[test]
+fn test_engine_enforces_its_schema() {
+    let schema = schema();
+    let params = Params::from_schema(&schema, 2.0).unwrap();
+    let mut engine = EqbslEngine::new(params.clone(), EqbslState::new(0))
+        .unwrap()
+        .with_history(1)
+        .unwrap()
+        .with_schema(schema.clone())
+        .unwrap();
+    engine.step(1, vec![pair("e1", 1, &schema, &[("on_time", 2.0), ("chargeback", 1.0)])]).unwrap();
+    assert_eq!(engine.schema(), Some(&schema));
+
+    // Events with a different channel layout are rejected by name, wherever they enter
+    let other = ChannelSchema::new("other", 1, vec![ChannelSpec::new("on_time", "", Polarity::Positive)]).unwrap();
+    let stray = pair("e2", 2, &other, &[("on_time", 1.0)]);
+    assert!(engine.step(2, vec![stray.clone()]).unwrap_err().contains("'e2'"));
+    assert!(engine.amend(stray).unwrap_err().contains("marketplace"));
+    assert_eq!(engine.state().t, 1);
+
+    // θ must agree with the schema
+    let reweighted = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], schema.decay_beta()).unwrap();
+    let engine = EqbslEngine::new(reweighted, EqbslState::new(0)).unwrap();
+    assert!(engine.with_schema(schema.clone()).unwrap_err().contains("weights"));
+    let engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    assert!(engine.with_schema(other).is_err());
+}
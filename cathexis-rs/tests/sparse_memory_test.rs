@@ -0,0 +1,118 @@
+use cathexis::sparse::SparseGraph;
+use cathexis::Params;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// System allocator that tracks live and peak heap bytes.
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+fn grew(bytes: usize) {
+    let live = LIVE.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            grew(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+            grew(new_size);
+        }
+        new
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Peak heap use of `f` above the live bytes when it starts.
+fn peak_during<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let base = LIVE.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    let result = f();
+    (result, PEAK.load(Ordering::Relaxed) - base)
+}
+
+/// Builds and propagates a graph of `n` nodes with out-degree 4 straight from (r, s)
+/// triplets, and checks that peak heap use is linear in the stored entries: the consumed
+/// input, the CSR outputs, one copy of the propagated rows while they are packed, and O(n)
+/// scratch per thread.
+fn check_peak_memory(n: u32) {
+    // The counters are global: measure one graph at a time
+    static SERIAL: Mutex<()> = Mutex::new(());
+    let _guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let (degree, top_k) = (4, 2);
+    let mut params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.95, 1.0]).unwrap();
+    params.propagation_depth = 1;
+    params.witness_top_k = top_k;
+    let mut rng = StdRng::seed_from_u64(3);
+    let nodes: Vec<String> = (0..n).map(|i| format!("n{:07}", i)).collect();
+    let triplets: Vec<(u32, u32, (f64, f64))> = (0..n)
+        .flat_map(|i| {
+            let offsets: Vec<u32> = (1..=degree).map(|d| d * d * 7 + rng.gen_range(0..5)).collect();
+            let rs: Vec<(f64, f64)> = (0..degree).map(|_| (rng.gen_range(0.0..5.0), rng.gen_range(0.0..2.0))).collect();
+            offsets.into_iter().zip(rs).map(move |(o, rs)| (i, (i + o) % n, rs))
+        })
+        .collect();
+    let input = std::mem::size_of_val(triplets.as_slice());
+
+    let ((graph, propagated), peak) = peak_during(|| {
+        let graph = SparseGraph::from_rs(nodes, triplets, &params).unwrap();
+        let propagated = graph.propagate(&params);
+        (graph, propagated)
+    });
+    assert_eq!(graph.direct().nnz(), (n * degree) as usize);
+    let output = graph.memory_bytes() + propagated.memory_bytes();
+    let scratch = rayon_threads() * n as usize * std::mem::size_of::<Vec<(f64, f64)>>();
+    let bound = input + 2 * output + scratch;
+    assert!(
+        peak <= bound,
+        "{} nodes: peak {} bytes exceeds {} (input {}, output {}, scratch {})",
+        n,
+        peak,
+        bound,
+        input,
+        output,
+        scratch
+    );
+}
+
+#[test]
+fn test_propagation_peak_memory() {
+    check_peak_memory(50_000);
+}
+
+#[test]
+#[ignore = "1M nodes: run with `cargo test --release -- --ignored`"]
+fn test_million_node_propagation_peak_memory() {
+    check_peak_memory(1_000_000);
+}
+
+#[cfg(feature = "parallel")]
+fn rayon_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn rayon_threads() -> usize {
+    1
+}
@@ -0,0 +1,188 @@
+use cathexis::eqbsl::EvidenceTensor;
+use cathexis::events::{Event, HyperEvent, PairEvent};
+use cathexis::operators;
+use cathexis::sparse::{self, SparseGraph};
+use cathexis::witness::{
+    CustomPolicy, ExcludeClusters, MinCertainty, SeededSample, WitnessCandidate, WitnessPolicy, WitnessPolicyConfig,
+};
+use cathexis::{EqbslEngine, EqbslState, Params};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+fn params(depth: usize, top_k: usize) -> Params {
+    let mut params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.95, 1.0]).unwrap();
+    params.propagation_depth = depth;
+    params.witness_top_k = top_k;
+    params
+}
+
+#[test]
+fn test_sparse_propagation_matches_dense() {
+    let mut rng = StdRng::seed_from_u64(11);
+    for (depth, top_k) in [(1, 32), (2, 3), (3, 1)] {
+        let mut engine = EqbslEngine::new(params(depth, top_k), EqbslState::new(0)).unwrap();
+        for t in 1..=4 {
+            let mut events: Vec<Event> = (0..60)
+                .filter_map(|x| {
+                    let src = rng.gen_range(0..25);
+                    let dst = rng.gen_range(0..25);
+                    (src != dst).then(|| {
+                        Event::Pair(PairEvent {
+                            id: format!("e{}-{}", t, x),
+                            t,
+                            src: format!("n{}", src),
+                            dst: format!("n{}", dst),
+                            de: EvidenceTensor::new(vec![rng.gen_range(0.0..3.0), rng.gen_range(0.0..1.0)]),
+                        })
+                    })
+                })
+                .collect();
+            events.push(Event::Hyper(HyperEvent {
+                id: format!("h{}", t),
+                t,
+                hid: format!("deal{}", t),
+                nodes: vec!["n1".to_string(), "n2".to_string(), "n3".to_string()],
+                roles: Default::default(),
+                de: EvidenceTensor::new(vec![2.0, 0.5]),
+            }));
+            engine.step(t, events).unwrap();
+        }
+
+        let dense = operators::propagate(engine.state(), engine.params()).unwrap();
+        let sparse = sparse::propagate(engine.state(), engine.params()).unwrap();
+        assert_eq!(dense, sparse, "depth {} top_k {}", depth, top_k);
+    }
+}
+
+/// 200 random pair events over 20 nodes.
+fn random_state() -> EqbslState {
+    let mut rng = StdRng::seed_from_u64(19);
+    let mut engine = EqbslEngine::new(params(2, 4), EqbslState::new(0)).unwrap();
+    let events: Vec<Event> = (0..200)
+        .filter_map(|x| {
+            let src = rng.gen_range(0..20);
+            let dst = rng.gen_range(0..20);
+            (src != dst).then(|| {
+                Event::Pair(PairEvent {
+                    id: format!("e{}", x),
+                    t: 1,
+                    src: format!("n{}", src),
+                    dst: format!("n{}", dst),
+                    de: EvidenceTensor::new(vec![rng.gen_range(0.0..3.0), rng.gen_range(0.0..1.0)]),
+                })
+            })
+        })
+        .collect();
+    engine.step(1, events).unwrap();
+    engine.state().clone()
+}
+
+#[test]
+fn test_witness_policies_in_propagation() {
+    let state = random_state();
+
+    let clusters: BTreeMap<String, String> = (0..20).map(|n| (format!("n{}", n), format!("c{}", n % 3))).collect();
+    let mut policed = params(2, 4);
+    policed.witness_policy = vec![
+        WitnessPolicyConfig::ExcludeClusters(ExcludeClusters { clusters }),
+        WitnessPolicyConfig::MinCertainty(MinCertainty { min_certainty: 0.3 }),
+        WitnessPolicyConfig::SeededSample(SeededSample { seed: 9, size: 3 }),
+    ];
+    policed.validate().unwrap();
+
+    // Policies are part of the serialised parameters
+    let json = serde_json::to_string(&policed).unwrap();
+    let restored: Params = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, policed);
+    let legacy: Params = serde_json::from_str(&serde_json::to_string(&params(2, 4)).unwrap().replace(",\"witness_policy\":[]", "")).unwrap();
+    assert!(legacy.witness_policy.is_empty());
+
+    let dense = operators::propagate(&state, &policed).unwrap();
+    assert_eq!(dense, sparse::propagate(&state, &policed).unwrap());
+    assert_ne!(dense, operators::propagate(&state, &params(2, 4)).unwrap());
+
+    let mut invalid = policed.clone();
+    invalid.witness_policy = vec![WitnessPolicyConfig::MinCertainty(MinCertainty { min_certainty: 1.5 })];
+    assert!(invalid.validate().is_err());
+}
+
+/// Keeps witnesses with an even node number.
+struct EvenWitnesses;
+
+impl WitnessPolicy for EvenWitnesses {
+    fn select(&self, _source: &str, mut candidates: Vec<WitnessCandidate>) -> Vec<WitnessCandidate> {
+        candidates.retain(|c| c.witness[1..].parse::<usize>().unwrap() % 2 == 0);
+        candidates
+    }
+}
+
+#[test]
+fn test_custom_witness_policy_in_propagation() {
+    let state = random_state();
+    let mut custom = params(2, 4);
+    custom.witness_policy = vec![WitnessPolicyConfig::Custom(CustomPolicy::new("even", Arc::new(EvenWitnesses)))];
+    custom.validate().unwrap();
+    let dense = operators::propagate(&state, &custom).unwrap();
+    assert_eq!(dense, sparse::propagate(&state, &custom).unwrap());
+    assert_ne!(dense, operators::propagate(&state, &params(2, 4)).unwrap());
+    assert_ne!(custom.digest(), params(2, 4).digest());
+
+    // Only the name is serialised; the implementation is re-attached by name
+    let json = serde_json::to_string(&custom).unwrap();
+    assert!(json.contains("\"policy\":\"custom\",\"name\":\"even\""));
+    let mut restored: Params = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.digest(), custom.digest());
+    assert!(restored.validate().unwrap_err().contains("not attached"));
+    assert!(restored.attach_witness_policy("odd", Arc::new(EvenWitnesses)).is_err());
+    restored.attach_witness_policy("even", Arc::new(EvenWitnesses)).unwrap();
+    assert_eq!(operators::propagate(&state, &restored).unwrap(), dense);
+}
+
+#[test]
+fn test_sparse_large_graph_bounded_memory() {
+    // 200k nodes, out-degree 4: 800k edges, built straight from (r, s) triplets
+    let n: u32 = 200_000;
+    let degree = 4;
+    let top_k = 2;
+    let params = params(1, top_k);
+    let mut rng = StdRng::seed_from_u64(3);
+    let nodes: Vec<String> = (0..n).map(|i| format!("n{:06}", i)).collect();
+    let triplets: Vec<(u32, u32, (f64, f64))> = (0..n)
+        .flat_map(|i| {
+            let offsets: Vec<u32> = (1..=degree).map(|d| d * d * 7 + rng.gen_range(0..5)).collect();
+            let rs: Vec<(f64, f64)> = (0..degree).map(|_| (rng.gen_range(0.0..5.0), rng.gen_range(0.0..2.0))).collect();
+            offsets.into_iter().zip(rs).map(move |(o, rs)| (i, (i + o) % n, rs))
+        })
+        .collect();
+
+    let graph = SparseGraph::from_rs(nodes, triplets, &params).unwrap();
+    let edges = graph.direct().nnz();
+    assert_eq!(edges, (n * degree) as usize);
+    assert_eq!(graph.discount().nnz(), n as usize * top_k);
+
+    let propagated = graph.propagate(&params);
+    // Each row gains at most top_k * degree indirect entries
+    assert!(propagated.nnz() <= n as usize * (degree as usize + top_k * degree as usize));
+    assert!(propagated.nnz() > edges);
+
+    // Memory is linear in the number of stored entries: index, value and row pointer bytes only
+    let per_entry = std::mem::size_of::<u32>() + std::mem::size_of::<(f64, f64)>();
+    let bound = propagated.nnz() * per_entry + (n as usize + 1) * std::mem::size_of::<usize>();
+    assert!(propagated.memory_bytes() <= bound + bound / 4);
+    assert!(graph.memory_bytes() < 64 * 1024 * 1024);
+
+    // Witness selection keeps the most certain-positive neighbours
+    let (witnesses, deltas) = graph.discount().row(0);
+    let (cols, vals) = graph.direct().row(0);
+    let mut all: Vec<(f64, u32)> = cols
+        .iter()
+        .zip(vals)
+        .map(|(&k, &(r, s))| (params.lift(r, s).expected_probability(), k))
+        .collect();
+    all.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    let expected: Vec<u32> = all.iter().take(top_k).map(|x| x.1).collect();
+    assert_eq!(witnesses, expected.as_slice());
+    assert_eq!(deltas[0], all[0].0);
+}
@@ -0,0 +1,182 @@
+use cathexis::categoriser::MLPCategoriser;
+use cathexis::eqbsl::{EvidenceTensor, TrustGraph};
+use cathexis::events::{Event, PairEvent};
+use cathexis::labeling::DummyLabeler;
+use cathexis::pipeline::CathexisPipeline;
+use cathexis::sybil::{self, SybilConfig};
+use cathexis::{EqbslEngine, EqbslState, Params};
+use ndarray::{Array1, Array2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+
+const HONEST: usize = 40;
+const SYBILS: usize = 15;
+
+fn params() -> Params {
+    Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]).unwrap()
+}
+
+fn event(n: usize, src: &str, dst: &str, r: f64, s: f64) -> Event {
+    Event::Pair(PairEvent {
+        id: format!("e{}", n),
+        t: 1,
+        src: src.to_string(),
+        dst: dst.to_string(),
+        de: EvidenceTensor::new(vec![r, s]),
+    })
+}
+
+/// Honest region: sparse random graph with solid mutual evidence. Sybil region: a near-clique
+/// with heavy mutual praise. Attack edges: a handful of thin, low-certainty links.
+fn attack_fixture(attack_edges: usize) -> Vec<Event> {
+    let mut rng = StdRng::seed_from_u64(23);
+    let mut events = Vec::new();
+    let honest = |i: usize| format!("h{:02}", i);
+    let sybil = |i: usize| format!("s{:02}", i);
+    for i in 0..HONEST {
+        // Ring for connectivity plus random chords
+        let mut peers = vec![(i + 1) % HONEST];
+        peers.extend((0..3).map(|_| rng.gen_range(0..HONEST)).filter(|&j| j != i));
+        for j in peers {
+            let (a, b) = (honest(i), honest(j));
+            events.push(event(events.len(), &a, &b, rng.gen_range(5.0..15.0), rng.gen_range(0.0..1.0)));
+            events.push(event(events.len(), &b, &a, rng.gen_range(5.0..15.0), rng.gen_range(0.0..1.0)));
+        }
+    }
+    for i in 0..SYBILS {
+        for j in 0..SYBILS {
+            if i != j && rng.gen_bool(0.8) {
+                events.push(event(events.len(), &sybil(i), &sybil(j), 40.0, 0.0));
+            }
+        }
+    }
+    for a in 0..attack_edges {
+        let (s, h) = (sybil(a % SYBILS), honest(rng.gen_range(0..HONEST)));
+        events.push(event(events.len(), &h, &s, 0.4, 0.1));
+        events.push(event(events.len(), &s, &h, 30.0, 0.0));
+    }
+    events
+}
+
+fn engine(events: Vec<Event>) -> EqbslEngine {
+    let mut engine = EqbslEngine::new(params(), EqbslState::new(0)).unwrap();
+    engine.step(1, events).unwrap();
+    engine
+}
+
+#[test]
+fn test_sybil_region_ranked_below_honest_region() {
+    let engine = engine(attack_fixture(3));
+    let config = SybilConfig::new(vec!["h00".to_string(), "h17".to_string(), "h31".to_string()]);
+    let report = sybil::analyse(engine.state(), engine.params(), &config).unwrap();
+
+    let score = |prefix: &str| -> Vec<f64> {
+        report
+            .scores
+            .iter()
+            .filter(|(v, _)| v.starts_with(prefix))
+            .map(|(_, &s)| s)
+            .collect()
+    };
+    let (honest, sybils) = (score("h"), score("s"));
+    let worst_honest = honest.iter().copied().fold(0.0, f64::max);
+    let best_sybil = sybils.iter().copied().fold(1.0, f64::min);
+    assert!(best_sybil > worst_honest, "sybil {} vs honest {}", best_sybil, worst_honest);
+
+    // The Sybil region is found as one cluster behind a thin cut
+    let cluster = &report.clusters[0];
+    let members: BTreeSet<&str> = cluster.members.iter().map(String::as_str).collect();
+    assert_eq!(members.iter().filter(|m| m.starts_with('s')).count(), SYBILS);
+    assert!(members.iter().all(|m| m.starts_with('s')));
+    assert_eq!(cluster.attack_edges, 3);
+    assert!(cluster.cut_ratio() < 0.05, "cut ratio {}", cluster.cut_ratio());
+}
+
+#[test]
+fn test_certainty_weighting_matters() {
+    // Many attack edges: without certainty weighting, belief alone leaks trust into the region
+    let engine = engine(attack_fixture(10));
+    let seeds = vec!["h00".to_string(), "h17".to_string(), "h31".to_string()];
+    let weighted = sybil::analyse(engine.state(), engine.params(), &SybilConfig::new(seeds.clone())).unwrap();
+    let mut plain_config = SybilConfig::new(seeds);
+    plain_config.certainty_weighted = false;
+    let plain = sybil::analyse(engine.state(), engine.params(), &plain_config).unwrap();
+    let mean_sybil = |r: &sybil::SybilReport| {
+        let s: Vec<f64> = r.scores.iter().filter(|(v, _)| v.starts_with('s')).map(|(_, &s)| s).collect();
+        s.iter().sum::<f64>() / s.len() as f64
+    };
+    assert!(mean_sybil(&weighted) > mean_sybil(&plain));
+
+    assert!(sybil::analyse(engine.state(), engine.params(), &SybilConfig::new(vec![])).is_err());
+    // Absent seeds carry no trust
+    let with_absent = SybilConfig::new(vec!["h00".to_string(), "h17".to_string(), "h31".to_string(), "nobody".to_string()]);
+    assert_eq!(sybil::analyse(engine.state(), engine.params(), &with_absent).unwrap(), weighted);
+}
+
+#[test]
+fn test_seeds_may_join_later_and_sparse_suspects_are_not_clustered() {
+    let config = SybilConfig::new(vec!["h00".to_string()]);
+    let mut engine = EqbslEngine::new(params(), EqbslState::new(0))
+        .unwrap()
+        .with_sybil_analysis(config)
+        .unwrap();
+    assert!(engine.sybil_report().unwrap().scores.is_empty());
+    engine.step(1, vec![event(0, "a", "b", 5.0, 0.0)]).unwrap();
+    assert_eq!(engine.sybil_report().unwrap().score("a"), Some(1.0));
+
+    // A chain far from the seeds scores low but has no dense internal evidence
+    let mut events = attack_fixture(3);
+    for i in 0..6 {
+        events.push(event(events.len(), &format!("c{}", i), &format!("c{}", i + 1), 20.0, 0.0));
+    }
+    engine.step(2, events).unwrap();
+    let report = engine.sybil_report().unwrap();
+    assert!(report.score("c3").unwrap() > 0.8);
+    assert!(report.clusters.iter().all(|c| c.members.iter().all(|m| !m.starts_with('c'))));
+    let cluster = report.clusters.iter().find(|c| c.members.contains(&"s00".to_string())).unwrap();
+    assert_eq!(cluster.members.len(), SYBILS);
+    assert!(cluster.density > 0.9, "density {}", cluster.density);
+}
+
+#[test]
+fn test_sybil_score_feeds_trust_features() {
+    let engine = engine(attack_fixture(3));
+    let plain_dim = engine.compute_features("s03").unwrap().to_vector().len();
+    assert_eq!(engine.compute_features("s03").unwrap().trust.sybil_score, None);
+
+    let mut engine = engine
+        .with_sybil_analysis(SybilConfig::new(vec!["h00".to_string()]))
+        .unwrap();
+    let features = engine.compute_features("s03").unwrap();
+    assert!(features.trust.sybil_score.unwrap() > 0.8);
+    assert_eq!(features.to_vector().len(), plain_dim + 1);
+
+    // The report follows the state
+    let before = engine.sybil_report().unwrap().clone();
+    engine.step(2, vec![event(9999, "h05", "s03", 50.0, 0.0)]).unwrap();
+    assert_ne!(engine.sybil_report().unwrap(), &before);
+}
+
+#[test]
+fn test_refresh_follows_global_sybil_scores() {
+    let mut engine = engine(attack_fixture(3))
+        .with_sybil_analysis(SybilConfig::new(vec!["h00".to_string()]))
+        .unwrap();
+    engine.take_dirty();
+    let input_dim = engine.compute_features("h00").unwrap().to_vector().len();
+    let w1 = Array2::from_shape_fn((4, input_dim), |(i, j)| ((i * 7 + j * 3) % 5) as f64 - 2.0);
+    let w2 = Array2::from_shape_fn((3, 4), |(i, j)| ((i + 2 * j) % 3) as f64 - 1.0);
+    let categoriser = MLPCategoriser::new(w1, Array1::zeros(4), w2, Array1::zeros(3));
+    let mut pipeline = CathexisPipeline::new(engine, categoriser, DummyLabeler);
+    pipeline.verify_incremental = true;
+    pipeline.batch_process().unwrap();
+
+    // A new endorsement of the Sybil region moves scores far beyond its endpoints
+    pipeline.graph.step(2, vec![event(9999, "h05", "s03", 50.0, 0.0)]).unwrap();
+    let dirty = pipeline.graph.take_dirty();
+    assert!(dirty.agents.iter().any(|a| !dirty.nodes().contains(a) && a.starts_with('s')));
+    let agents = dirty.feature_agents(pipeline.graph.state());
+    assert!(agents.len() < pipeline.graph.get_nodes().len());
+    pipeline.refresh(&agents).unwrap();
+}
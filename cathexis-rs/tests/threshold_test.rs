@@ -0,0 +1,108 @@
+mod common;
+
+use cathexis::attestation::{HandleSigner, KeyRing};
+use cathexis::numeric::{Scalar, Q32};
+use cathexis::threshold::{IssuedCommitment, OpinionOpening, ThresholdAttestation, ThresholdClaim};
+use cathexis::{EqbslEngine, EqbslState, Params};
+use common::pair;
+use ed25519_dalek::SigningKey;
+
+/// The opinion of A about B has E ≈ 0.86 and u ≈ 0.09; the issuer's key is in `keys`.
+fn setup() -> (EqbslEngine, HandleSigner, KeyRing) {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![0.9, 0.8]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0)).unwrap();
+    engine.step(1, vec![pair("e1", 1, "A", "B", vec![18.0, 2.0])]).unwrap();
+    let signer = HandleSigner::new("issuer", SigningKey::from_bytes(&[3; 32]));
+    let mut keys = KeyRing::new();
+    keys.add("issuer", signer.verifying_key(), 0).unwrap();
+    (engine, signer, keys)
+}
+
+#[test]
+fn test_threshold_attestation_round_trip() {
+    let (engine, signer, keys) = setup();
+    let (issued, opening) = engine.issue_commitment("A", "B", &signer, 100).unwrap();
+    assert_eq!(issued.state_root, engine.commitment().root);
+    assert_eq!(issued.commitment, opening.commitment());
+    let claim = ThresholdClaim::new(0.8).with_max_uncertainty(0.1);
+    let attestation = ThresholdAttestation::prove(&opening, issued, claim).unwrap();
+    attestation.verify(&keys).unwrap();
+
+    // Verifiable from its JSON form and the key ring alone
+    let json = serde_json::to_string(&attestation).unwrap();
+    assert!(!json.contains(&opening.expectation.raw().to_string()));
+    let published: ThresholdAttestation = serde_json::from_str(&json).unwrap();
+    published.verify(&keys).unwrap();
+
+    // Fresh blindings hide equal opinions from each other
+    let (again, reopened) = engine.issue_commitment("A", "B", &signer, 100).unwrap();
+    assert_ne!(again.commitment, attestation.issued.commitment);
+    assert_eq!(reopened.expectation, opening.expectation);
+    ThresholdAttestation::prove(&reopened, again, ThresholdClaim::new(0.5)).unwrap().verify(&keys).unwrap();
+    assert!(engine.issue_commitment("B", "A", &signer, 100).is_err());
+}
+
+#[test]
+fn test_false_claims_cannot_be_proven_or_transplanted() {
+    let (engine, signer, keys) = setup();
+    let (issued, opening) = engine.issue_commitment("A", "B", &signer, 100).unwrap();
+    let prove = |claim| ThresholdAttestation::prove(&opening, issued.clone(), claim);
+    assert!(prove(ThresholdClaim::new(0.9)).unwrap_err().contains("expectation"));
+    assert!(prove(ThresholdClaim::new(0.5).with_max_uncertainty(0.05)).unwrap_err().contains("uncertainty"));
+    assert!(prove(ThresholdClaim::new(1.2)).unwrap_err().contains("[0, 1]"));
+
+    let attestation = prove(ThresholdClaim::new(0.8)).unwrap();
+    let mut raised = attestation.clone();
+    raised.claim = ThresholdClaim::new(0.85);
+    assert!(raised.verify(&keys).is_err());
+    let mut subject = attestation.clone();
+    subject.issued.subject = "C".to_string();
+    assert!(subject.verify(&keys).unwrap_err().contains("Invalid signature"));
+
+    // A proof for another commitment does not carry over
+    let (other, _) = engine.issue_commitment("A", "B", &signer, 100).unwrap();
+    let mut moved = attestation.clone();
+    moved.issued = other;
+    assert!(moved.verify(&keys).is_err());
+
+    let mut truncated = attestation;
+    truncated.proof.truncate(100);
+    assert!(truncated.verify(&keys).unwrap_err().contains("Malformed"));
+}
+
+#[test]
+fn test_commitments_must_be_issued_and_in_range() {
+    let (engine, signer, keys) = setup();
+    let (_, opening) = engine.issue_commitment("A", "B", &signer, 100).unwrap();
+    let root = engine.commitment().root;
+
+    // The subject cannot commit to a better opinion of its own
+    let inflated = OpinionOpening {
+        expectation: Q32::from_f64(0.99),
+        ..opening.clone()
+    };
+    let mallory = HandleSigner::new("mallory", SigningKey::from_bytes(&[9; 32]));
+    let forged = IssuedCommitment::issue(&mallory, "A", "B", root, 100, &inflated);
+    let attestation = ThresholdAttestation::prove(&inflated, forged, ThresholdClaim::new(0.95)).unwrap();
+    assert!(attestation.verify(&keys).unwrap_err().contains("Unknown"));
+    let genuine = IssuedCommitment::issue(&signer, "A", "B", root, 100, &opening);
+    assert!(ThresholdAttestation::prove(&inflated, genuine, ThresholdClaim::new(0.95))
+        .unwrap_err()
+        .contains("does not match"));
+
+    // Values outside [0, 1] cannot be proven, even under a valid signature
+    let above = OpinionOpening {
+        expectation: Q32::from_f64(1.5),
+        ..opening.clone()
+    };
+    let issued = IssuedCommitment::issue(&signer, "A", "B", root, 100, &above);
+    assert!(ThresholdAttestation::prove(&above, issued, ThresholdClaim::new(0.9)).is_err());
+    let negative = OpinionOpening {
+        uncertainty: Q32::from_f64(-0.5),
+        ..opening
+    };
+    let issued = IssuedCommitment::issue(&signer, "A", "B", root, 100, &negative);
+    assert!(ThresholdAttestation::prove(&negative, issued, ThresholdClaim::new(0.5).with_max_uncertainty(0.1))
+        .unwrap_err()
+        .contains("uncertainty"));
+}
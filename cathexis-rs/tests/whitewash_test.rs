@@ -0,0 +1,184 @@
+use cathexis::categoriser::MLPCategoriser;
+use cathexis::eqbsl::{EvidenceTensor, TrustGraph};
+use cathexis::events::{Event, HyperEvent, PairEvent};
+use cathexis::labeling::DummyLabeler;
+use cathexis::pipeline::CathexisPipeline;
+use cathexis::whitewash::{self, WhitewashConfig};
+use cathexis::{EqbslEngine, EqbslState, Params};
+use ndarray::{Array1, Array2};
+use std::collections::BTreeMap;
+
+const PARTNERS: [&str; 4] = ["p1", "p2", "p3", "p4"];
+
+fn pair(t: i64, src: &str, dst: &str, r: f64, s: f64) -> Event {
+    Event::Pair(PairEvent {
+        id: format!("{}-{}-{}", t, src, dst),
+        t,
+        src: src.to_string(),
+        dst: dst.to_string(),
+        de: EvidenceTensor::new(vec![r, s]),
+    })
+}
+
+/// "mallory" trades with four partners at hours 2-3, praises them and is rated down hard;
+/// "mallory2" appears a day later at the same hours with three of the same partners.
+/// "nina" is an unrelated newcomer sharing one partner, active at noon, rating harshly.
+/// "olga" has traded with the same partners from the start but was never rated.
+fn engine(config: WhitewashConfig) -> EqbslEngine {
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0))
+        .unwrap()
+        .with_history(4)
+        .unwrap()
+        .with_whitewash_detection(config)
+        .unwrap();
+    for t in [2, 3, 26, 27] {
+        let mut events = Vec::new();
+        for p in PARTNERS {
+            events.push(pair(t, "mallory", p, 3.0, 0.0));
+            events.push(pair(t, p, "mallory", 0.0, 4.0));
+        }
+        events.push(pair(t, "p1", "p2", 2.0, 0.0));
+        events.push(pair(t, "p3", "p4", 2.0, 0.0));
+        if t == 2 {
+            events.extend(PARTNERS[..3].iter().map(|p| pair(t, "olga", p, 3.0, 0.0)));
+        }
+        engine.step(t, events).unwrap();
+    }
+    for t in [50, 51] {
+        let mut events = Vec::new();
+        for p in &PARTNERS[..3] {
+            events.push(pair(t, "mallory2", p, 3.0, 0.0));
+        }
+        events.push(pair(t, "p2", "mallory2", 0.3, 0.0));
+        engine.step(t, events).unwrap();
+    }
+    engine
+        .step(60, vec![pair(60, "nina", "p4", 0.0, 1.0), pair(60, "nina", "q", 0.0, 1.0)])
+        .unwrap();
+    engine
+}
+
+#[test]
+fn test_reset_identity_linked_to_penalised_agent() {
+    let config = WhitewashConfig {
+        min_confidence: 0.0,
+        ..Default::default()
+    };
+    let engine = engine(config.clone());
+    let report = engine.whitewash_report().unwrap();
+
+    let top = &report.links[0];
+    assert_eq!((top.new_agent.as_str(), top.penalised_agent.as_str()), ("mallory2", "mallory"));
+    assert_eq!(top.shared_counterparties, vec!["p1", "p2", "p3"]);
+    assert!((top.counterparty - 0.75).abs() < 1e-12);
+    assert!(top.timing.unwrap() > 0.99);
+    assert!(top.confidence > 0.8, "{:?}", top);
+
+    let nina = report.links_for("nina");
+    assert_eq!(nina.len(), 1);
+    assert!(nina[0].confidence < 0.5, "{:?}", nina[0]);
+    assert_eq!(report.review_queue(0.5).count(), 1);
+
+    // Without activity data the timing term drops out but the link stands
+    let static_report = whitewash::detect(engine.state(), engine.params(), None, &config).unwrap();
+    assert_eq!(static_report.links[0].new_agent, "mallory2");
+    assert_eq!(static_report.links[0].timing, None);
+
+    // olga is as uncertain as a newcomer, but has been around since before mallory left
+    assert!(report.links_for("olga").is_empty());
+    assert!(static_report.links.iter().any(|l| l.new_agent == "olga"));
+    let recent = WhitewashConfig {
+        newcomer_window: Some(60),
+        ..config
+    };
+    let activity = engine.activity().unwrap();
+    let windowed = whitewash::detect(engine.state(), engine.params(), Some(activity), &recent).unwrap();
+    assert!(windowed.links_for("olga").iter().any(|l| l.new_agent == "olga"));
+
+    // Penalised agents and established partners are never reported as new
+    assert!(report.links.iter().all(|l| l.new_agent != "mallory" && !l.new_agent.starts_with('p')));
+}
+
+#[test]
+fn test_whitewash_score_feeds_trust_features() {
+    let engine = engine(WhitewashConfig::default());
+    let suspect = engine.compute_features("mallory2").unwrap();
+    let bystander = engine.compute_features("p1").unwrap();
+    assert!(suspect.trust.whitewash_score.unwrap() > 0.8);
+    assert_eq!(bystander.trust.whitewash_score, Some(0.0));
+    assert_eq!(suspect.to_vector().len(), bystander.to_vector().len());
+
+    let json = serde_json::to_string(engine.whitewash_report().unwrap()).unwrap();
+    assert!(json.contains("\"penalised_agent\":\"mallory\""));
+
+    let bad = WhitewashConfig {
+        weights: [0.0; 3],
+        ..Default::default()
+    };
+    let params = engine.params().clone();
+    assert!(EqbslEngine::new(params, EqbslState::new(0))
+        .unwrap()
+        .with_whitewash_detection(bad)
+        .is_err());
+}
+
+#[test]
+fn test_refresh_follows_global_whitewash_scores() {
+    let mut engine = engine(WhitewashConfig::default());
+    engine.take_dirty();
+
+    // A rejected amendment observes no activity
+    let activity = engine.activity().unwrap().clone();
+    assert!(engine.amend(pair(-1, "mallory3", "p1", 1.0, 0.0)).is_err());
+    assert_eq!(engine.activity().unwrap(), &activity);
+
+    let input_dim = engine.compute_features("p1").unwrap().to_vector().len();
+    let w1 = Array2::from_shape_fn((4, input_dim), |(i, j)| ((i * 7 + j * 3) % 5) as f64 - 2.0);
+    let w2 = Array2::from_shape_fn((3, 4), |(i, j)| ((i + 2 * j) % 3) as f64 - 1.0);
+    let categoriser = MLPCategoriser::new(w1, Array1::zeros(4), w2, Array1::zeros(3));
+    let mut pipeline = CathexisPipeline::new(engine, categoriser, DummyLabeler);
+    pipeline.verify_incremental = true;
+    pipeline.batch_process().unwrap();
+
+    // Rehabilitating mallory clears mallory2's score, two hops from the dirty edge
+    pipeline.graph.step(70, vec![pair(70, "p4", "mallory", 200.0, 0.0)]).unwrap();
+    let dirty = pipeline.graph.take_dirty();
+    assert!(dirty.agents.contains("mallory2") && !dirty.nodes().contains("mallory2"));
+    assert_eq!(pipeline.graph.compute_features("mallory2").unwrap().trust.whitewash_score, Some(0.0));
+    pipeline.refresh(&dirty.feature_agents(pipeline.graph.state())).unwrap();
+}
+
+#[test]
+fn test_activity_follows_members_of_existing_hyperedges() {
+    let config = WhitewashConfig::default();
+    let params = Params::new(2.0, vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]).unwrap();
+    let mut engine = EqbslEngine::new(params, EqbslState::new(0))
+        .unwrap()
+        .with_whitewash_detection(config.clone())
+        .unwrap();
+    let deal = |id: &str, t: i64, nodes: &[&str]| {
+        Event::Hyper(HyperEvent {
+            id: id.to_string(),
+            t,
+            hid: "deal".to_string(),
+            nodes: nodes.iter().map(|n| n.to_string()).collect(),
+            roles: BTreeMap::new(),
+            de: EvidenceTensor::new(vec![1.0, 0.0]),
+        })
+    };
+    engine.step(2, vec![deal("h1", 2, &["a", "b", "c"])]).unwrap();
+    // Later events on the hyperedge omit its members
+    engine.step(5, vec![deal("h2", 5, &[])]).unwrap();
+
+    let activity = engine.activity().unwrap();
+    for agent in ["a", "b", "c"] {
+        let profile = &activity.agents[agent];
+        assert_eq!((profile.first_seen, profile.last_seen, profile.events), (2, 5, 2));
+        assert_eq!(profile.slots[5], 1.0);
+    }
+
+    let mut direct = whitewash::Activity::default();
+    direct.observe(&config, engine.state(), &[deal("h3", 7, &[])]);
+    assert_eq!(direct.agents.len(), 3);
+}